@@ -0,0 +1,146 @@
+// src/agents/agent-definition.ts
+// One agent, as declared by the user: either a caret spec from `--agents`
+// ("id^model[@baseUrl]^kind.protocol[+parser]^prompt") or an entry of the org.yaml team
+// file (see config/team-file). AgentManger creates agents from these.
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { R } from "../runtime/runtime";
+import { hasToolCallParser, toolCallParserNames } from "../drivers/toolcall-parser-registry";
+import type { SamplingParams } from "../drivers/sampling";
+
+export const MODEL_KINDS = ["mock", "lmstudio", "ollama", "anthropic"] as const;
+export const PROTOCOLS = ["openai", "google", "deepseek", "anthropic", "deepseek-notools", "native", "mock"] as const;
+/** Tools an LlmAgent knows how to offer and run. */
+export const AGENT_TOOLS = ["sh"] as const;
+
+export type ModelKind = typeof MODEL_KINDS[number];
+export type Protocol = typeof PROTOCOLS[number];
+
+/** NormativeMemory knobs an agent may override. */
+export type MemorySettings = {
+    contextTokens?: number;
+    highRatio?: number;
+    lowRatio?: number;
+    summaryRatio?: number;
+    keepRecentPerLane?: number;
+    keepRecentTools?: number;
+};
+
+/** AdvancedGuardRail limits an agent may override. */
+export type GuardrailSettings = {
+    missingArgEndTurnLimit?: number;
+    repeatToolSigEndTurnLimit?: number;
+};
+
+/** Per-agent limits; see scheduler/budgets for the session-wide ones. */
+export type AgentBudgets = {
+    /** Tool calls per turn; falls back to the scheduler's --max-tools. */
+    maxTools?: number;
+    /** Turns this agent may take. */
+    maxHops?: number;
+    /** Prompt plus completion tokens. */
+    maxTokens?: number;
+    /** Time spent in this agent's turns. */
+    maxTimeMs?: number;
+    /** Estimated spend, from `pricing`. */
+    maxCostUsd?: number;
+};
+
+/** USD per million tokens, for cost budgets. */
+export type Pricing = {
+    promptPerMTok?: number;
+    completionPerMTok?: number;
+};
+
+export type AgentDefinition = {
+    id: string;
+    /** What the agent does on the team (lead, coder, reviewer, ...); the priority scheduler weights by it. */
+    role?: string;
+    model?: string;
+    kind?: ModelKind;
+    protocol?: Protocol;
+    toolParser?: string;
+    baseUrl?: string;
+    systemPrompt?: string;
+    sampling?: SamplingParams;
+    /** Tool allowlist; undefined means every tool in AGENT_TOOLS. */
+    tools?: string[];
+    memory?: MemorySettings;
+    guardrails?: GuardrailSettings;
+    budgets?: AgentBudgets;
+    pricing?: Pricing;
+    /** Globs of the files this agent writes; with --isolation paths, agents with disjoint globs run at once. */
+    paths?: string[];
+};
+
+export type DriverSpec = Pick<AgentDefinition, "kind" | "protocol" | "toolParser">;
+
+/** "kind.protocol[+parser]"; either side of the dot may be omitted. Throws a message without a prefix. */
+export function parseDriverSpec(raw: string): DriverSpec {
+    const out: DriverSpec = {};
+    const [driverSpec, parserName] = raw.split("+", 2).map(s => s.trim());
+    if (parserName) {
+        if (!hasToolCallParser(parserName)) {
+            throw new Error(`unknown tool-call parser: ${parserName} (known: ${toolCallParserNames().join(", ")})`);
+        }
+        out.toolParser = parserName;
+    }
+    const [k, p] = driverSpec.split(".", 2).map(s => s.trim());
+    if (k) {
+        if (!(MODEL_KINDS as readonly string[]).includes(k)) throw new Error(`unknown driver kind: ${k}`);
+        out.kind = k as ModelKind;
+    }
+    if (p) {
+        if (!(PROTOCOLS as readonly string[]).includes(p)) throw new Error(`unknown protocol: ${p}`);
+        out.protocol = p as Protocol;
+    }
+    return out;
+}
+
+/** Parse the `--agents` shorthand: comma-separated "id ^ model[@baseUrl] ^ [kind.protocol[+parser]] ^ [##prompt-file|inline]". */
+export function parseCaretSpec(spec: string): AgentDefinition[] {
+    const list = String(spec).split(",").map(x => x.trim()).filter(Boolean);
+    const out: AgentDefinition[] = [];
+    for (const seg of list) {
+        const parts = seg.split("^").map(s => s.trim());
+
+        // Enforce: id required; no empty placeholders allowed (no skipping fields).
+        if (!parts[0] || parts.slice(1).some(p => p.length === 0)) {
+            throw new Error(`[agents] invalid spec "${seg}". Do not skip fields; if you use defaults, stop there.`);
+        }
+
+        const def: AgentDefinition = { id: parts[0] };
+        if (parts.length >= 2) {
+            // "model@http://host:port" points this agent at its own server (same form as ORG_LLM_FAILOVER).
+            const m = parts[1].match(/^(.*?)@(https?:\/\/\S+)$/);
+            if (m) def.baseUrl = m[2];
+            if (!m || m[1]) def.model = m ? m[1] : parts[1];
+        }
+        if (parts.length >= 3) {
+            try {
+                Object.assign(def, parseDriverSpec(parts[2]));
+            } catch (e: any) {
+                throw new Error(`[agents] ${e?.message ?? e}`);
+            }
+        }
+        if (parts.length >= 4) {
+            const pr = parts[3];
+            def.systemPrompt = pr.startsWith("##")
+                ? fs.readFileSync(path.resolve(R.cwd(), pr.slice(2)), "utf8")
+                : pr;
+        }
+        out.push(def);
+    }
+    return out;
+}
+
+/**
+ * Give agents without their own `tools` a recipe's allowlist. Recipes also name
+ * commands that run through sh (git, apply_patch); only real tool names count.
+ */
+export function withRecipeTools(defs: AgentDefinition[], allowTools?: readonly string[]): AgentDefinition[] {
+    if (!allowTools) return defs;
+    const tools = (AGENT_TOOLS as readonly string[]).filter(t => allowTools.includes(t));
+    return defs.map(d => (d.tools ? d : { ...d, tools }));
+}
@@ -1,30 +1,57 @@
-import * as fs from "node:fs";
-import * as path from "node:path";
-
 import { makeStreamingOpenAiLmStudio } from "../drivers/streaming-openai-lmstudio";
 import { Agent } from "./agent";
-import { LlmAgent } from "./llm-agent";
+import { LlmAgent, type LlmAgentOptions } from "./llm-agent";
 import { MockModel } from "./mock-model";
+import * as path from "node:path";
 import { R } from "../runtime/runtime";
 import { Logger } from "../logger";
 import { makeStreamingGoogleLmStudio } from "../drivers/streaming-google-lmstudio";
 import { makeStreamingDeepseekOllama } from "../drivers/streaming-deepseek-ollama";
 import { makeStreamingDeepseekNoToolsOllama } from "../drivers/streaming-deepseek-no-toools-ollama";
+import { makeStreamingAnthropic } from "../drivers/streaming-anthropic";
+import { makeStreamingOllamaNative, type OllamaOptions } from "../drivers/streaming-ollama-native";
+import { recordCapabilities, replayDir, replayedCapabilities, withCassette } from "../drivers/cassette-driver";
+import { ScenarioDriver, isScenarioPath, loadScenario } from "../drivers/scenario-driver";
+import { withRetries, type DriverTarget } from "../drivers/retry-driver";
+import { probeModelCapabilities, type ModelCapabilities } from "../drivers/model-capabilities";
+import { redactAuth, requestHeaders, resolveDriverAuth } from "../drivers/auth";
+import type { SamplingParams } from "../drivers/sampling";
+import { AdvancedGuardRail } from "../guardrails/advanced-guardrail";
+import type { GuardRail } from "../guardrails/guardrail";
+import { parseCaretSpec, type AgentDefinition, type ModelKind, type Protocol } from "./agent-definition";
 
-type ModelKind = "mock" | "lmstudio" | "ollama";
 type AgentSpec = { id: string; kind: ModelKind; model: Agent };
-type AgentCreator = (agentId: string, model: string, extra: string, defaults: LlmDefaults) => Promise<AgentSpec>;
-type LlmDefaults = { model: string; baseUrl: string; protocol: "openai" | "google" | "deepseek" | "antrhopic"; apiKey?: string };
-
-const openaiModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults): Promise<AgentSpec> => {
-    Logger.debug("openai", {agentId, model, extra, defaults});
-
-    const driver = makeStreamingOpenAiLmStudio({
-        baseUrl: defaults.baseUrl,
-        model: defaults.model,
-        //apiKey: defaults.apiKey
-    });
-    const agentModel = new LlmAgent(agentId, driver, defaults.model);
+/** What a handler passes on to LlmAgent: probed capabilities plus the definition's guard/memory/tools. */
+type AgentOptions = LlmAgentOptions & { caps: ModelCapabilities; guard?: GuardRail };
+type AgentCreator = (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions) => Promise<AgentSpec>;
+export type LlmDefaults = { model: string; baseUrl: string; protocol: Protocol; apiKey?: string; headers?: Record<string, string>; sampling?: SamplingParams };
+
+/** An explicit `+parser` always wins; otherwise models without native tools get their profiled text parser. */
+const textToolParser = (extra: string, caps: ModelCapabilities): string | undefined =>
+    extra || (caps.nativeTools ? undefined : caps.toolParser);
+
+/** The agent's own endpoint; its credentials are not handed to failover targets on other hosts. */
+const primaryTarget = (defaults: LlmDefaults, model: string): DriverTarget =>
+    ({ baseUrl: defaults.baseUrl, model, auth: { apiKey: defaults.apiKey, headers: defaults.headers } });
+
+const envNumber = (v: string | undefined): number | undefined => {
+    if (v === undefined || v.trim() === "") return undefined;
+    const n = Number(v);
+    return Number.isFinite(n) ? n : undefined;
+};
+
+const openaiModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("openai", {agentId, model, extra, defaults: redactAuth(defaults)});
+
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingOpenAiLmStudio({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        toolParser: textToolParser(extra, opts.caps),
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        sampling: defaults.sampling,
+    }));
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, opts);
     await agentModel.load();
 
     return {
@@ -34,15 +61,18 @@ const openaiModelCreationHandler = async (agentId: string, model: string, extra:
     };
 };
 
-const gemmaModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults): Promise<AgentSpec> => {
-    Logger.debug("gemma", {agentId, model, extra, defaults});
-
-    const driver = makeStreamingGoogleLmStudio({
-        baseUrl: defaults.baseUrl,
-        model: defaults.model,
-        //apiKey: defaults.apiKey
-    });
-    const agentModel = new LlmAgent(agentId, driver, defaults.model);
+const gemmaModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("gemma", {agentId, model, extra, defaults: redactAuth(defaults)});
+
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingGoogleLmStudio({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        toolParser: extra || undefined,
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        sampling: defaults.sampling,
+    }));
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, opts);
     await agentModel.load();
 
     return {
@@ -52,15 +82,40 @@ const gemmaModelCreationHandler = async (agentId: string, model: string, extra:
     };
 };
 
-const deepseekModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults): Promise<AgentSpec> => {
-    Logger.debug("deepseek", {agentId, model, extra, defaults});
+const deepseekModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("deepseek", {agentId, model, extra, defaults: redactAuth(defaults)});
+
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingDeepseekOllama({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        toolParser: extra || undefined,
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        sampling: defaults.sampling,
+    }));
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, opts);
+    await agentModel.load();
+
+    return {
+        id: agentId,
+        kind: "ollama",
+        model: agentModel,
+    };
+};
 
-    const driver = makeStreamingDeepseekOllama({
-        baseUrl: defaults.baseUrl,
-        model: defaults.model,
-        //apiKey: defaults.apiKey
-    });
-    const agentModel = new LlmAgent(agentId, driver, defaults.model);
+const deepseekNoToolsModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("deepseek-notools", {agentId, model, extra, defaults: redactAuth(defaults)});
+
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingDeepseekNoToolsOllama({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        toolParser: extra || undefined,
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        sampling: defaults.sampling,
+    }));
+    // This driver never sends `tools`; tool calls only ever arrive as text.
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, { ...opts, caps: { ...opts.caps, nativeTools: false } });
     await agentModel.load();
 
     return {
@@ -70,15 +125,55 @@ const deepseekModelCreationHandler = async (agentId: string, model: string, extr
     };
 };
 
-const deepseekNoToolsModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults): Promise<AgentSpec> => {
-    Logger.debug("deepseek-notools", {agentId, model, extra, defaults});
+const anthropicModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("anthropic", {agentId, model, extra});
+
+    // baseUrl is the agent's own or ANTHROPIC_BASE_URL (see AgentManger.create), never the shared local server.
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingAnthropic({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        thinkingBudgetTokens: R.env.ANTHROPIC_THINKING_BUDGET ? Number(R.env.ANTHROPIC_THINKING_BUDGET) : undefined,
+        sampling: { maxTokens: envNumber(R.env.ANTHROPIC_MAX_TOKENS), ...defaults.sampling },
+    }), { sharedFailover: false }); // ORG_LLM_FAILOVER lists OpenAI-compatible endpoints
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, opts);
+    await agentModel.load();
+
+    return {
+        id: agentId,
+        kind: "anthropic",
+        model: agentModel,
+    };
+};
 
-    const driver = makeStreamingDeepseekNoToolsOllama({
-        baseUrl: defaults.baseUrl,
-        model: defaults.model,
-        //apiKey: defaults.apiKey
-    });
-    const agentModel = new LlmAgent(agentId, driver, defaults.model);
+const ollamaNativeModelCreationHandler = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    Logger.debug("ollama-native", {agentId, model, extra, defaults: redactAuth(defaults)});
+
+    const options: OllamaOptions = {};
+    const numCtx = opts.memory?.contextTokens ?? envNumber(R.env.ORG_OLLAMA_NUM_CTX);
+    const temperature = envNumber(R.env.ORG_OLLAMA_TEMPERATURE);
+    const seed = envNumber(R.env.ORG_OLLAMA_SEED);
+    if (numCtx !== undefined) options.num_ctx = numCtx;
+    if (temperature !== undefined) options.temperature = temperature;
+    if (seed !== undefined) options.seed = seed;
+
+    const keepAliveRaw = R.env.ORG_OLLAMA_KEEP_ALIVE;
+    const keepAlive = keepAliveRaw ? (envNumber(keepAliveRaw) ?? keepAliveRaw) : undefined;
+
+    const driver = withRetries(agentId, primaryTarget(defaults, model), (t) => makeStreamingOllamaNative({
+        baseUrl: t.baseUrl,
+        model: t.model,
+        options,
+        keepAlive,
+        toolParser: textToolParser(extra, opts.caps),
+        apiKey: t.auth?.apiKey,
+        headers: t.auth?.headers,
+        sampling: defaults.sampling,
+    }));
+    // An explicit num_ctx is the window the server will actually use.
+    const caps = numCtx !== undefined ? { ...opts.caps, contextTokens: numCtx } : opts.caps;
+    const agentModel = new LlmAgent(agentId, withCassette(agentId, driver), model, opts.guard, { ...opts, caps });
     await agentModel.load();
 
     return {
@@ -88,9 +183,13 @@ const deepseekNoToolsModelCreationHandler = async (agentId: string, model: strin
     };
 };
 
-
-const mockModelCreationHanlder = async (agentId: string, model: string, extra: string, defaults: LlmDefaults): Promise<AgentSpec> => {
-    const agentModel = new MockModel(agentId);
+const mockModelCreationHanlder = async (agentId: string, model: string, extra: string, defaults: LlmDefaults, opts: AgentOptions): Promise<AgentSpec> => {
+    // A scenario file as the model gets a real LlmAgent driven by the script (see drivers/scenario-driver).
+    // Memory is not loaded so every run starts from the same state.
+    const agentModel = isScenarioPath(model)
+        ? new LlmAgent(agentId, new ScenarioDriver(loadScenario(path.resolve(R.cwd(), model)), agentId), model, opts.guard,
+            { ...opts, caps: { ...opts.caps, nativeTools: true } })
+        : new MockModel(agentId);
     return {
         id: agentId,
         kind: "mock",
@@ -110,70 +209,36 @@ export class AgentManger {
         ['ollama.deepseek']: deepseekModelCreationHandler,
         ['lmstudio.deepseek-notools']: deepseekNoToolsModelCreationHandler,
         ['ollama.deepseek-notools']: deepseekNoToolsModelCreationHandler,
+        ['ollama.native']: ollamaNativeModelCreationHandler,
+        ['anthropic.anthropic']: anthropicModelCreationHandler,
         ['mock.mock']: mockModelCreationHanlder,
     };
 
+    /** Create agents from the `--agents` shorthand ("id^model^kind.protocol[+parser]^prompt", comma-separated). */
     async parse(
         spec: string,
         llmDefaults: LlmDefaults,
         recipeSystemPrompt?: string | null
     ): Promise<AgentSpec[]> {
-        const list = String(spec).split(",").map(x => x.trim()).filter(Boolean);
-        const out: AgentSpec[] = [];
-        for (const item of list) {
-
-            // item is a single agent segment
-            // ORDER: "id ^ model ^ [driverKind.protocol] ^ [##system-prompt.txt|inline]"
-            const seg = item.trim();
-            const partsRaw = seg.split("^");
-            const parts = partsRaw.map(s => s.trim());
-
-            // Enforce: id required; no empty placeholders allowed (no skipping fields).
-            if (!parts[0] || parts.slice(1).some(p => p.length === 0)) {
-                throw new Error(`[agents] invalid spec "${seg}". Do not skip fields; if you use defaults, stop there.`);
-            }
-
-            const id = parts[0];
-
-            // -------------------- MODEL (now field #2) --------------------
-            let model = llmDefaults.model;
-            if (parts.length >= 2) {
-                model = parts[1];
-            }
-
-            // -------------------- KIND.PROTOCOL (now field #3) --------------------
-            type Protocol = LlmDefaults["protocol"];
-            const PROTOCOLS = ["openai", "google", "deepseek", "anthropic", "deepseek-notools"] as const;
-            const isProtocol = (p: string): p is Protocol =>
-                (PROTOCOLS as readonly string[]).includes(p);
-
-            type ModelKindStrict = "mock" | "lmstudio";
-            const isModelKind = (k: string): k is ModelKindStrict => k === "mock" || k === "lmstudio" || k === "ollama";
-
-            let kind: ModelKindStrict = "lmstudio";
-            let protocol: Protocol = llmDefaults.protocol;
-
-            if (parts.length >= 3) {
-                const [k, p] = parts[2].split(".", 2).map(s => s.trim());
-                if (k) {
-                    if (!isModelKind(k)) throw new Error(`[agents] unknown driver kind: ${k}`);
-                    // @ts-expect-error keep existing surface (ollama allowed at runtime)
-                    kind = k;
-                }
-                if (p) {
-                    if (!isProtocol(p)) throw new Error(`[agents] unknown protocol: ${p}`);
-                    protocol = p;
-                }
-            }
+        return this.create(parseCaretSpec(spec), llmDefaults, recipeSystemPrompt);
+    }
 
-            // -------------------- SYSTEM PROMPT (still field #4) --------------------
-            let systemPrompt: string | undefined;
-            if (parts.length >= 4) {
-                const pr = parts[3];
-                systemPrompt = pr.startsWith("##")
-                    ? fs.readFileSync(path.resolve(R.cwd(), pr.slice(2)), "utf8")
-                    : pr;
-            }
+    /**
+     * Create agents from definitions (caret specs or org.yaml entries, see config/team-file).
+     * `templates` names what they may spawn at runtime (see agents/agent-spawner).
+     */
+    async create(
+        definitions: AgentDefinition[],
+        llmDefaults: LlmDefaults,
+        recipeSystemPrompt?: string | null,
+        templates: string[] = [],
+    ): Promise<AgentSpec[]> {
+        const out: AgentSpec[] = [];
+        for (const def of definitions) {
+            const id = def.id;
+            const model = def.model ?? llmDefaults.model;
+            const kind: ModelKind = def.kind ?? "lmstudio";
+            const protocol: Protocol = def.protocol ?? (kind === "mock" ? "mock" : llmDefaults.protocol);
 
             // Create the agent using the resolved handler
             const handlerKey = `${kind}.${protocol}`;
@@ -182,18 +247,47 @@ export class AgentManger {
                 throw new Error(`[agents] no creation handler for "${handlerKey}"`);
             }
 
-            const agentSpec = await creationHandler(id, model, "", llmDefaults);
-
-            // If the model supports a system prompt, set it
-            function hasSystemPromptSetter(x: unknown): x is { setSystemPrompt: (s: string) => void } {
-                return typeof x === "object" && x !== null
-                    && "setSystemPrompt" in x
-                    && typeof (x as { setSystemPrompt?: unknown }).setSystemPrompt === "function";
-            }
-
-            if (systemPrompt && hasSystemPromptSetter(agentSpec.model)) {
-                agentSpec.model.setSystemPrompt(systemPrompt);
-            }
+            // Per-agent credentials (env or key file); Anthropic keeps its own shared key.
+            const auth = protocol === "anthropic"
+                ? resolveDriverAuth(id, "ANTHROPIC_API_KEY")
+                : resolveDriverAuth(id, "ORG_LLM_API_KEY", llmDefaults.apiKey);
+            // The shared base URL points at a local OpenAI-compatible server; Anthropic has its own.
+            const sharedBaseUrl = protocol === "anthropic"
+                ? (R.env.ANTHROPIC_BASE_URL || "https://api.anthropic.com")
+                : llmDefaults.baseUrl;
+            const agentDefaults: LlmDefaults = {
+                ...llmDefaults,
+                ...auth,
+                model,
+                baseUrl: def.baseUrl ?? sharedBaseUrl,
+                sampling: { ...llmDefaults.sampling, ...def.sampling },
+            };
+
+            // Context window / native tools / reasoning, probed from the server with a profile fallback.
+            // A replay takes what the recorded run probed (else the profile) and stays off the network.
+            const replaying = replayDir() !== undefined;
+            const caps = (replaying ? replayedCapabilities(id) : undefined) ?? await probeModelCapabilities({
+                baseUrl: agentDefaults.baseUrl,
+                model,
+                headers: requestHeaders(auth),
+                ollama: kind === "ollama",
+                offline: kind === "mock" || protocol === "anthropic" || replaying,
+            });
+            if (!replaying) recordCapabilities(id, caps);
+
+            const guard = def.guardrails ? new AdvancedGuardRail({ agentId: id, ...def.guardrails }) : undefined;
+            const agentSpec = await creationHandler(id, model, def.toolParser ?? "", agentDefaults, {
+                caps,
+                guard,
+                memory: def.memory,
+                tools: def.tools,
+                systemPrompt: def.systemPrompt,
+                templates,
+            });
+            if (def.budgets) agentSpec.model.budgets = { ...def.budgets };
+            if (def.pricing) agentSpec.model.pricing = { ...def.pricing };
+            if (def.role) agentSpec.model.role = def.role;
+            if (def.paths) agentSpec.model.paths = [...def.paths];
 
             const agentModel = agentSpec.model;
 
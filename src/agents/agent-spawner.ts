@@ -0,0 +1,66 @@
+// src/agents/agent-spawner.ts
+// Creates agents at runtime from named templates: the `templates:` of org.yaml,
+// or any agent the team declares (spawning "coder" gives a fresh "coder-1" with
+// the same model, prompt, tools and budgets). The scheduler decides when.
+
+import type { Agent } from "./agent";
+import type { AgentDefinition } from "./agent-definition";
+import type { AgentManger, LlmDefaults } from "./agent-manager";
+import { MemoryPersisitence } from "../memory/memory-persistence";
+
+export class AgentSpawner {
+    private readonly templates = new Map<string, AgentDefinition>();
+
+    constructor(
+        private readonly manager: AgentManger,
+        templates: AgentDefinition[],
+        private readonly llmDefaults: LlmDefaults,
+        private readonly recipeSystemPrompt?: string | null,
+    ) {
+        // Explicit templates first; team agents only fill in names not taken.
+        for (const t of templates) {
+            const key = t.id.toLowerCase();
+            if (!this.templates.has(key)) this.templates.set(key, t);
+        }
+    }
+
+    templateNames(): string[] {
+        return [...this.templates.values()].map(t => t.id);
+    }
+
+    /** Next free "<template>-<n>" among `takenIds` (case-insensitive). */
+    nextId(template: string, takenIds: string[]): string {
+        const taken = new Set(takenIds.map(id => id.toLowerCase()));
+        let n = 1;
+        while (taken.has(`${template}-${n}`.toLowerCase())) n++;
+        return `${template}-${n}`;
+    }
+
+    /** Create a fresh agent from a template. Throws a message fit to show the user or the model. */
+    async spawn(template: string, takenIds: string[]): Promise<Agent> {
+        const def = this.template(template);
+        const id = this.nextId(def.id, takenIds);
+        // Workers start clean, even if an earlier run left memory under the same id.
+        await new MemoryPersisitence().remove(id);
+        return this.build(def, id);
+    }
+
+    /** Recreate a worker under its old id (`org --resume`), keeping its memory. */
+    async respawn(template: string, id: string): Promise<Agent> {
+        return this.build(this.template(template), id);
+    }
+
+    private template(name: string): AgentDefinition {
+        const def = this.templates.get(name.toLowerCase());
+        if (!def) {
+            const known = this.templateNames();
+            throw new Error(`unknown template "${name}" (known: ${known.length ? known.join(", ") : "none"})`);
+        }
+        return def;
+    }
+
+    private async build(def: AgentDefinition, id: string): Promise<Agent> {
+        const [spec] = await this.manager.create([{ ...def, id }], this.llmDefaults, this.recipeSystemPrompt);
+        return spec.model;
+    }
+}
@@ -2,19 +2,26 @@ import { AdvancedGuardRail } from "../guardrails/advanced-guardrail";
 import { GuardDecision, GuardRail, GuardRouteKind } from "../guardrails/guardrail";
 import { NoiseFilters } from "../scheduler/filters";
 import { ChatMessage } from "../types";
+import type { AgentBudgets, Pricing } from "./agent-definition";
+import type { ToolObserver } from "../executors/tool-executor";
 
 export interface AgentReply {
   message: string;   // assistant text
   reasoning?: string;
   toolsUsed: number; // number of tool calls consumed this hop
+  interrupted?: boolean; // generation was cancelled by the user; `message` is partial
 }
 
 export type AgentCallbacks = {
   shouldAbort: () => boolean,
-  onStreamStart: () => void;
-  onStreamEnd: () => void | Promise<void>;
+  /** May return a signal that cancels the model stream (ESC / interject hotkeys). */
+  onStreamStart: () => AbortSignal | void | Promise<AbortSignal | void>;
+  /** Gets back the signal onStreamStart returned, if any. */
+  onStreamEnd: (signal?: AbortSignal) => void | Promise<void>;
   onRoute: (message: string, filters: NoiseFilters) => Promise<boolean>;
   onRouteCompleted: (message: string, numToolsUsed: number, yieldToUser: boolean) => Promise<boolean>;
+  /** Told about each tool call the agent runs. */
+  onTool?: ToolObserver;
 };
 
 
@@ -22,6 +29,15 @@ export abstract class Agent {
   // Guard rails (loop / quality signals), per-agent, pluggable.
   protected readonly guard: GuardRail;
   public readonly id: string;
+  /** Team role from org.yaml, if any. */
+  public role?: string;
+  /** Globs of the files this agent writes (org.yaml `paths`). */
+  public paths?: string[];
+  /** Where its tools run and ##file blocks land; set by the scheduler under --concurrency, else the current directory. */
+  public workDir?: string;
+  // Per-agent limits from the team file; unset fields fall back to the scheduler's.
+  public budgets: AgentBudgets = {};
+  public pricing: Pricing = {};
 
   constructor(id: string, guard?: GuardRail) {
     this.id = id;
@@ -47,6 +63,11 @@ export abstract class Agent {
     return typeof anyGuard.onIdle === 'function' ? anyGuard.onIdle(state) : null;
   }
 
+  /** Tokens used so far; agents that don't call a model use none. */
+  getUsage(): { promptTokens: number; completionTokens: number } {
+    return { promptTokens: 0, completionTokens: 0 };
+  }
+
   abstract load(): Promise<void>;
   abstract save(): Promise<void>;
 }
@@ -1,18 +1,25 @@
 import { C, Logger } from "../logger";
 import { R } from "../runtime/runtime";
-import type { ChatDriver, ChatMessage, ChatToolCall } from "../drivers/types";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall } from "../drivers/types";
 import { AgentMemory } from "../memory";
 import { GuardRail } from "../guardrails/guardrail";
 import { Agent, AgentCallbacks, AgentReply } from "./agent";
 import { sanitizeContent } from "../utils/sanitize-content";
 import { sanitizeAndRepairAssistantReply } from "../guard/sanitizer";
-import { ToolExecutor } from "../executors/tool-executor";
+import { ToolExecutor, type ToolObserver } from "../executors/tool-executor";
 import { StandardToolExecutor } from "../executors/standard-tool-executor";
 import { createPDAStreamFilterHeuristic } from "../utils/filter-passes/llm-pda-stream-heuristic";
 import { SH_TOOL_DEF } from "../tools/sh";
-import { ChatResponse } from "../scheduler/types";
 import { NoiseFilters } from "../scheduler/filters";
 import { NormativeMemory } from "../memory/normative-memory";
+import { usageOrEstimate } from "../drivers/usage";
+import { RunMetrics } from "../metrics/runtime-metrics";
+import { currentRunId } from "../runtime/run-dir";
+import { DEFAULT_CAPABILITIES, type ModelCapabilities } from "../drivers/model-capabilities";
+import type { MemorySettings } from "./agent-definition";
+
+/** Appended to a reply the user cut off, so the model knows it never finished. */
+export const INTERRUPTED_MARK = "[interrupted by user]";
 
 enum ResponState {
   IDLE = 'IDLE',
@@ -20,15 +27,32 @@ enum ResponState {
   FINAL_RESPONSE = 'FINAL_RESPONSE'
 }
 
-function buildSystemPrompt(id: string): [string, string] {
+/** Running token/latency totals for one agent (see RunMetrics "llm" events for per-call detail). */
+export type AgentUsage = {
+  calls: number;
+  promptTokens: number;
+  completionTokens: number;
+  estimatedCalls: number;   // calls where the server did not report usage
+  ttftMsTotal: number;
+  ttftSamples: number;
+  totalMs: number;
+};
+
+function buildSystemPrompt(id: string, tools: readonly string[], templates: readonly string[] = []): [string, string] {
+  const toolLines = tools.includes("sh")
+    ? [
+      "- sh(cmd): run a POSIX command. Args: {cmd:string}. Returns {ok, stdout, stderr, exit_code, cmd}.",
+      "  • Use for builds/tests/git/etc. Check exit_code and stderr. Never invent outputs.",
+      "  • To look at an image (screenshot, chart, PNG diff), print a line `##image:path/to/file.png` from sh; the image is attached to the result.",
+    ]
+    : ["- You have no tools. Do not emit tool calls; if something must be run, ask a peer (@@<agent>) or the user."];
   return [[
     `You are agent "${id}".`,
     "- DO NOT LIE",
     "- Do not pretend or hallucinate tool call results. Do not misrepresent the facts.",
     "",
     "TOOLS",
-    "- sh(cmd): run a POSIX command. Args: {cmd:string}. Returns {ok, stdout, stderr, exit_code, cmd}.",
-    "  • Use for builds/tests/git/etc. Check exit_code and stderr. Never invent outputs.",
+    ...toolLines,
     "",
     "FILES",
     "- Prefer tag-based writes for full files (no code fences):",
@@ -40,8 +64,13 @@ function buildSystemPrompt(id: string): [string, string] {
     "MESSAGING",
     "- @@user to talk to the human.",
     "- All messages intended for the user must be prefixed with @@user. No other tags are permitted for direct user communication. The user does not see group chat.",
-    "- @@<agent> to DM a peer.",
+    "- @@<agent> to DM a peer; @@alice,bob to DM several; @@<role>s (e.g. @@reviewers) for every peer with that role.",
+    "- Add cc:<agent> to a list (@@alice,cc:bob) for peers who should see it without replying. A message marked [cc] needs no reply from you.",
     "-  @@group to address everyone.",
+    ...(templates.length ? [
+      `- ##spawn:<template> <task> on its own line starts a short-lived worker (templates: ${templates.join(", ")}).`,
+      "  The worker reports back to you with a DM and is then retired; fan out independent subtasks this way.",
+    ] : []),
     "- **Only insert a tag when a reply from that participant is required.** If I can keep working on the task without waiting for input, I should proceed silently.",
     "",
     "POLICY",
@@ -77,6 +106,19 @@ function buildSystemPrompt(id: string): [string, string] {
   ]
 }
 
+export type LlmAgentOptions = {
+  /** What the model can do (see drivers/model-capabilities); sizes memory and gates native tools. */
+  caps?: ModelCapabilities;
+  /** Overrides for the memory defaults below. */
+  memory?: MemorySettings;
+  /** Tool allowlist by name; undefined offers every tool. */
+  tools?: string[];
+  /** Replaces the soft default prompt; the base header (tools, messaging, policy) always stays. */
+  systemPrompt?: string;
+  /** Templates this agent may `##spawn:` workers from (see scheduler/workers). */
+  templates?: string[];
+};
+
 /**
  * LlmAgent
  * - Keeps conversation state via pluggable memory (SummaryMemory with hysteresis).
@@ -87,7 +129,9 @@ function buildSystemPrompt(id: string): [string, string] {
 export class LlmAgent extends Agent {
   private readonly driver: ChatDriver;
   private readonly model: string;
-  private readonly tools = [SH_TOOL_DEF];
+  private readonly tools: typeof SH_TOOL_DEF[];
+  // False when the model only understands tool calls written into its text (parsed by the driver).
+  private readonly nativeTools: boolean;
 
   // Memory replaces the old raw history array.
   private readonly memory: AgentMemory;
@@ -101,14 +145,22 @@ export class LlmAgent extends Agent {
   private readonly toolExecutor: ToolExecutor;
   private streamFilter = createPDAStreamFilterHeuristic();
 
-  constructor(id: string, driver: ChatDriver, model: string, guard?: GuardRail) {
+  private readonly usage: AgentUsage = {
+    calls: 0, promptTokens: 0, completionTokens: 0, estimatedCalls: 0, ttftMsTotal: 0, ttftSamples: 0, totalMs: 0,
+  };
+
+  constructor(id: string, driver: ChatDriver, model: string, guard?: GuardRail, opts: LlmAgentOptions = {}) {
     super(id, guard);
 
+    const caps = opts.caps ?? DEFAULT_CAPABILITIES;
     this.driver = driver;
     this.model = model;
+    this.nativeTools = caps.nativeTools;
+    this.tools = [SH_TOOL_DEF].filter(t => !opts.tools || opts.tools.includes(t.function.name));
 
     // Compose system prompt: a short agent header + the shared default.
-    [this.baseSystemPrompt, this.defaultSystemPrompt] = buildSystemPrompt(this.id);
+    [this.baseSystemPrompt, this.defaultSystemPrompt] = buildSystemPrompt(this.id, this.tools.map(t => t.function.name), opts.templates);
+    if (opts.systemPrompt?.trim()) this.defaultSystemPrompt = opts.systemPrompt.trim();
 
     // Attach a hysteresis-based memory that summarizes overflow.
     this.memory = new NormativeMemory({
@@ -117,28 +169,65 @@ export class LlmAgent extends Agent {
       baseSystemPrompt: this.baseSystemPrompt,
       defaultSystemPrompt: this.defaultSystemPrompt,
 
-      contextTokens: 30_000,          // model window
+      // Window comes from the capability probe; reasoning models get room to think.
+      contextTokens: caps.contextTokens,
       reserveHeaderTokens: 1200,      // header/tool schema reserve
-      reserveResponseTokens: 800,     // space for the next reply
+      reserveResponseTokens: caps.reasoning ? 2000 : 800,
       highRatio: 0.70,                // trigger summarization earlier than overflow
       lowRatio: 0.50,                 // target after summarization
       summaryRatio: 0.35,             // 35% of budget for the 3 summaries
 
       avgCharsPerToken: 4,            // char→token estimate
       keepRecentPerLane: 4,           // retain 4 most-recent per lane
-      keepRecentTools: 3              // retain 3 most-recent tool outputs
+      keepRecentTools: 3,             // retain 3 most-recent tool outputs
+
+      ...opts.memory,
     });
 
     // Default executor used polymorphically
-    this.toolExecutor = new StandardToolExecutor();
+    this.toolExecutor = new StandardToolExecutor({ allowedTools: this.tools.map(t => t.function.name) });
+  }
+
+  getUsage(): AgentUsage {
+    return { ...this.usage };
+  }
+
+  private recordUsage(sent: ChatMessage[], out: ChatOutput, elapsedMs: number): void {
+    const u = usageOrEstimate(sent, out);
+    const totalMs = out.timing?.totalMs ?? elapsedMs;
+    const ttftMs = out.timing?.ttftMs;
+
+    this.usage.calls++;
+    this.usage.promptTokens += u.promptTokens ?? 0;
+    this.usage.completionTokens += u.completionTokens ?? 0;
+    if (u.estimated) this.usage.estimatedCalls++;
+    if (ttftMs !== undefined) {
+      this.usage.ttftMsTotal += ttftMs;
+      this.usage.ttftSamples++;
+    }
+    this.usage.totalMs += totalMs;
+
+    Logger.debug(`${this.id} usage`, { ...u, ttftMs, totalMs, totals: this.usage });
+
+    // Fire-and-forget: metrics must never slow down or break a turn.
+    RunMetrics.emitLlm({
+      runId: currentRunId(),
+      agent: this.id,
+      model: this.model,
+      promptTokens: u.promptTokens ?? 0,
+      completionTokens: u.completionTokens ?? 0,
+      estimated: u.estimated || undefined,
+      ttftMs,
+      totalMs,
+    }).catch(() => { /* ignore */ });
   }
 
   async load(): Promise<void> {
-    this.memory.load(this.id);
+    await this.memory.load(this.id);
   }
 
   async save(): Promise<void> {
-    this.memory.save(this.id);
+    await this.memory.save(this.id);
   }
 
   async respond(messages: ChatMessage[], maxTools: number, filters: NoiseFilters, peers: Agent[], callbacks: AgentCallbacks): Promise<AgentReply[]> {
@@ -206,23 +295,31 @@ export class LlmAgent extends Agent {
   }
 
   async onHandleMessages(messages: ChatMessage[], remaining: number, filters: NoiseFilters, peers: Agent[], callbacks: AgentCallbacks): Promise<AgentReply[]> {
-    let replies: ChatResponse[] = [];
+    let replies: AgentReply[] = [];
     // ---- STREAM DEFERRAL (single seam to TTY controller) ----
-    callbacks.onStreamStart?.();
+    const signal = (await callbacks.onStreamStart?.()) || undefined;
     try {
       replies = await this.respondOnce(
         messages,
         remaining,
         peers,
         callbacks.shouldAbort,
+        signal,
+        callbacks.onTool,
       );
 
-      for (const { message, toolsUsed } of replies) {
+      for (const { message, toolsUsed, interrupted } of replies) {
         Logger.debug(
           `${this.id} replied toolsRemaining=${remaining} message=`,
           JSON.stringify(message)
         );
 
+        // A cut-off reply is never routed (its tags may be half-written); the user has the floor.
+        if (interrupted) {
+          this.yieldToUser = true;
+          continue;
+        }
+
         const maybeYieldToUser = await callbacks.onRoute(message, filters);
         const yieldToUser = await callbacks.onRouteCompleted(message, toolsUsed, maybeYieldToUser);
         if (yieldToUser) {
@@ -230,7 +327,7 @@ export class LlmAgent extends Agent {
         }
       }
     } finally {
-      await callbacks?.onStreamEnd();
+      await callbacks?.onStreamEnd(signal);
     }
 
     return replies;
@@ -241,8 +338,9 @@ export class LlmAgent extends Agent {
    * - Add the user's text to memory.
    * - Let the model respond; if it asks for tools, execute (sh only) and loop.
    * - Stop after first assistant text with no more tool calls or when budget is hit.
+   * - If `signal` aborts mid-stream, keep the partial text (marked interrupted) and stop.
    */
-  async respondOnce(messages: ChatMessage[], remaining: number, _peers: Agent[], abortCallback: () => boolean): Promise<AgentReply[]> {
+  async respondOnce(messages: ChatMessage[], remaining: number, _peers: Agent[], abortCallback: () => boolean, signal?: AbortSignal, onTool?: ToolObserver): Promise<AgentReply[]> {
     Logger.debug(`${this.id} start`, { promptChars: prompt.length, remaining });
     if (abortCallback?.()) {
       Logger.debug("Aborted turn");
@@ -296,30 +394,44 @@ export class LlmAgent extends Agent {
       debugStreaming = true;
     }
 
-    const out = await this.driver.chat(this.memory.messages().map(m => this.formatMessage(m)), {
-      model: this.model,
-      tools: this.tools,
-      onReasoningToken: t => {
-        const hideCot = !R.env.ORG_HIDE_COT && (String(R.env.DEBUG || "").trim() !== "1");
-        if (hideCot) {
-          Logger.streamInfo(C.cyan('.'));
-          return;
-        }
-        Logger.streamInfo(C.cyan(t))
-      },
-      onToken: t => {
-        if (streamState !== "content") {
-          Logger.info("");
-          streamState = "content";
-        }
+    // What has streamed so far; kept if the user cancels the generation.
+    let partialText = "";
+    let partialReasoning = "";
 
-        const cleaned = this.streamFilter.feed(t);
-        if (cleaned) Logger.streamInfo(C.bold(cleaned));
-      },
-      onToolCallDelta
-    });
+    const sent = this.memory.messages().map(m => this.formatMessage(m));
+    let out: ChatOutput;
+    try {
+      out = await this.driver.chat(sent, {
+        model: this.model,
+        tools: this.nativeTools && this.tools.length ? this.tools : undefined,
+        signal,
+        onReasoningToken: t => {
+          partialReasoning += t;
+          const hideCot = !R.env.ORG_HIDE_COT && (String(R.env.DEBUG || "").trim() !== "1");
+          if (hideCot) {
+            Logger.streamInfo(C.cyan('.'));
+            return;
+          }
+          Logger.streamInfo(C.cyan(t))
+        },
+        onToken: t => {
+          partialText += t;
+          if (streamState !== "content") {
+            Logger.info("");
+            streamState = "content";
+          }
+          const cleaned = this.streamFilter.feed(t);
+          if (cleaned) Logger.streamInfo(C.bold(cleaned));
+        },
+        onToolCallDelta
+      });
+    } catch (e) {
+      if (!signal?.aborted) throw e;
+      return this.onInterrupted(partialText, partialReasoning);
+    }
 
     Logger.debug("this.driver.chat", out);
+    this.recordUsage(sent, out, Date.now() - t0);
 
     const tail = this.streamFilter.flush();                            // then flush filter
     if (tail) Logger.streamInfo(C.bold(tail) + "\n");
@@ -380,20 +492,37 @@ export class LlmAgent extends Agent {
       memory: this.memory,
       finalText,
       agentId: this.id,
+      cwd: this.workDir,
+      observer: onTool,
     });
     const toolsUsed = execResult.toolsUsed;
     forceEndTurn = execResult.forceEndTurn;
 
+    const thinking = out.thinking?.length ? { thinking: out.thinking } : {}; // Anthropic wants these back with the tool calls
     if (finalText) {
       Logger.debug(`${this.id} add assistant memory`, { chars: finalText.length });
-      await this.memory.add({ role: "assistant", content: `${allReasoning ? `${allReasoning} -> ` : ""}${finalText}`, from: "Me" });
+      await this.memory.add({ role: "assistant", content: `${allReasoning ? `${allReasoning} -> ` : ""}${finalText}`, from: "Me", ...thinking });
     } else if (allReasoning) {
       Logger.debug(`${this.id} add assistant memory`, { chars: allReasoning.length });
-      await this.memory.add({ role: "assistant", content: allReasoning, from: "Me" });
+      await this.memory.add({ role: "assistant", content: allReasoning, from: "Me", ...thinking });
     }
 
     Logger.info(C.blue(`\n[${this.id}] wrote. [${calls.length}] tools requested. [${toolsUsed}] tools used.`));
 
     return [{ message: finalText, toolsUsed: calls.length, reasoning: allReasoning }];
   }
+
+  /** The user cancelled the stream: remember what was said so far and hand back control. */
+  private async onInterrupted(partialText: string, partialReasoning: string): Promise<AgentReply[]> {
+    const tail = this.streamFilter.flush();
+    if (tail) Logger.streamInfo(C.bold(tail));
+    Logger.info(C.yellow(`\n[${this.id}] interrupted.`));
+
+    const text = partialText.trim();
+    const reasoning = partialReasoning.trim();
+    if (text || reasoning) {
+      await this.memory.add({ role: "assistant", content: `${text || reasoning} ${INTERRUPTED_MARK}`, from: "Me" });
+    }
+    return [{ message: text, toolsUsed: 0, reasoning: reasoning || undefined, interrupted: true }];
+  }
 }
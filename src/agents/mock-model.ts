@@ -11,8 +11,8 @@ export class MockModel extends Agent {
 
   private turn = 0;
 
-  constructor(private readonly name: string) {
-    super("mock");
+  constructor(id: string) {
+    super(id);
   }
 
   async respond(messages: ChatMessage[], maxTools: number, filters: NoiseFilters, peers: Agent[], callbacks: AgentCallbacks): Promise<AgentReply[]> {
@@ -27,16 +27,16 @@ export class MockModel extends Agent {
       this.turn++;
       const iso = new Date().toISOString();
       // Show it's grouped (and to keep routers exercised)
-      return [{ message: `@@group ${this.name} ran a tool: ${iso}`, toolsUsed: 1 }];
+      return [{ message: `@@group ${this.id} ran a tool: ${iso}`, toolsUsed: 1 }];
     }
 
     // After tools are done, emit a few variations that include tags.
     this.turn++;
 
-    const peer = messages.find((m) => m.from !== this.name) || "group";
+    const peer = messages.find((m) => m.from !== this.id)?.from || "group";
     const patterns = [
       `@@${peer} did you see the update?`,
-      `##notes-${this.name}.txt Here are some notes for the team.\nLine 2.`,
+      `##notes-${this.id}.txt Here are some notes for the team.\nLine 2.`,
       `@@group All good on my side.`,
       `@@user Done.`,
     ];
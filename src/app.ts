@@ -17,7 +17,9 @@ import { R } from "./runtime/runtime";
 import { ExecutionGate } from "./tools/execution-gate";
 import { loadConfig } from "./config/config";
 import { C, Logger } from "./logger";
-import type { IScheduler, SchedulerLike } from "./scheduler/scheduler";
+import type { SchedulerLike } from "./scheduler/scheduler";
+import type { SchedulerEvents } from "./scheduler/events";
+import { RunMetrics } from "./metrics/runtime-metrics";
 import { getRecipe } from "./recipes";
 import { sandboxMangers } from "./sandbox/session";
 import { TtyController } from "./input/tty-controller";
@@ -26,7 +28,16 @@ import { createFeedbackController } from "./ui/feedback";
 import { installHotkeys } from "./runtime/hotkeys";
 import { printInitCard } from "./ui/pretty";
 import { AgentManger } from "./agents/agent-manager";
-import { RandomScheduler } from "./scheduler/random-scheduler";
+import { resolveAgentDefinitions } from "./config/team-file";
+import { sessionLimits } from "./scheduler/budgets";
+import { AgentSpawner } from "./agents/agent-spawner";
+import { withRecipeTools } from "./agents/agent-definition";
+import { createScheduler, schedulerKind } from "./scheduler/create-scheduler";
+import { FSMScheduler, traceTransitions } from "./scheduler/fsm-scheduler";
+import { recordTranscript } from "./runtime/transcript";
+import { Workspaces, concurrencyLevel, isolationMode } from "./sandbox/workspaces";
+import { SessionError, SessionStore, latestSessionRunId } from "./runtime/session-state";
+import { currentRunId, isRunId, runDir } from "./runtime/run-dir";
 
 if (R.env.ORG_LAUNCHER_SCRIPT_RAN !== "1") { // TODO - safely support non-sandboxed workflows without opening up this hole.
   Logger.error(C.red("org must be launched via the org wrapper (sandbox). Refusing to run on host."));
@@ -89,7 +100,10 @@ installShutdownHook();
 Logger.debug("[org] Installing hotkeys 🔥");
 const uninstallHotkeys = installHotkeys({
   stdin: R.stdin as any,
-  onEsc: async () => { await R.ttyController?.unwind(); /* finalizer handles review+exit */ },
+  onEsc: async () => {
+    R.ttyController?.cancelStream(); // stop the model now, not after it finishes
+    await R.ttyController?.unwind(); /* finalizer handles review+exit */
+  },
   onCtrlC: () => { Logger.error("SIGINT"); R.exit(130); },
   feedback: R.stderr,
   debug: !!R.env.DEBUG,
@@ -118,11 +132,12 @@ function enableDebugIfRequested(args: Record<string, string | boolean>) {
   }
 }
 
-function computeMode(extra?: { allowTools?: string[] }) {
+// Tool allowlists are per agent (LlmAgent + StandardToolExecutor), not global.
+function computeMode() {
   const interactive = true;
   const cfg = loadConfig();
   const safe = !!(cfg as unknown as { runtime?: { safe?: boolean } })?.runtime?.safe;
-  ExecutionGate.configure({ safe, interactive, allowTools: extra?.allowTools });
+  ExecutionGate.configure({ safe, interactive });
 }
 
 
@@ -173,17 +188,35 @@ function applyPatch(workDir: string, patchPath: string) {
   execFileSync("git", ["-C", workDir, "apply", "--index", patchPath], { stdio: "inherit" });
 }
 
-async function finalizeOnce(scheduler: SchedulerLike | null, workDir: string, reviewMode: "ask" | "auto" | "never") {
+/** Bring concurrent agents' worktrees back into the work dir, so the session patch has all of it. */
+function mergeWorkspaces(workspaces: Workspaces | undefined, events?: SchedulerEvents) {
+  for (const r of workspaces?.merge() ?? []) {
+    events?.emit({ type: "patch-produced", agentId: r.agentId, path: r.patch, merged: r.merged });
+    if (r.merged) Logger.info(C.magenta(`[workspace] merged ${r.agentId}'s changes (${r.patch})`));
+    else Logger.warn(C.yellow(`[workspace] ${r.agentId}'s changes did not merge cleanly; check for conflicts, or apply ${r.patch} by hand${r.error ? `\n${r.error}` : ""}`));
+  }
+}
+
+async function finalizeOnce(scheduler: SchedulerLike | null, workDir: string, reviewMode: "ask" | "auto" | "never", session?: SessionStore, workspaces?: Workspaces) {
   // Ensure tools/sandboxes are finalized before we exit
   try { await (sandboxMangers as { finalizeAll?: () => Promise<void> }).finalizeAll?.(); } catch { /* ignore */ }
   try { await scheduler?.stop?.(); } catch { /* ignore */ }
   try { await scheduler?.drain?.(); } catch { /* ignore */ }
+  mergeWorkspaces(workspaces, scheduler?.events);
 
   const patches = await listRecentSessionPatches(workDir, 120);
   if (patches.length === 0) {
     Logger.info("No patch produced.");
     return;
   }
+  for (const patch of patches) scheduler?.events.emit({ type: "patch-produced", path: patch });
+  await reviewPatches(patches, workDir, reviewMode, session);
+}
+
+/** Offer each patch for review. The session remembers undecided patches so `--resume` can finish the review. */
+async function reviewPatches(patches: string[], workDir: string, reviewMode: "ask" | "auto" | "never", session?: SessionStore) {
+  const pending = [...patches];
+  session?.setReview(pending);
 
   for (const patch of patches) {
     Logger.info(`Patch ready: ${patch}`);
@@ -208,17 +241,44 @@ async function finalizeOnce(scheduler: SchedulerLike | null, workDir: string, re
     } else {
       Logger.info("Patch NOT applied.");
     }
+    pending.splice(pending.indexOf(patch), 1);
+    session?.setReview(pending);
   }
+  session?.setReview(null);
 }
 
 // ───────────────────────────────────────────────────────────────────────────────
 // main
 // ───────────────────────────────────────────────────────────────────────────────
 
+/** Flags a resumed session keeps unless given again. */
+const SESSION_ARGS = ["recipe", "scheduler", "trace-scheduler", "concurrency", "isolation", "max-tools", "max-hops", "max-tokens", "timeout-ms", "max-cost", "review"] as const;
+
+/** `--resume [run-id]`: load that run's session (default: the latest) and continue under its run id. */
+function openResumedSession(args: Record<string, string | boolean>): SessionStore | null {
+  const resume = args["resume"];
+  if (resume === undefined || resume === false) return null;
+  if (args["team"] || args["agents"]) throw new SessionError("a resumed session keeps its team; drop --team/--agents");
+  const runId = typeof resume === "string" && resume.trim() ? resume.trim() : latestSessionRunId();
+  if (!runId) throw new SessionError("no saved session under .org/runs to resume");
+  if (!isRunId(runId)) throw new SessionError(`invalid run id "${runId}" (letters, digits, ".", "_" and "-" only)`);
+  const session = SessionStore.load(runId);
+  R.env.ORG_RUN_ID = runId; // cassettes, metrics and checkpoints keep going into the same run directory
+  session.restoreMemories();
+  return session;
+}
+
 async function main() {
+  // `org transcript <run-id>` renders a finished run's transcript and exits (see cli/transcript).
+  if (R.argv[2] === "transcript") {
+    const { transcriptCommand } = await import("./cli/transcript");
+    R.exit(await transcriptCommand(R.argv.slice(3)));
+  }
+
   const cfg = loadConfig();
   const argv = R.argv.slice(2);
-  const args = R.args;
+  const resumed = openResumedSession(R.args);
+  const args = resumed ? { ...resumed.args, ...R.args } : R.args;
 
   // tmux handoff
   if (args["ui"] === "tmux" && R.env.ORG_TMUX !== "1") {
@@ -235,7 +295,7 @@ async function main() {
   }
 
   enableDebugIfRequested(args);
-  computeMode({ allowTools: getRecipe(null)?.allowTools });
+  computeMode();
 
   Logger.info(
     `${C.gray("Press ")}${C.bold("Esc")} ${C.gray("to gracefully exit (saves sandbox patches).")} ` +
@@ -263,24 +323,48 @@ async function main() {
 
   const recipeName = (typeof args["recipe"] === "string" && args["recipe"]) || (R.env.ORG_RECIPE || "");
   const recipe = getRecipe(recipeName || null);
+  // --scheduler random|round-robin|priority|fsm (or ORG_SCHEDULER); checked before any agent is built.
+  // --trace-scheduler records the fsm's transitions, so it picks fsm unless a scheduler is named.
+  const traceScheduler = !!args["trace-scheduler"];
+  const schedKind = schedulerKind(args["scheduler"] ?? (traceScheduler ? "fsm" : undefined));
+  if (traceScheduler && schedKind !== "fsm") {
+    Logger.warn(C.yellow(`[scheduler] --trace-scheduler needs --scheduler fsm; ${schedKind} is not traced.`));
+  }
+  // --concurrency N runs up to N agent turns at once, isolated per --isolation (see sandbox/workspaces)
+  const concurrency = concurrencyLevel(args["concurrency"]);
+  const workspaces = concurrency > 1 ? new Workspaces(isolationMode(args["isolation"]), R.cwd()) : undefined;
+
+  // LLM cassettes: drivers are wrapped at creation time (see drivers/cassette-driver).
+  if (args["record"]) R.env.ORG_LLM_RECORD = "1";
+  if (typeof args["replay"] === "string" && args["replay"]) R.env.ORG_LLM_REPLAY = args["replay"];
 
   // Build agents
-  const agentSpecs = await agentManager.parse(String(args["agents"] || "alice^lmstudio"), cfg.llm, recipe?.system ?? null);
+  // --team org.yaml (or ./org.yaml) declares the team; --agents is the one-line shorthand.
+  const team = resumed?.team ?? resolveAgentDefinitions(args, R.cwd());
+  Logger.debug("agents from", team.source);
+  const session = resumed ?? SessionStore.create(
+    currentRunId(),
+    { source: team.source, definitions: team.definitions, templates: team.templates },
+    Object.fromEntries(SESSION_ARGS.filter(k => args[k] !== undefined).map(k => [k, args[k]])),
+  );
+  const definitions = withRecipeTools(team.definitions, recipe?.allowTools);
+  const templates = withRecipeTools(team.templates, recipe?.allowTools);
+  const agentSpecs = await agentManager.create(definitions, cfg.llm, recipe?.system ?? null, templates.map(t => t.id));
   if (agentSpecs.length === 0) {
     Logger.error("No agents created.");
     R.exit(1);
   }
 
   const agents = agentSpecs.map(a => a.model);
-  // kickoff FIRST so we can set promptEnabled properly
+  // kickoff FIRST so we can set promptEnabled properly; a resumed session already had its kickoff
   const kickoff: string | undefined =
-    typeof args["prompt"] === "string" ? String(args["prompt"])
-      : typeof recipe?.kickoff === "string" ? recipe!.kickoff
+    typeof R.args["prompt"] === "string" ? String(R.args["prompt"])
+      : typeof recipe?.kickoff === "string" && !resumed ? recipe!.kickoff
         : undefined;
 
   const reviewMode = (args["review"] ?? "ask") as "ask" | "auto" | "never";
 
-  const scheduler: IScheduler = new RandomScheduler({
+  const scheduler = createScheduler(schedKind, {
     agents,
     maxTools: Math.max(0, Number(args["max-tools"] ?? (recipe?.budgets?.maxTools ?? 20))),
     onAskUser: async (_: string, content: string) => {
@@ -288,18 +372,38 @@ async function main() {
         return R.ttyController?.askUser();
       }
 
-      await finalizeOnce(scheduler, workDir, reviewMode);
+      await finalizeOnce(scheduler, workDir, reviewMode, session, workspaces);
       R.exit(0);
     },
     workDir, // repo root to copy/sync into /work
     reviewMode,
     promptEnabled: R.isInteractive(),
+    // Workers come from org.yaml templates, or copy a team agent (`/spawn coder ...`).
+    spawner: new AgentSpawner(agentManager, [...templates, ...definitions], cfg.llm, recipe?.system ?? null),
+    // Checkpoint after every turn so `org --resume <run-id>` can pick up from here.
+    onCheckpoint: (snapshot) => session.checkpoint(snapshot),
+    // --max-hops/--max-tokens/--timeout-ms/--max-cost (recipes set hops and time); agents add their own.
+    budgets: sessionLimits(args),
+    concurrency,
+    workspaces,
+    onBudgetStop: async () => {
+      await finalizeOnce(scheduler, workDir, reviewMode, session, workspaces);
+      R.exit(0);
+    },
     // Bridge: scheduler keeps the logic; controller renders & collects the line.
     readUserLine: async () => R.ttyController!.readUserLine(),
     // STREAM DEFERRAL: bracket every chattering section
     onStreamStart: async () => R.ttyController?.onStreamStart(),
-    onStreamEnd: async () => R.ttyController?.onStreamEnd(),
+    onStreamEnd: async (signal) => R.ttyController?.onStreamEnd(signal),
   });
+  // Metrics and traces follow the scheduler's event bus (see scheduler/events).
+  RunMetrics.follow(scheduler.events, session.runId);
+  recordTranscript(scheduler.events, path.join(runDir(session.runId), "transcript.jsonl"));
+  if (traceScheduler && scheduler instanceof FSMScheduler) {
+    const traceFile = path.join(runDir(session.runId), "scheduler-trace.jsonl");
+    traceTransitions(scheduler, traceFile);
+    Logger.info(C.gray(`[scheduler] tracing transitions to ${path.relative(R.cwd(), traceFile)}`));
+  }
 
   // Build input (controller binds raw mode & keys; loop owned by scheduler)
   if (R.stdin.isTTY) {
@@ -329,10 +433,20 @@ async function main() {
       stdin: R.stdin,
       stdout: R.stdout,
       scheduler,
-      finalizer: async () => { await finalizeOnce(scheduler, workDir, reviewMode); },
+      finalizer: async () => { await finalizeOnce(scheduler, workDir, reviewMode, session, workspaces); },
     });
   }
 
+  if (resumed) {
+    if (resumed.scheduler) await scheduler.restore(resumed.scheduler);
+    const queued = Object.values(resumed.scheduler?.inbox ?? {}).reduce((n, q) => n + q.length, 0);
+    Logger.info(C.magenta(`Resumed run ${resumed.runId} (${definitions.length} agents, ${queued} queued messages).`));
+    // A review cut short (crash, closed laptop) is finished before the agents carry on.
+    if (resumed.review) await reviewPatches(resumed.review.patches, workDir, reviewMode, session);
+  }
+  Logger.info(C.gray(`Run ${session.runId}; continue later with: org --resume ${session.runId}`));
+  session.write();
+
   // Seed a kickoff message if provided
   if (typeof kickoff === "string" && kickoff.length > 0) {
     await scheduler.interject(kickoff);
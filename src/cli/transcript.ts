@@ -0,0 +1,161 @@
+// src/cli/transcript.ts
+// `org transcript <run-id> [--format md|html] [--out FILE]`: render a run's
+// transcript.jsonl (see runtime/transcript) as a table with one lane (column) per
+// participant. A message sent to several agents is one row; runs of tool calls
+// by one agent fold into a single <details> row.
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { C, Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import { isRunId, runDir } from "../runtime/run-dir";
+import { readTranscript, type TranscriptEntry } from "../runtime/transcript";
+import type { GuardDecision } from "../guardrails/guardrail";
+
+export type TranscriptFormat = "md" | "html";
+
+/** One cell of the table: a heading line, a body, and (for tool calls) the folded lines. */
+type Row = { at: string; lane: string; head: string; body: string; folded?: string[] };
+
+const CC_NOTE = /^\[cc[^\]]*\] /; // see scheduler/router ccNote()
+
+const laneOf = (who: string) => (who.toLowerCase() === "user" ? "User" : who);
+
+/** What a guard decision did, in a few words. */
+export function guardSummary(g: GuardDecision): string {
+  const out: string[] = [];
+  if (g.suppressBroadcast) out.push("broadcast suppressed");
+  if (g.nudge) out.push("nudged");
+  if (g.muteMs) out.push(`muted ${g.muteMs} ms`);
+  if (g.endTurn) out.push("turn ended");
+  if (g.askUser) out.push("asked the user");
+  if (g.warnings?.length) out.push(g.warnings.join(", "));
+  return out.join("; ") || "no action";
+}
+
+/** Group the entries into rows: one per message (all its recipients), file, suppressed broadcast or run of tool calls. */
+function rows(entries: TranscriptEntry[]): Row[] {
+  const out: Row[] = [];
+  let open: { row: Row; key: string; to: string[]; cc: string[]; group: boolean; guard?: GuardDecision } | undefined;
+  let tools: { row: Row; failed: number } | undefined;
+
+  const closeMsg = () => {
+    if (!open) return;
+    const to = open.group ? ["group"] : open.to;
+    open.row.head = `→ ${to.join(", ")}${open.cc.length ? ` · cc ${open.cc.join(", ")}` : ""}${open.guard ? ` (guard: ${guardSummary(open.guard)})` : ""}`;
+    open = undefined;
+  };
+
+  for (const e of entries) {
+    if (e.type !== "tool") tools = undefined;
+    if (e.type !== "message") closeMsg();
+
+    if (e.type === "message") {
+      if (e.kind === "system") {
+        closeMsg();
+        out.push({ at: e.at, lane: laneOf(e.to), head: "system note", body: e.content });
+        continue;
+      }
+      const content = e.kind === "cc" ? e.content.replace(CC_NOTE, "") : e.content;
+      const key = `${e.from}\0${e.threadId ?? ""}\0${content}`;
+      if (open?.key !== key) {
+        closeMsg();
+        const row: Row = { at: e.at, lane: laneOf(e.from), head: "", body: content };
+        out.push(row);
+        open = { row, key, to: [], cc: [], group: e.kind === "group" };
+      }
+      (e.kind === "cc" ? open.cc : open.to).push(laneOf(e.to));
+      if (e.kind !== "group") open.group = false;
+      if (e.guard) open.guard = e.guard;
+    } else if (e.type === "tool") {
+      const line = `${e.tool} ${e.args} → ${e.ok ? "ok" : `exit ${e.exitCode}`}, ${e.ms} ms`;
+      if (!tools || tools.row.lane !== laneOf(e.from)) {
+        tools = { row: { at: e.at, lane: laneOf(e.from), head: "", body: "", folded: [] }, failed: 0 };
+        out.push(tools.row);
+      }
+      tools.row.folded!.push(line);
+      if (!e.ok) tools.failed++;
+      const n = tools.row.folded!.length;
+      tools.row.head = `${n} tool call${n === 1 ? "" : "s"}${tools.failed ? ` (${tools.failed} failed)` : ""}`;
+    } else if (e.type === "file") {
+      out.push({ at: e.at, lane: laneOf(e.from), head: `wrote ${e.path}`, body: `${e.bytes} bytes` });
+    } else if (e.type === "suppressed") {
+      out.push({ at: e.at, lane: laneOf(e.from), head: `@@group (guard: ${guardSummary(e.guard)})`, body: "" });
+    }
+  }
+  closeMsg();
+  return out;
+}
+
+const escHtml = (s: string) => s.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;").replace(/"/g, "&quot;");
+
+function cell(r: Row, nl: string): string {
+  const esc = (s: string) => escHtml(s).replace(/\n/g, nl);
+  if (r.folded) return `<details><summary>${esc(r.head)}</summary>${r.folded.map(esc).join(nl)}</details>`;
+  return `<b>${esc(r.head)}</b>${r.body ? nl + esc(r.body) : ""}`;
+}
+
+/** Render transcript entries as a Markdown or HTML table with a lane per participant (the user first). */
+export function renderTranscript(entries: TranscriptEntry[], format: TranscriptFormat, title: string): string {
+  const all = rows(entries);
+  const lanes = [...new Set(all.map((r) => r.lane))].sort((a, b) => Number(b === "User") - Number(a === "User"));
+  const time = (at: string) => at.slice(11, 19);
+
+  if (format === "md") {
+    const line = (cells: string[]) => `| ${cells.join(" | ")} |`;
+    return [
+      `# Transcript ${title}`,
+      "",
+      line(["time", ...lanes]),
+      line(["---", ...lanes.map(() => "---")]),
+      ...all.map((r) => line([time(r.at), ...lanes.map((l) => (l === r.lane ? cell(r, "<br>").replace(/\|/g, "\\|") : ""))])),
+      "",
+    ].join("\n");
+  }
+
+  const th = lanes.map((l) => `<th>${escHtml(l)}</th>`).join("");
+  const trs = all.map((r) => `<tr><td class="t">${time(r.at)}</td>${lanes.map((l) => `<td>${l === r.lane ? cell(r, "<br>") : ""}</td>`).join("")}</tr>`);
+  return [
+    "<!doctype html>",
+    `<html><head><meta charset="utf-8"><title>Transcript ${escHtml(title)}</title>`,
+    "<style>body{font:14px system-ui,sans-serif}table{border-collapse:collapse;width:100%}td,th{border:1px solid #ddd;padding:4px 8px;vertical-align:top;text-align:left}td.t{color:#888;white-space:nowrap}details summary{cursor:pointer}</style>",
+    `</head><body><h1>Transcript ${escHtml(title)}</h1>`,
+    `<table><thead><tr><th>time</th>${th}</tr></thead><tbody>`,
+    ...trs,
+    "</tbody></table></body></html>",
+    "",
+  ].join("\n");
+}
+
+/** `org transcript` entry point; returns the exit code. */
+export async function transcriptCommand(argv: string[], root: string = R.cwd()): Promise<number> {
+  let runId: string | undefined, format: string | undefined, out: string | undefined;
+  for (let i = 0; i < argv.length; i++) {
+    const [flag, inline] = argv[i].split("=", 2);
+    if (flag === "--format") format = inline ?? argv[++i];
+    else if (flag === "--out") out = inline ?? argv[++i];
+    else if (!argv[i].startsWith("--") && !runId) runId = argv[i];
+  }
+  format ??= out?.endsWith(".html") ? "html" : "md";
+  if (!runId || (format !== "md" && format !== "html")) {
+    Logger.error("usage: org transcript <run-id> [--format md|html] [--out FILE]");
+    return 2;
+  }
+  if (!isRunId(runId)) {
+    Logger.error(`[transcript] invalid run id "${runId}" (letters, digits, ".", "_" and "-" only)`);
+    return 2;
+  }
+  const file = path.join(runDir(runId, root), "transcript.jsonl");
+  if (!fs.existsSync(file)) {
+    Logger.error(`[transcript] no transcript for run ${runId} (looked for ${path.relative(root, file)})`);
+    return 1;
+  }
+  const text = renderTranscript(readTranscript(file), format, runId);
+  if (!out) {
+    R.stdout.write(text);
+    return 0;
+  }
+  fs.writeFileSync(out, text);
+  Logger.info(C.gray(`[transcript] wrote ${out}`));
+  return 0;
+}
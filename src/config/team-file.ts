@@ -0,0 +1,325 @@
+// src/config/team-file.ts
+// org.yaml: a versioned team file with one entry per agent.
+//
+//   version: 1
+//   defaults:                 # optional; merged under every agent
+//     driver: ollama.native
+//     baseUrl: http://192.168.5.2:11434
+//   agents:
+//     - id: alice
+//       role: lead
+//       paths: ["src/**"]        # files it writes; see scheduler/concurrency
+//       model: qwen3:8b
+//       temperature: 0.2
+//       stop: ["</done>"]
+//       tools: [sh]
+//       systemPromptFile: prompts/alice.md
+//       memory: { contextTokens: 32768 }
+//       guardrails: { repeatToolSigEndTurnLimit: 3 }
+//       budgets: { maxTools: 30, maxHops: 40, maxCostUsd: 2 }
+//       pricing: { promptPerMTok: 3, completionPerMTok: 15 }
+//   templates:                # optional; agents spawned at runtime (see agents/agent-spawner)
+//     - id: tester
+//       model: qwen3:8b
+//
+// Everything is validated up front; errors name the file and the offending
+// field, e.g. `org.yaml: agents[1].temperature: expected a number between 0 and 2`.
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { parseYaml } from "./yaml";
+import {
+    AGENT_TOOLS,
+    parseCaretSpec,
+    parseDriverSpec,
+    type AgentBudgets,
+    type AgentDefinition,
+    type GuardrailSettings,
+    type MemorySettings,
+    type Pricing,
+} from "../agents/agent-definition";
+import type { SamplingParams } from "../drivers/sampling";
+
+export const TEAM_FILE_VERSION = 1;
+export const DEFAULT_TEAM_FILE = "org.yaml";
+
+export class TeamFileError extends Error {
+    constructor(readonly source: string, readonly field: string, message: string) {
+        super(field ? `${source}: ${field}: ${message}` : `${source}: ${message}`);
+        this.name = "TeamFileError";
+    }
+}
+
+type Obj = Record<string, unknown>;
+
+const AGENT_KEYS = [
+    "id", "model", "driver", "baseUrl", "systemPrompt", "systemPromptFile",
+    "temperature", "topP", "seed", "maxTokens", "stop", "tools", "memory", "guardrails", "budgets", "pricing", "role", "paths",
+] as const;
+const DEFAULT_KEYS = AGENT_KEYS.filter(k => k !== "id" && k !== "role" && k !== "paths" && k !== "systemPrompt" && k !== "systemPromptFile");
+
+class Validator {
+    constructor(readonly source: string, readonly baseDir: string) {}
+
+    fail(field: string, msg: string): never {
+        throw new TeamFileError(this.source, field, msg);
+    }
+
+    object(v: unknown, field: string, known: readonly string[]): Obj {
+        if (typeof v !== "object" || v === null || Array.isArray(v)) this.fail(field, "expected a mapping");
+        for (const k of Object.keys(v as Obj)) {
+            if (!known.includes(k)) this.fail(field ? `${field}.${k}` : k, `unknown field (known: ${known.join(", ")})`);
+        }
+        return v as Obj;
+    }
+
+    string(v: unknown, field: string): string {
+        if (typeof v !== "string" || v.trim() === "") this.fail(field, "expected a non-empty string");
+        return v.trim();
+    }
+
+    number(v: unknown, field: string, min: number, max: number, integer = false): number {
+        const ok = typeof v === "number" && Number.isFinite(v) && v >= min && v <= max && (!integer || Number.isInteger(v));
+        if (!ok) {
+            const what = integer ? "an integer" : "a number";
+            this.fail(field, max === Infinity ? `expected ${what} >= ${min}` : `expected ${what} between ${min} and ${max}`);
+        }
+        return v as number;
+    }
+
+    /** Validate the fields shared by `defaults` and agent entries into `out`. */
+    settings(o: Obj, field: string, out: Partial<AgentDefinition>): void {
+        const at = (k: string) => `${field}.${k}`;
+
+        if (o.model !== undefined) out.model = this.string(o.model, at("model"));
+        if (o.driver !== undefined) {
+            const raw = this.string(o.driver, at("driver"));
+            try {
+                Object.assign(out, parseDriverSpec(raw));
+            } catch (e: any) {
+                this.fail(at("driver"), `${e?.message ?? e} (expected "kind.protocol[+parser]", e.g. "ollama.native")`);
+            }
+        }
+        if (o.baseUrl !== undefined) {
+            const url = this.string(o.baseUrl, at("baseUrl"));
+            if (!/^https?:\/\/\S+$/.test(url)) this.fail(at("baseUrl"), `expected an http(s) URL, got "${url}"`);
+            out.baseUrl = url;
+        }
+        const sampling = this.sampling(o, field);
+        if (Object.keys(sampling).length) out.sampling = { ...out.sampling, ...sampling };
+        if (o.tools !== undefined) out.tools = this.tools(o.tools, at("tools"));
+        if (o.memory !== undefined) out.memory = { ...out.memory, ...this.memory(o.memory, at("memory")) };
+        if (o.guardrails !== undefined) out.guardrails = { ...out.guardrails, ...this.guardrails(o.guardrails, at("guardrails")) };
+        if (o.budgets !== undefined) out.budgets = { ...out.budgets, ...this.budgets(o.budgets, at("budgets")) };
+        if (o.pricing !== undefined) out.pricing = { ...out.pricing, ...this.pricing(o.pricing, at("pricing")) };
+    }
+
+    sampling(o: Obj, field: string): SamplingParams {
+        const at = (k: string) => `${field}.${k}`;
+        const out: SamplingParams = {};
+        if (o.temperature !== undefined) out.temperature = this.number(o.temperature, at("temperature"), 0, 2);
+        if (o.topP !== undefined) out.topP = this.number(o.topP, at("topP"), 0, 1);
+        if (o.seed !== undefined) out.seed = this.number(o.seed, at("seed"), 0, Number.MAX_SAFE_INTEGER, true);
+        if (o.maxTokens !== undefined) out.maxTokens = this.number(o.maxTokens, at("maxTokens"), 1, Infinity, true);
+        if (o.stop !== undefined) {
+            const list = typeof o.stop === "string" ? [o.stop] : o.stop;
+            if (!Array.isArray(list) || list.length === 0 || list.some(x => typeof x !== "string" || x === "")) {
+                this.fail(at("stop"), "expected a stop sequence or a list of them");
+            }
+            out.stop = list as string[];
+        }
+        return out;
+    }
+
+    tools(v: unknown, field: string): string[] {
+        if (!Array.isArray(v)) this.fail(field, `expected a list of tool names (known: ${AGENT_TOOLS.join(", ")})`);
+        const out: string[] = [];
+        v.forEach((t, i) => {
+            const name = this.string(t, `${field}[${i}]`);
+            if (!(AGENT_TOOLS as readonly string[]).includes(name)) {
+                this.fail(`${field}[${i}]`, `unknown tool "${name}" (known: ${AGENT_TOOLS.join(", ")})`);
+            }
+            if (!out.includes(name)) out.push(name);
+        });
+        return out;
+    }
+
+    memory(v: unknown, field: string): MemorySettings {
+        const o = this.object(v, field, ["contextTokens", "highRatio", "lowRatio", "summaryRatio", "keepRecentPerLane", "keepRecentTools"]);
+        const out: MemorySettings = {};
+        if (o.contextTokens !== undefined) out.contextTokens = this.number(o.contextTokens, `${field}.contextTokens`, 2048, Infinity, true);
+        if (o.highRatio !== undefined) out.highRatio = this.number(o.highRatio, `${field}.highRatio`, 0.55, 0.95);
+        if (o.lowRatio !== undefined) out.lowRatio = this.number(o.lowRatio, `${field}.lowRatio`, 0.35, 0.9);
+        if (o.summaryRatio !== undefined) out.summaryRatio = this.number(o.summaryRatio, `${field}.summaryRatio`, 0.15, 0.5);
+        if (o.keepRecentPerLane !== undefined) out.keepRecentPerLane = this.number(o.keepRecentPerLane, `${field}.keepRecentPerLane`, 1, Infinity, true);
+        if (o.keepRecentTools !== undefined) out.keepRecentTools = this.number(o.keepRecentTools, `${field}.keepRecentTools`, 0, Infinity, true);
+        if (out.highRatio !== undefined && out.lowRatio !== undefined && out.lowRatio >= out.highRatio) {
+            this.fail(`${field}.lowRatio`, `must be below highRatio (${out.highRatio})`);
+        }
+        return out;
+    }
+
+    guardrails(v: unknown, field: string): GuardrailSettings {
+        const o = this.object(v, field, ["missingArgEndTurnLimit", "repeatToolSigEndTurnLimit"]);
+        const out: GuardrailSettings = {};
+        if (o.missingArgEndTurnLimit !== undefined) {
+            out.missingArgEndTurnLimit = this.number(o.missingArgEndTurnLimit, `${field}.missingArgEndTurnLimit`, 1, Infinity, true);
+        }
+        if (o.repeatToolSigEndTurnLimit !== undefined) {
+            out.repeatToolSigEndTurnLimit = this.number(o.repeatToolSigEndTurnLimit, `${field}.repeatToolSigEndTurnLimit`, 1, Infinity, true);
+        }
+        return out;
+    }
+
+    budgets(v: unknown, field: string): AgentBudgets {
+        const o = this.object(v, field, ["maxTools", "maxHops", "maxTokens", "maxTimeMs", "maxCostUsd"]);
+        const out: AgentBudgets = {};
+        if (o.maxTools !== undefined) out.maxTools = this.number(o.maxTools, `${field}.maxTools`, 0, Infinity, true);
+        if (o.maxHops !== undefined) out.maxHops = this.number(o.maxHops, `${field}.maxHops`, 1, Infinity, true);
+        if (o.maxTokens !== undefined) out.maxTokens = this.number(o.maxTokens, `${field}.maxTokens`, 1, Infinity, true);
+        if (o.maxTimeMs !== undefined) out.maxTimeMs = this.number(o.maxTimeMs, `${field}.maxTimeMs`, 1, Infinity, true);
+        if (o.maxCostUsd !== undefined) out.maxCostUsd = this.number(o.maxCostUsd, `${field}.maxCostUsd`, 0, Infinity);
+        return out;
+    }
+
+    pricing(v: unknown, field: string): Pricing {
+        const o = this.object(v, field, ["promptPerMTok", "completionPerMTok"]);
+        const out: Pricing = {};
+        if (o.promptPerMTok !== undefined) out.promptPerMTok = this.number(o.promptPerMTok, `${field}.promptPerMTok`, 0, Infinity);
+        if (o.completionPerMTok !== undefined) out.completionPerMTok = this.number(o.completionPerMTok, `${field}.completionPerMTok`, 0, Infinity);
+        return out;
+    }
+
+    paths(v: unknown, field: string): string[] {
+        if (!Array.isArray(v) || v.length === 0) this.fail(field, 'expected a non-empty list of globs relative to the work dir, e.g. ["docs/**"]');
+        return v.map((g, i) => {
+            const glob = this.string(g, `${field}[${i}]`).trim().replace(/^\.\//, "");
+            if (!glob || glob.startsWith("/") || glob.split("/").includes("..")) {
+                this.fail(`${field}[${i}]`, `"${glob}" must be a glob inside the work dir`);
+            }
+            return glob;
+        });
+    }
+
+    agent(v: unknown, field: string, defaults: Partial<AgentDefinition>): AgentDefinition {
+        const o = this.object(v, field, AGENT_KEYS);
+        const id = this.string(o.id, `${field}.id`);
+        if (!/^[A-Za-z0-9][\w.-]*$/.test(id)) {
+            this.fail(`${field}.id`, `"${id}" is not a valid agent id (letters, digits, "_", "-", ".")`);
+        }
+
+        const def: AgentDefinition = { ...defaults, id };
+        if (o.role !== undefined) {
+            const role = this.string(o.role, `${field}.role`).trim();
+            if (!/^[A-Za-z][\w-]*$/.test(role)) this.fail(`${field}.role`, `"${role}" is not a valid role (a word, e.g. "reviewer")`);
+            def.role = role;
+        }
+        if (o.paths !== undefined) def.paths = this.paths(o.paths, `${field}.paths`);
+        this.settings(o, field, def);
+
+        if (o.systemPrompt !== undefined && o.systemPromptFile !== undefined) {
+            this.fail(field, "set either systemPrompt or systemPromptFile, not both");
+        }
+        if (o.systemPrompt !== undefined) def.systemPrompt = this.string(o.systemPrompt, `${field}.systemPrompt`);
+        if (o.systemPromptFile !== undefined) {
+            const rel = this.string(o.systemPromptFile, `${field}.systemPromptFile`);
+            const file = path.resolve(this.baseDir, rel);
+            try {
+                def.systemPrompt = fs.readFileSync(file, "utf8");
+            } catch {
+                this.fail(`${field}.systemPromptFile`, `cannot read ${file}`);
+            }
+        }
+        return def;
+    }
+}
+
+/** A validated team file. Templates are only instantiated when someone spawns them. */
+export type Team = { agents: AgentDefinition[]; templates: AgentDefinition[] };
+
+/**
+ * Validate a parsed team document. `source` names the file in errors;
+ * `systemPromptFile` paths are resolved against `baseDir`.
+ */
+export function teamFromObject(doc: unknown, source: string, baseDir: string): Team {
+    const v = new Validator(source, baseDir);
+    const top = v.object(doc, "", ["version", "defaults", "agents", "templates"]);
+
+    if (top.version !== TEAM_FILE_VERSION) {
+        v.fail("version", top.version === undefined
+            ? `missing (add "version: ${TEAM_FILE_VERSION}")`
+            : `unsupported version ${JSON.stringify(top.version)} (expected ${TEAM_FILE_VERSION})`);
+    }
+
+    const defaults: Partial<AgentDefinition> = {};
+    if (top.defaults !== undefined) v.settings(v.object(top.defaults, "defaults", DEFAULT_KEYS), "defaults", defaults);
+
+    if (!Array.isArray(top.agents) || top.agents.length === 0) v.fail("agents", "expected a non-empty list of agents");
+
+    const seen = new Set<string>();
+    const agents = top.agents.map((entry, i) => {
+        const def = v.agent(entry, `agents[${i}]`, defaults);
+        const key = def.id.toLowerCase();
+        if (seen.has(key)) v.fail(`agents[${i}].id`, `duplicate agent id "${def.id}"`);
+        seen.add(key);
+        return def;
+    });
+
+    // Template names share the namespace with agent ids: `##spawn:coder` must mean one thing.
+    if (top.templates !== undefined && !Array.isArray(top.templates)) v.fail("templates", "expected a list of templates");
+    const templates = ((top.templates as unknown[] | undefined) ?? []).map((entry, i) => {
+        const def = v.agent(entry, `templates[${i}]`, defaults);
+        const key = def.id.toLowerCase();
+        if (seen.has(key)) v.fail(`templates[${i}].id`, `duplicate agent or template id "${def.id}"`);
+        seen.add(key);
+        return def;
+    });
+    return { agents, templates };
+}
+
+export function parseTeam(text: string, source: string, baseDir: string): Team {
+    return teamFromObject(parseYaml(text, source), source, baseDir);
+}
+
+/** The agents of a team file (without its templates). */
+export function parseTeamFile(text: string, source: string, baseDir: string): AgentDefinition[] {
+    return parseTeam(text, source, baseDir).agents;
+}
+
+/** Read and validate a team file. */
+export function loadTeamFile(file: string): Team {
+    let text: string;
+    try {
+        text = fs.readFileSync(file, "utf8");
+    } catch {
+        throw new TeamFileError(file, "", "cannot read team file");
+    }
+    return parseTeam(text, file, path.dirname(path.resolve(file)));
+}
+
+/**
+ * Where the agents come from, in order: `--team <file>`, the `--agents`
+ * shorthand, `./org.yaml` when present, then a single default agent.
+ */
+export function resolveAgentDefinitions(
+    args: Record<string, unknown>,
+    cwd: string,
+): { source: string; definitions: AgentDefinition[]; templates: AgentDefinition[] } {
+    const team = typeof args["team"] === "string" ? args["team"] : "";
+    const agents = typeof args["agents"] === "string" ? args["agents"] : "";
+    if (team && agents) throw new Error("[agents] use either --team or --agents, not both");
+
+    if (team) {
+        const file = path.resolve(cwd, team);
+        const { agents: definitions, templates } = loadTeamFile(file);
+        return { source: file, definitions, templates };
+    }
+    if (agents) return { source: "--agents", definitions: parseCaretSpec(agents), templates: [] };
+
+    const local = path.resolve(cwd, DEFAULT_TEAM_FILE);
+    if (fs.existsSync(local)) {
+        const { agents: definitions, templates } = loadTeamFile(local);
+        return { source: local, definitions, templates };
+    }
+    return { source: "default", definitions: parseCaretSpec("alice^lmstudio"), templates: [] };
+}
@@ -0,0 +1,296 @@
+// src/config/yaml.ts
+// A small YAML subset parser for org's own config files (org.yaml), so we stay
+// dependency-free. Supported:
+//   - block mappings and sequences (including "- key: value" items and
+//     sequences written at their parent key's indent)
+//   - plain, 'single' and "double" quoted scalars; numbers, true/false, null/~
+//   - flow collections: [a, b] and {k: v}
+//   - block scalars: | |- |+ > >-
+//   - comments, a leading "---"
+// Not supported (rejected with an error): anchors/aliases, tags, multi-document
+// streams, complex keys, tabs in indentation.
+
+export class YamlError extends Error {
+  constructor(readonly source: string, readonly line: number, message: string) {
+    super(`${source}:${line}: ${message}`);
+    this.name = "YamlError";
+  }
+}
+
+type Line = { n: number; indent: number; text: string };
+
+const KEY_RE = /^("(?:[^"\\]|\\.)*"|'(?:[^']|'')*'|[^\s"'#\[\]{},:][^:#]*?)\s*:(?:\s+(.*))?$/;
+const NUMBER_RE = /^[-+]?(\d+(\.\d*)?|\.\d+)([eE][-+]?\d+)?$/;
+
+/** Drop a trailing " # comment" that is not inside quotes. */
+function stripComment(s: string): string {
+  let quote: string | null = null;
+  for (let i = 0; i < s.length; i++) {
+    const ch = s[i];
+    if (quote) {
+      if (ch === "\\" && quote === '"') i++;
+      else if (ch === quote) quote = null;
+    } else if (ch === '"' || ch === "'") {
+      if (i === 0 || /[\s\[{,:]/.test(s[i - 1])) quote = ch;
+    } else if (ch === "#" && (i === 0 || /\s/.test(s[i - 1]))) {
+      return s.slice(0, i).trimEnd();
+    }
+  }
+  return s.trimEnd();
+}
+
+class Parser {
+  private i = 0;
+  private started = false;
+  private readonly raw: string[];
+
+  constructor(text: string, private readonly source: string) {
+    this.raw = text.replace(/\r\n?/g, "\n").split("\n");
+  }
+
+  fail(line: number, msg: string): never {
+    throw new YamlError(this.source, line, msg);
+  }
+
+  /** Next meaningful line (skips blanks and comments) without consuming it. */
+  peek(): Line | null {
+    while (this.i < this.raw.length) {
+      const rawLine = this.raw[this.i];
+      const lead = rawLine.match(/^[ \t]*/)![0];
+      if (lead.includes("\t") && rawLine.trim()) this.fail(this.i + 1, "tabs are not allowed in indentation");
+      const text = stripComment(rawLine.slice(lead.length));
+      if (text === "" || (!this.started && text === "---")) {
+        this.i++;
+        continue;
+      }
+      if (text === "---" || text === "...") this.fail(this.i + 1, "multiple documents are not supported");
+      this.started = true;
+      return { n: this.i + 1, indent: lead.length, text };
+    }
+    return null;
+  }
+
+  parseDocument(): unknown {
+    const first = this.peek();
+    if (!first) return null;
+    const value = this.parseBlock(first.indent);
+    const rest = this.peek();
+    if (rest) this.fail(rest.n, "unexpected content (check indentation)");
+    return value;
+  }
+
+  private parseBlock(indent: number): unknown {
+    const line = this.peek()!;
+    if (line.text === "-" || line.text.startsWith("- ")) return this.parseSequence(indent);
+    if (KEY_RE.test(line.text) && !/^[\[{]/.test(line.text)) return this.parseMapping(indent);
+    this.i++;
+    return this.parseInline(line.text, line.n);
+  }
+
+  private parseMapping(indent: number): Record<string, unknown> {
+    const out: Record<string, unknown> = {};
+    for (let line = this.peek(); line && line.indent >= indent; line = this.peek()) {
+      if (line.indent > indent) this.fail(line.n, "unexpected indentation");
+      const m = line.text.match(KEY_RE);
+      if (!m) this.fail(line.n, `expected "key: value", got "${line.text}"`);
+      const key = this.parseKey(m[1], line.n);
+      if (Object.prototype.hasOwnProperty.call(out, key)) this.fail(line.n, `duplicate key "${key}"`);
+      this.i++;
+      out[key] = this.parseValue(m[2] ?? "", indent, line.n);
+    }
+    return out;
+  }
+
+  private parseSequence(indent: number): unknown[] {
+    const out: unknown[] = [];
+    for (let line = this.peek(); line && line.indent >= indent; line = this.peek()) {
+      if (line.indent > indent) this.fail(line.n, "unexpected indentation");
+      if (!(line.text === "-" || line.text.startsWith("- "))) break;
+      const content = line.text.slice(1).trimStart();
+      if (content === "") {
+        this.i++;
+        const next = this.peek();
+        out.push(next && next.indent > indent ? this.parseBlock(next.indent) : null);
+        continue;
+      }
+      const itemIndent = line.indent + (line.text.length - content.length);
+      if (KEY_RE.test(content) && !/^[\[{"']/.test(content)) {
+        // "- key: value" starts a mapping whose keys align with "key".
+        this.raw[this.i] = " ".repeat(itemIndent) + content;
+        out.push(this.parseMapping(itemIndent));
+      } else if (content === "-" || content.startsWith("- ")) {
+        this.raw[this.i] = " ".repeat(itemIndent) + content;
+        out.push(this.parseSequence(itemIndent));
+      } else {
+        this.i++;
+        out.push(this.parseValue(content, indent, line.n));
+      }
+    }
+    return out;
+  }
+
+  /** The value after "key:" (or "- "): inline scalar, block scalar, or a nested block. */
+  private parseValue(rest: string, parentIndent: number, n: number): unknown {
+    if (/^[|>][-+]?$/.test(rest)) return this.parseBlockScalar(rest, parentIndent);
+    if (rest !== "") return this.parseInline(rest, n);
+
+    const next = this.peek();
+    if (!next) return null;
+    if (next.indent > parentIndent) return this.parseBlock(next.indent);
+    // YAML allows a sequence at the same indent as its parent key.
+    if (next.indent === parentIndent && (next.text === "-" || next.text.startsWith("- "))) {
+      return this.parseSequence(parentIndent);
+    }
+    return null;
+  }
+
+  private parseBlockScalar(header: string, parentIndent: number): string {
+    const lines: string[] = [];
+    let contentIndent = -1;
+    while (this.i < this.raw.length) {
+      const rawLine = this.raw[this.i];
+      if (rawLine.trim() === "") {
+        lines.push("");
+        this.i++;
+        continue;
+      }
+      const indent = rawLine.match(/^ */)![0].length;
+      if (contentIndent < 0) {
+        if (indent <= parentIndent) break;
+        contentIndent = indent;
+      }
+      if (indent < contentIndent) break;
+      lines.push(rawLine.slice(contentIndent));
+      this.i++;
+    }
+    // Trailing blank lines belong to the chomping rule, not the content.
+    let trailing = 0;
+    while (lines.length && lines[lines.length - 1] === "") {
+      lines.pop();
+      trailing++;
+    }
+    const body = header.startsWith(">")
+      ? lines.reduce((acc, l, k) => (k === 0 ? l : l === "" || lines[k - 1] === "" ? `${acc}\n${l}` : `${acc} ${l}`), "")
+      : lines.join("\n");
+    if (header.endsWith("-") || body === "") return body;
+    if (header.endsWith("+")) return body + "\n".repeat(trailing + 1);
+    return body + "\n";
+  }
+
+  private parseKey(k: string, n: number): string {
+    const v = k.startsWith('"') || k.startsWith("'") ? this.parseInline(k, n) : k.trim();
+    return String(v);
+  }
+
+  private parseInline(text: string, n: number): unknown {
+    const s = text.trim();
+    if (s.startsWith("[") || s.startsWith("{")) {
+      const flow = new FlowParser(s, (msg) => this.fail(n, msg));
+      const v = flow.value();
+      flow.end();
+      return v;
+    }
+    return scalar(s, (msg) => this.fail(n, msg));
+  }
+}
+
+function scalar(s: string, fail: (msg: string) => never): unknown {
+  if (s.startsWith('"')) {
+    if (!/^"(?:[^"\\]|\\.)*"$/.test(s)) fail(`unterminated or malformed string ${s}`);
+    try {
+      return JSON.parse(s);
+    } catch {
+      fail(`invalid escape in ${s}`);
+    }
+  }
+  if (s.startsWith("'")) {
+    if (!/^'(?:[^']|'')*'$/.test(s)) fail(`unterminated or malformed string ${s}`);
+    return s.slice(1, -1).replace(/''/g, "'");
+  }
+  if (/^[&*!]/.test(s)) fail(`anchors, aliases and tags are not supported: ${s}`);
+  if (s === "" || s === "~" || s === "null") return null;
+  if (s === "true") return true;
+  if (s === "false") return false;
+  if (NUMBER_RE.test(s)) return Number(s);
+  return s;
+}
+
+class FlowParser {
+  private p = 0;
+  constructor(private readonly s: string, private readonly fail: (msg: string) => never) {}
+
+  private ws() {
+    while (this.p < this.s.length && /\s/.test(this.s[this.p])) this.p++;
+  }
+
+  end() {
+    this.ws();
+    if (this.p < this.s.length) this.fail(`unexpected "${this.s.slice(this.p)}" after flow collection`);
+  }
+
+  value(): unknown {
+    this.ws();
+    const ch = this.s[this.p];
+    if (ch === "[") return this.seq();
+    if (ch === "{") return this.map();
+    return this.atom(/[,\]}]/);
+  }
+
+  private atom(stop: RegExp): unknown {
+    this.ws();
+    const start = this.p;
+    const q = this.s[this.p];
+    if (q === '"' || q === "'") {
+      this.p++;
+      while (this.p < this.s.length) {
+        const ch = this.s[this.p];
+        if (ch === q) {
+          if (q === "'" && this.s[this.p + 1] === "'") { this.p += 2; continue; }
+          break;
+        }
+        if (q === '"' && ch === "\\") this.p++;
+        this.p++;
+      }
+      this.p++;
+    } else {
+      while (this.p < this.s.length && !stop.test(this.s[this.p])) this.p++;
+    }
+    return scalar(this.s.slice(start, this.p).trim(), this.fail);
+  }
+
+  private seq(): unknown[] {
+    const out: unknown[] = [];
+    this.p++; // [
+    this.ws();
+    if (this.s[this.p] === "]") { this.p++; return out; }
+    for (;;) {
+      out.push(this.value());
+      this.ws();
+      const ch = this.s[this.p++];
+      if (ch === "]") return out;
+      if (ch !== ",") this.fail(`expected "," or "]" in ${this.s}`);
+    }
+  }
+
+  private map(): Record<string, unknown> {
+    const out: Record<string, unknown> = {};
+    this.p++; // {
+    this.ws();
+    if (this.s[this.p] === "}") { this.p++; return out; }
+    for (;;) {
+      const key = String(this.atom(/[:,}]/));
+      this.ws();
+      if (this.s[this.p++] !== ":") this.fail(`expected ":" after "${key}" in ${this.s}`);
+      out[key] = this.value();
+      this.ws();
+      const ch = this.s[this.p++];
+      if (ch === "}") return out;
+      if (ch !== ",") this.fail(`expected "," or "}" in ${this.s}`);
+    }
+  }
+}
+
+/** Parse a YAML document; errors carry `source:line`. */
+export function parseYaml(text: string, source = "<yaml>"): unknown {
+  return new Parser(text, source).parseDocument();
+}
@@ -0,0 +1,142 @@
+// auth.ts
+// API keys and extra HTTP headers for authenticated endpoints (gateways in front
+// of vLLM, hosted OpenAI-compatible APIs, ...).
+//
+// Resolution, most specific first (AGENT = agent id upper-cased, non-alnum → "_"):
+//   key:     ORG_LLM_API_KEY_<AGENT>, ORG_LLM_API_KEY_FILE_<AGENT>,
+//            ORG_LLM_API_KEY,         ORG_LLM_API_KEY_FILE
+//            (Anthropic agents use ANTHROPIC_API_KEY[_FILE] as the shared key)
+//   headers: ORG_LLM_HEADERS_<AGENT>, ORG_LLM_HEADERS   (JSON object)
+//
+// Those credentials belong to the agent's own endpoint. A failover endpoint on
+// another origin gets only what ORG_LLM_ENDPOINT_AUTH configures for it (see
+// resolveEndpointAuth), and nothing otherwise.
+//
+// Keys are never logged: use redactHeaders()/redactAuth() for anything that may
+// end up in a debug line.
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { R } from "../runtime/runtime";
+
+export interface DriverAuth {
+  /** Sent as `Authorization: Bearer <key>` (Anthropic: `x-api-key`). */
+  apiKey?: string;
+  /** Extra headers merged over the defaults; may override Authorization. */
+  headers?: Record<string, string>;
+}
+
+const SENSITIVE = /authorization|api[-_]?key|token|secret|cookie|password/i;
+
+function agentEnvSuffix(agentId: string): string {
+  return agentId.toUpperCase().replace(/[^A-Z0-9]/g, "_");
+}
+
+function readKeyFile(file: string): string {
+  const p = path.resolve(R.cwd(), file.replace(/^~(?=\/)/, R.env.HOME ?? "~"));
+  try {
+    return fs.readFileSync(p, "utf8").trim();
+  } catch (e: any) {
+    // The path is fine to show; the content never is.
+    throw new Error(`[auth] cannot read API key file ${p}: ${e?.code ?? e?.message ?? e}`);
+  }
+}
+
+function parseJsonObject(raw: string, source: string, example: string): Record<string, unknown> {
+  let obj: unknown;
+  try {
+    obj = JSON.parse(raw);
+  } catch {
+    throw new Error(`[auth] ${source} must be a JSON object, e.g. ${example}`);
+  }
+  if (!obj || typeof obj !== "object" || Array.isArray(obj)) {
+    throw new Error(`[auth] ${source} must be a JSON object, e.g. ${example}`);
+  }
+  return obj as Record<string, unknown>;
+}
+
+/** Parse a JSON object of header name → value; anything else is a configuration error. */
+export function parseHeaders(raw: string, source: string): Record<string, string> {
+  const out: Record<string, string> = {};
+  for (const [k, v] of Object.entries(parseJsonObject(raw, source, `{"X-Team":"infra"}`))) out[k] = String(v);
+  return out;
+}
+
+/**
+ * Key and headers for one agent from the environment. `sharedEnv` names the
+ * variable (and its `_FILE` twin) used when the agent has no key of its own;
+ * `fallbackKey` applies when nothing is set at all.
+ */
+export function resolveDriverAuth(agentId: string, sharedEnv = "ORG_LLM_API_KEY", fallbackKey?: string): DriverAuth {
+  const suffix = agentEnvSuffix(agentId);
+  const env = R.env;
+
+  let apiKey: string | undefined;
+  if (env[`ORG_LLM_API_KEY_${suffix}`]) apiKey = env[`ORG_LLM_API_KEY_${suffix}`];
+  else if (env[`ORG_LLM_API_KEY_FILE_${suffix}`]) apiKey = readKeyFile(env[`ORG_LLM_API_KEY_FILE_${suffix}`]!);
+  else if (env[sharedEnv]) apiKey = env[sharedEnv];
+  else if (env[`${sharedEnv}_FILE`]) apiKey = readKeyFile(env[`${sharedEnv}_FILE`]!);
+  else apiKey = fallbackKey;
+
+  const headersVar = env[`ORG_LLM_HEADERS_${suffix}`] ? `ORG_LLM_HEADERS_${suffix}` : env.ORG_LLM_HEADERS ? "ORG_LLM_HEADERS" : undefined;
+  const headers = headersVar ? parseHeaders(env[headersVar]!, headersVar) : undefined;
+
+  return { apiKey: apiKey || undefined, headers };
+}
+
+/** scheme://host:port of a base URL; undefined when it does not parse. */
+function originOf(baseUrl: string): string | undefined {
+  try {
+    return new URL(baseUrl).origin;
+  } catch {
+    return undefined;
+  }
+}
+
+/**
+ * Credentials for a failover endpoint. The same origin as the agent's primary keeps
+ * the primary's `auth`; any other origin gets its entry in ORG_LLM_ENDPOINT_AUTH, a
+ * JSON object of origin → { apiKey?, apiKeyFile?, headers? }, or no credentials.
+ */
+export function resolveEndpointAuth(baseUrl: string, primary: { baseUrl: string; auth?: DriverAuth }): DriverAuth | undefined {
+  const origin = originOf(baseUrl);
+  if (!origin) return undefined;
+  if (origin === originOf(primary.baseUrl)) return primary.auth;
+
+  const raw = R.env.ORG_LLM_ENDPOINT_AUTH;
+  if (!raw) return undefined;
+  const all = parseJsonObject(raw, "ORG_LLM_ENDPOINT_AUTH", `{"https://backup.example.com":{"apiKey":"sk-..."}}`);
+  const entry = Object.entries(all).find(([k]) => originOf(k) === origin)?.[1];
+  if (!entry) return undefined;
+  if (typeof entry !== "object" || Array.isArray(entry)) throw new Error(`[auth] ORG_LLM_ENDPOINT_AUTH["${origin}"] must be an object`);
+
+  const e = entry as { apiKey?: unknown; apiKeyFile?: unknown; headers?: unknown };
+  const apiKey = e.apiKey !== undefined ? String(e.apiKey) : e.apiKeyFile !== undefined ? readKeyFile(String(e.apiKeyFile)) : undefined;
+  const headers = e.headers !== undefined ? parseHeaders(JSON.stringify(e.headers), `ORG_LLM_ENDPOINT_AUTH["${origin}"].headers`) : undefined;
+  return { apiKey: apiKey || undefined, headers };
+}
+
+/** Headers for a JSON POST to an OpenAI-compatible endpoint. */
+export function requestHeaders(auth: DriverAuth | undefined): Record<string, string> {
+  return {
+    "Content-Type": "application/json",
+    ...(auth?.apiKey ? { Authorization: `Bearer ${auth.apiKey}` } : {}),
+    ...(auth?.headers ?? {}),
+  };
+}
+
+/** Copy of `headers` safe to log: credential-looking values are masked. */
+export function redactHeaders(headers: Record<string, string>): Record<string, string> {
+  const out: Record<string, string> = {};
+  for (const [k, v] of Object.entries(headers)) out[k] = SENSITIVE.test(k) ? "***" : v;
+  return out;
+}
+
+/** Copy of any object carrying `apiKey`/`headers` (driver configs, LLM defaults) safe to log. */
+export function redactAuth<T extends DriverAuth>(o: T): T {
+  return {
+    ...o,
+    ...(o.apiKey ? { apiKey: "***" } : {}),
+    ...(o.headers ? { headers: redactHeaders(o.headers) } : {}),
+  };
+}
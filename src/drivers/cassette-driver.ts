@@ -0,0 +1,226 @@
+// cassette-driver.ts
+//
+// Record/replay wrapper around any ChatDriver.
+//
+// Recording appends one JSON line per chat() call to a cassette file: the request
+// (model, messages, tool names), every streamed delta in arrival order, and the
+// final ChatOutput (or the error). Replaying reads the same file back and feeds
+// the deltas through the caller's callbacks, so agents, memory summarizers and the
+// scheduler behave exactly as they did in the recorded run — no model server needed.
+//
+// Layout: .org/runs/<run-id>/llm/<agent-id>.jsonl, plus <agent-id>.caps.json with the
+// model capabilities the run probed, so a replay never has to reach the server.
+
+import * as fs from "fs";
+import * as path from "path";
+
+import { Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import { currentRunId, runDir } from "../runtime/run-dir";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall } from "./types";
+import type { ModelCapabilities } from "./model-capabilities";
+
+/** The cassette folder ORG_LLM_REPLAY names, or undefined when not replaying. */
+export function replayDir(): string | undefined {
+  const replay = (R.env.ORG_LLM_REPLAY || "").trim();
+  if (!replay) return undefined;
+  return replay.includes("/") ? path.resolve(replay) : path.join(runDir(replay), "llm");
+}
+
+function recording(): boolean {
+  const record = (R.env.ORG_LLM_RECORD || "").trim().toLowerCase();
+  return record === "1" || record === "true" || record === "yes";
+}
+
+const capabilitiesPath = (dir: string, agentId: string) => cassettePath(dir, agentId).replace(/\.jsonl$/, ".caps.json");
+
+/** Under ORG_LLM_RECORD, keep the agent's model capabilities beside its cassette. */
+export function recordCapabilities(agentId: string, caps: ModelCapabilities): void {
+  if (!recording()) return;
+  const file = capabilitiesPath(path.join(runDir(currentRunId()), "llm"), agentId);
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  fs.writeFileSync(file, JSON.stringify(caps, null, 2) + "\n");
+}
+
+/** Under ORG_LLM_REPLAY, the capabilities the recorded run used; undefined if it kept none. */
+export function replayedCapabilities(agentId: string): ModelCapabilities | undefined {
+  const dir = replayDir();
+  if (!dir) return undefined;
+  try {
+    return JSON.parse(fs.readFileSync(capabilitiesPath(dir, agentId), "utf8"));
+  } catch {
+    return undefined;
+  }
+}
+
+export const CASSETTE_VERSION = 1;
+
+export type CassetteEvent =
+  | { k: "token"; s: string }
+  | { k: "reasoning"; s: string }
+  | { k: "tool"; call: ChatToolCall };
+
+export interface CassetteEntry {
+  v: number;
+  seq: number;
+  at: string;
+  ms: number;
+  /** `structuredOutput`: the call asked for a JSON schema and the recorded driver sends it (see ChatDriver). */
+  request: { model?: string; messages: ChatMessage[]; tools?: string[]; structuredOutput?: boolean };
+  /** Short hash of role+content of every request message; used to detect drift on replay. */
+  fingerprint: string;
+  events: CassetteEvent[];
+  output?: ChatOutput;
+  error?: string;
+}
+
+/** FNV-1a over role/content; stable across runs and cheap enough for every call. */
+export function requestFingerprint(messages: ChatMessage[]): string {
+  let h = 0x811c9dc5;
+  for (const m of messages) {
+    const s = `${m.role}\u0000${m.content ?? ""}\u0001`;
+    for (let i = 0; i < s.length; i++) {
+      h ^= s.charCodeAt(i);
+      h = Math.imul(h, 0x01000193) >>> 0;
+    }
+  }
+  return h.toString(16).padStart(8, "0");
+}
+
+/** Cassette file for one agent inside a run directory's llm/ folder. */
+export function cassettePath(dir: string, agentId: string): string {
+  return path.join(dir, `${agentId.replace(/[^A-Za-z0-9._-]/g, "_")}.jsonl`);
+}
+
+export function readCassette(file: string): CassetteEntry[] {
+  const raw = fs.readFileSync(file, "utf8");
+  const out: CassetteEntry[] = [];
+  for (const line of raw.split("\n")) {
+    const t = line.trim();
+    if (!t) continue;
+    const e = JSON.parse(t) as CassetteEntry;
+    if (e.v !== CASSETTE_VERSION) throw new Error(`cassette ${file}: unsupported version ${e.v}`);
+    out.push(e);
+  }
+  return out;
+}
+
+/** Wrap `inner` so every call is appended to `file`. Callbacks still fire live. */
+export function makeRecordingDriver(inner: ChatDriver, file: string): ChatDriver {
+  let seq = 0;
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+
+  async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    const events: CassetteEvent[] = [];
+    const t0 = Date.now();
+    const entry: CassetteEntry = {
+      v: CASSETTE_VERSION,
+      seq: seq++,
+      at: new Date(t0).toISOString(),
+      ms: 0,
+      request: {
+        model: opts?.model,
+        messages,
+        tools: Array.isArray(opts?.tools) ? opts.tools.map((t: any) => t?.function?.name ?? t?.name).filter(Boolean) : undefined,
+        ...(opts?.responseFormat && inner.structuredOutput ? { structuredOutput: true } : {}),
+      },
+      fingerprint: requestFingerprint(messages),
+      events,
+    };
+
+    const wrapped = {
+      ...opts,
+      onToken: (s: string) => { events.push({ k: "token", s }); opts?.onToken?.(s); },
+      onReasoningToken: (s: string) => { events.push({ k: "reasoning", s }); opts?.onReasoningToken?.(s); },
+      // Drivers reuse and mutate the same call object between deltas; snapshot it.
+      onToolCallDelta: (call: ChatToolCall) => {
+        events.push({ k: "tool", call: { ...call, function: { ...call.function } } });
+        opts?.onToolCallDelta?.(call);
+      },
+    };
+
+    const write = () => {
+      entry.ms = Date.now() - t0;
+      try {
+        fs.appendFileSync(file, JSON.stringify(entry) + "\n", "utf8");
+      } catch (e: any) {
+        Logger.warn(`cassette: failed to record to ${file}: ${e?.message ?? e}`);
+      }
+    };
+
+    try {
+      const out = await inner.chat(messages, wrapped);
+      entry.output = out;
+      write();
+      return out;
+    } catch (e: any) {
+      entry.error = String(e?.message ?? e);
+      write();
+      throw e;
+    }
+  }
+
+  return { chat, structuredOutput: inner.structuredOutput };
+}
+
+/**
+ * Replay a recorded cassette in order. Each chat() consumes the next entry.
+ * When the request no longer matches the recording (the code under test changed
+ * what it sends), `strict` throws; otherwise it is logged and replay continues.
+ */
+export function makeReplayDriver(file: string, opts?: { strict?: boolean }): ChatDriver {
+  const entries = readCassette(file);
+  let next = 0;
+
+  async function chat(messages: ChatMessage[], callOpts?: any): Promise<ChatOutput> {
+    const entry = entries[next];
+    if (!entry) throw new Error(`cassette ${file} exhausted after ${entries.length} call(s)`);
+    next++;
+
+    const fp = requestFingerprint(messages);
+    if (fp !== entry.fingerprint) {
+      const msg = `cassette ${file}: request #${entry.seq} differs from recording (${fp} != ${entry.fingerprint})`;
+      if (opts?.strict) throw new Error(msg);
+      Logger.warn(msg);
+    }
+
+    for (const ev of entry.events) {
+      if (callOpts?.signal?.aborted) break;
+      try {
+        if (ev.k === "token") callOpts?.onToken?.(ev.s);
+        else if (ev.k === "reasoning") callOpts?.onReasoningToken?.(ev.s);
+        else if (ev.k === "tool") callOpts?.onToolCallDelta?.(ev.call);
+      } catch { /* ignore sink errors */ }
+    }
+    // Behave like a live stream that was cut off.
+    callOpts?.signal?.throwIfAborted?.();
+
+    if (entry.error !== undefined) throw new Error(entry.error);
+    return entry.output ?? { text: "", toolCalls: [] };
+  }
+
+  // Re-ask structured calls exactly when the recorded run did.
+  return { chat, structuredOutput: entries.some((e) => e.request.structuredOutput) };
+}
+
+/**
+ * Apply the run's cassette mode to a freshly built driver.
+ *   ORG_LLM_REPLAY=<run-id|dir>  replay .org/runs/<run-id>/llm (or <dir>) instead of calling the model
+ *   ORG_LLM_RECORD=1             record into the current run's llm/ folder
+ */
+export function withCassette(agentId: string, driver: ChatDriver): ChatDriver {
+  const dir = replayDir();
+  if (dir) {
+    const file = cassettePath(dir, agentId);
+    Logger.info(`[cassette] ${agentId}: replaying ${file}`);
+    return makeReplayDriver(file, { strict: R.env.ORG_LLM_REPLAY_STRICT === "1" });
+  }
+
+  if (recording()) {
+    const file = cassettePath(path.join(runDir(currentRunId()), "llm"), agentId);
+    Logger.debug(`[cassette] ${agentId}: recording to ${file}`);
+    return makeRecordingDriver(driver, file);
+  }
+
+  return driver;
+}
@@ -11,6 +11,7 @@
 // Strict JSON inside; no “fixups”.
 
 import { ChatToolCall } from "./types";
+import type { ToolCallSpan } from "./toolcall-parser-registry";
 import { randomUUID as uuid } from "node:crypto";
 
 export class ToolCallParseError extends Error {
@@ -40,16 +41,22 @@ export class DeepseekToolcallParser {
 
   /** Return all parsed tool calls from every supported block in `text`. */
   parseAll(text: string): ChatToolCall[] {
+    return this.findAll(text).flatMap((s) => s.calls);
+  }
+
+  /** Every supported block in `text` that yielded a call, with its position. */
+  findAll(text: string): ToolCallSpan[] {
     const blocks = this.findToolcallBlocks(text);
-    const out: ChatToolCall[] = [];
+    const out: ToolCallSpan[] = [];
 
     blocks.forEach((b, i) => {
+      const calls: ChatToolCall[] = [];
       try {
         const value = this.strictJsonParse(b.content.trim());
         if (Array.isArray(value)) {
-          for (const v of value) out.push(this.coerceToolCall(v, i, b.start, b.end));
+          for (const v of value) calls.push(this.coerceToolCall(v, i, b.start, b.end));
         } else {
-          out.push(this.coerceToolCall(value, i, b.start, b.end));
+          calls.push(this.coerceToolCall(value, i, b.start, b.end));
         }
       } catch (e) {
         if (this.throwOnError) {
@@ -58,6 +65,7 @@ export class DeepseekToolcallParser {
         }
         // skip malformed block
       }
+      if (calls.length) out.push({ start: b.start, end: b.end, calls });
     });
 
     return out;
@@ -0,0 +1,30 @@
+// hermes-toolcall-parser.ts
+// Parse Hermes-style tool calls (NousResearch Hermes 2/3 and most fine-tunes that copied it):
+//
+//   <tool_call>
+//   {"name": "sh", "arguments": {"cmd": "ls -la"}}
+//   </tool_call>
+//
+// Several blocks may appear in one reply. A block left open at the end of the
+// text (model hit a stop token before the closer) is still parsed.
+
+import type { ChatToolCall } from "./types";
+import type { ToolCallSpan } from "./toolcall-parser-registry";
+import { toolCallsFromJsonText } from "./toolcall-json";
+
+export class HermesToolcallParser {
+  parseAll(text: string): ChatToolCall[] {
+    return this.findAll(text).flatMap((s) => s.calls);
+  }
+
+  findAll(text: string): ToolCallSpan[] {
+    const out: ToolCallSpan[] = [];
+    const re = /<tool_call>([\s\S]*?)(?:<\/tool_call>|$)/g;
+    let m: RegExpExecArray | null;
+    while ((m = re.exec(text))) {
+      const calls = toolCallsFromJsonText(m[1]);
+      if (calls.length) out.push({ start: m.index, end: re.lastIndex, calls });
+    }
+    return out;
+  }
+}
@@ -0,0 +1,55 @@
+// image-parts.ts
+// Wire encodings for ChatMessage.images, shared by the OpenAI-compatible drivers.
+// (Ollama native and Anthropic encode images in their own message converters.)
+
+import type { ChatImage, ChatMessage } from "./types";
+
+export function hasImages(messages: ChatMessage[]): boolean {
+  return messages.some((m) => Array.isArray(m.images) && m.images.length > 0);
+}
+
+/** For drivers without vision: fail loudly instead of silently dropping the picture. */
+export function rejectImages(messages: ChatMessage[], driver: string): void {
+  if (!hasImages(messages)) return;
+  const paths = messages.flatMap((m) => m.images ?? []).map((i) => i.path);
+  throw new Error(
+    `[driver:${driver}] image inputs are not supported by this driver (${paths.join(", ")}); ` +
+    `use an openai, google, ollama.native or anthropic agent with a vision model`
+  );
+}
+
+export function imageDataUrl(img: ChatImage): string {
+  return `data:${img.mimeType};base64,${img.data}`;
+}
+
+const imageNote = (images: ChatImage[]) => `[attached image${images.length > 1 ? "s" : ""}: ${images.map((i) => i.path).join(", ")}]`;
+
+const imageUrlParts = (images: ChatImage[]) =>
+  images.map((img) => ({ type: "image_url", image_url: { url: imageDataUrl(img) } }));
+
+/**
+ * OpenAI chat format: a user message with images gets array content
+ * ([text, image_url...]). Only user turns may carry images there, so images on
+ * tool results (or any other role) follow in a separate user message.
+ * Messages without images are passed through untouched.
+ */
+export function toOpenAiMessages(messages: ChatMessage[]): any[] {
+  if (!hasImages(messages)) return messages;
+
+  const out: any[] = [];
+  for (const m of messages) {
+    const images = m.images ?? [];
+    if (!images.length) {
+      out.push(m);
+      continue;
+    }
+    const { images: _images, ...rest } = m;
+    if (m.role === "user") {
+      out.push({ ...rest, content: [{ type: "text", text: String(m.content ?? "") }, ...imageUrlParts(images)] });
+    } else {
+      out.push(rest);
+      out.push({ role: "user", from: m.from, content: [{ type: "text", text: imageNote(images) }, ...imageUrlParts(images)] });
+    }
+  }
+  return out;
+}
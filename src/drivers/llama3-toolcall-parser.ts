@@ -0,0 +1,73 @@
+// llama3-toolcall-parser.ts
+// Parse Llama 3.1+ tool calls:
+//
+//   <|python_tag|>{"name": "sh", "parameters": {"cmd": "ls"}}<|eom_id|>
+//   <|python_tag|>{"name": "a", "parameters": {}}; {"name": "b", "parameters": {}}
+//   <function=sh>{"cmd": "ls"}</function>          (custom-tool format from the model card)
+//
+// The JSON after <|python_tag|> runs until <|eom_id|>, <|eot_id|> or the end of the text.
+
+import { randomUUID as uuid } from "node:crypto";
+import type { ChatToolCall } from "./types";
+import type { ToolCallSpan } from "./toolcall-parser-registry";
+import { toolCallFromJson } from "./toolcall-json";
+
+/** Split `a; b; c` on top-level semicolons only (not inside strings/objects). */
+function splitTopLevel(s: string): string[] {
+  const parts: string[] = [];
+  let depth = 0, inStr = false, esc = false, start = 0;
+  for (let i = 0; i < s.length; i++) {
+    const c = s[i];
+    if (inStr) {
+      if (esc) esc = false;
+      else if (c === "\\") esc = true;
+      else if (c === '"') inStr = false;
+      continue;
+    }
+    if (c === '"') inStr = true;
+    else if (c === "{" || c === "[") depth++;
+    else if (c === "}" || c === "]") depth--;
+    else if (c === ";" && depth === 0) {
+      parts.push(s.slice(start, i));
+      start = i + 1;
+    }
+  }
+  parts.push(s.slice(start));
+  return parts.map((p) => p.trim()).filter(Boolean);
+}
+
+export class Llama3ToolcallParser {
+  parseAll(text: string): ChatToolCall[] {
+    return this.findAll(text).flatMap((s) => s.calls);
+  }
+
+  findAll(text: string): ToolCallSpan[] {
+    const out: ToolCallSpan[] = [];
+
+    // The span takes the end-of-message token along with the JSON.
+    const tagRe = /<\|python_tag\|>([\s\S]*?)(?:<\|eom_id\|>|<\|eot_id\|>|(?=<\|python_tag\|>)|$)/g;
+    let m: RegExpExecArray | null;
+    while ((m = tagRe.exec(text))) {
+      const calls: ChatToolCall[] = [];
+      for (const piece of splitTopLevel(m[1])) {
+        try {
+          const call = toolCallFromJson(JSON.parse(piece));
+          if (call) calls.push(call);
+        } catch { /* skip malformed piece */ }
+      }
+      if (calls.length) out.push({ start: m.index, end: tagRe.lastIndex, calls });
+    }
+
+    const fnRe = /<function=([^>\s]+)>([\s\S]*?)<\/function>/g;
+    while ((m = fnRe.exec(text))) {
+      try {
+        const args = JSON.parse(m[2].trim() || "{}");
+        if (typeof args !== "object" || args === null || Array.isArray(args)) continue;
+        const call: ChatToolCall = { id: uuid(), type: "function", function: { name: m[1], arguments: JSON.stringify(args) } };
+        out.push({ start: m.index, end: fnRe.lastIndex, calls: [call] });
+      } catch { /* skip malformed block */ }
+    }
+
+    return out.sort((a, b) => a.start - b.start);
+  }
+}
@@ -0,0 +1,86 @@
+// mistral-toolcall-parser.ts
+// Parse Mistral tool calls. Two generations of the chat template are in the wild:
+//
+//   [TOOL_CALLS] [{"name": "sh", "arguments": {"cmd": "ls"}, "id": "a1b2c3d4e"}]   (v3 / Nemo)
+//   [TOOL_CALLS]sh[ARGS]{"cmd": "ls"}                                              (v11+ / Small 3.2)
+//
+// A reply may carry several [TOOL_CALLS] markers; each is parsed independently.
+
+import { randomUUID as uuid } from "node:crypto";
+import type { ChatToolCall } from "./types";
+import type { ToolCallSpan } from "./toolcall-parser-registry";
+import { toolCallsFromJsonText } from "./toolcall-json";
+
+/** Length of the first balanced JSON object/array at the start of `s` (0 if none). */
+function balancedJsonLength(s: string): number {
+  const open = s[0];
+  if (open !== "{" && open !== "[") return 0;
+  let depth = 0, inStr = false, esc = false;
+  for (let i = 0; i < s.length; i++) {
+    const c = s[i];
+    if (inStr) {
+      if (esc) esc = false;
+      else if (c === "\\") esc = true;
+      else if (c === '"') inStr = false;
+      continue;
+    }
+    if (c === '"') inStr = true;
+    else if (c === "{" || c === "[") depth++;
+    else if (c === "}" || c === "]") {
+      depth--;
+      if (depth === 0) return i + 1;
+    }
+  }
+  return 0;
+}
+
+const MARKER = "[TOOL_CALLS]";
+
+/** The calls in one segment (the text after a marker) and where the last one ends in it. */
+function parseSegment(seg: string): { calls: ChatToolCall[]; end: number } | null {
+  const args = seg.indexOf("[ARGS]");
+  const lead = seg.trimStart();
+
+  if (args >= 0 && !lead.startsWith("[") && !lead.startsWith("{")) {
+    // v11: name[ARGS]{json}
+    const name = seg.slice(0, args).trim();
+    const after = seg.slice(args + "[ARGS]".length);
+    const rest = after.trimStart();
+    const n = balancedJsonLength(rest);
+    if (!name || !n) return null;
+    try {
+      const parsed = JSON.parse(rest.slice(0, n));
+      const call: ChatToolCall = { id: uuid(), type: "function", function: { name, arguments: JSON.stringify(parsed) } };
+      return { calls: [call], end: seg.length - rest.length + n };
+    } catch {
+      return null; // malformed call
+    }
+  }
+
+  const n = balancedJsonLength(lead);
+  const calls = n ? toolCallsFromJsonText(lead.slice(0, n)) : [];
+  return calls.length ? { calls, end: seg.length - lead.length + n } : null;
+}
+
+export class MistralToolcallParser {
+  parseAll(text: string): ChatToolCall[] {
+    return this.findAll(text).flatMap((s) => s.calls);
+  }
+
+  findAll(text: string): ToolCallSpan[] {
+    const out: ToolCallSpan[] = [];
+    for (let at = text.indexOf(MARKER); at >= 0; ) {
+      const from = at + MARKER.length;
+      const next = text.indexOf(MARKER, from);
+      const seg = text.slice(from, next < 0 ? text.length : next);
+      const found = parseSegment(seg);
+      if (found) {
+        const end = from + found.end;
+        // the end-of-sequence token goes with the call
+        out.push({ start: at, end: text.startsWith("</s>", end) ? end + "</s>".length : end, calls: found.calls });
+      }
+      at = next;
+    }
+    return out;
+  }
+}
@@ -0,0 +1,196 @@
+// model-capabilities.ts
+// What an agent's model can do: context window, native tool calling, reasoning.
+//
+// Resolved once at agent creation:
+//   1. probe the server (Ollama `/api/show`, or the OpenAI-compatible `/v1/models`
+//      listing, which LM Studio / vLLM / llama.cpp / OpenRouter decorate with extras),
+//   2. fill whatever the probe did not report from the local profile table,
+//   3. fall back to the historical defaults (30k window, native tools on).
+//
+// Probing is best-effort: a short timeout, never throws, and ORG_MODEL_PROBE=0
+// turns it off (offline runs, tests).
+
+import { Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import { timedFetch } from "../utils/timed-fetch";
+
+export interface ModelCapabilities {
+  /** Usable context window in tokens. */
+  contextTokens: number;
+  /** Whether the model/server accepts a `tools` array and returns structured tool calls. */
+  nativeTools: boolean;
+  /** Whether the model emits a separate reasoning/thinking stream. */
+  reasoning: boolean;
+  /** Text tool-call parser to use when `nativeTools` is false (see toolcall-parser-registry). */
+  toolParser?: string;
+  /** Where the answer came from, for logs. */
+  source: "probe" | "profile" | "default";
+}
+
+type ProfileCaps = Partial<Omit<ModelCapabilities, "source">>;
+
+export const DEFAULT_CAPABILITIES: ModelCapabilities = {
+  contextTokens: 30_000,
+  nativeTools: true,
+  reasoning: false,
+  source: "default",
+};
+
+/**
+ * Local knowledge for servers that report nothing useful. First match wins, so
+ * more specific patterns come first. Matched against the model id, case-insensitive.
+ */
+export const MODEL_PROFILES: Array<{ match: RegExp } & ProfileCaps> = [
+  { match: /claude/, contextTokens: 200_000, nativeTools: true, reasoning: true },
+  { match: /gpt-oss/, contextTokens: 131_072, nativeTools: true, reasoning: true },
+  { match: /deepseek-r1/, contextTokens: 131_072, nativeTools: false, reasoning: true, toolParser: "deepseek" },
+  { match: /qwen3-coder/, contextTokens: 262_144, nativeTools: true, reasoning: false },
+  { match: /qwen3/, contextTokens: 40_960, nativeTools: true, reasoning: true },
+  { match: /qwen2\.5-coder/, contextTokens: 32_768, nativeTools: false, reasoning: false, toolParser: "qwen" },
+  { match: /qwen2\.5/, contextTokens: 32_768, nativeTools: true, reasoning: false },
+  { match: /llama-?3\.[123]/, contextTokens: 131_072, nativeTools: true, reasoning: false },
+  { match: /devstral|mistral|mixtral/, contextTokens: 32_768, nativeTools: true, reasoning: false },
+  { match: /gemma-?3/, contextTokens: 131_072, nativeTools: false, reasoning: false, toolParser: "gemma" },
+  { match: /gemma/, contextTokens: 8_192, nativeTools: false, reasoning: false, toolParser: "gemma" },
+];
+
+export function profileFor(model: string): ProfileCaps | undefined {
+  const id = model.toLowerCase();
+  const hit = MODEL_PROFILES.find((p) => p.match.test(id));
+  if (!hit) return undefined;
+  const { match: _match, ...caps } = hit;
+  return caps;
+}
+
+const positive = (v: unknown): number | undefined => {
+  const n = typeof v === "string" ? Number(v) : v;
+  return typeof n === "number" && Number.isFinite(n) && n > 0 ? Math.floor(n) : undefined;
+};
+
+/** Capabilities from an Ollama `/api/show` body. */
+export function parseOllamaShow(body: any): ProfileCaps {
+  const out: ProfileCaps = {};
+
+  // An explicit num_ctx in the Modelfile is what the server actually runs with.
+  const numCtx = String(body?.parameters ?? "").match(/^\s*num_ctx\s+(\d+)/m);
+  if (numCtx) out.contextTokens = positive(numCtx[1]);
+  else {
+    const info = body?.model_info ?? {};
+    const key = Object.keys(info).find((k) => k.endsWith(".context_length"));
+    if (key) out.contextTokens = positive(info[key]);
+  }
+
+  if (Array.isArray(body?.capabilities)) {
+    out.nativeTools = body.capabilities.includes("tools");
+    out.reasoning = body.capabilities.includes("thinking");
+  }
+  return out;
+}
+
+/** Capabilities from one entry of an OpenAI-compatible `/v1/models` listing. */
+export function parseModelEntry(entry: any): ProfileCaps {
+  const out: ProfileCaps = {};
+  const ctx =
+    positive(entry?.loaded_context_length) ??   // LM Studio (what is loaded right now)
+    positive(entry?.max_context_length) ??      // LM Studio
+    positive(entry?.max_model_len) ??           // vLLM
+    positive(entry?.meta?.n_ctx) ??             // llama.cpp server
+    positive(entry?.meta?.n_ctx_train) ??
+    positive(entry?.context_length) ??          // OpenRouter and friends
+    positive(entry?.top_provider?.context_length);
+  if (ctx) out.contextTokens = ctx;
+
+  const caps: unknown = entry?.capabilities;
+  if (Array.isArray(caps)) {
+    out.nativeTools = caps.includes("tool_use") || caps.includes("tools");
+    if (caps.includes("reasoning") || caps.includes("thinking")) out.reasoning = true;
+  }
+  const params: unknown = entry?.supported_parameters;
+  if (Array.isArray(params)) {
+    out.nativeTools = params.includes("tools");
+    out.reasoning = params.includes("reasoning") || params.includes("include_reasoning");
+  }
+  return out;
+}
+
+async function getJson(url: string, init: RequestInit, timeoutMs: number): Promise<any | undefined> {
+  try {
+    const res = await timedFetch(url, { ...init, timeoutMs, where: "probe:model-capabilities" });
+    if (!res.ok) return undefined;
+    return await res.json();
+  } catch (e: any) {
+    Logger.debug(`capability probe failed: ${e?.message ?? e}`);
+    return undefined;
+  }
+}
+
+export interface ProbeTarget {
+  baseUrl: string;
+  model: string;
+  /** Try Ollama's `/api/show` before `/v1/models`. */
+  ollama?: boolean;
+  /** Request headers (auth for gateways); see auth.ts. */
+  headers?: Record<string, string>;
+  /** Skip the network entirely (e.g. hosted APIs that need a key to list models). */
+  offline?: boolean;
+  timeoutMs?: number;
+}
+
+async function probe(t: ProbeTarget): Promise<ProfileCaps | undefined> {
+  const timeoutMs = t.timeoutMs ?? 3_000;
+  const root = t.baseUrl.replace(/\/+$/, "").replace(/\/v1$/, "");
+
+  if (t.ollama) {
+    const show = await getJson(`${root}/api/show`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json", ...t.headers },
+      body: JSON.stringify({ model: t.model }),
+    }, timeoutMs);
+    if (show) return parseOllamaShow(show);
+  }
+
+  const list = await getJson(`${root}/v1/models`, { method: "GET", headers: t.headers }, timeoutMs);
+  const data: any[] = Array.isArray(list?.data) ? list.data : [];
+  const want = t.model.toLowerCase();
+  const entry = data.find((m) => String(m?.id ?? "").toLowerCase() === want);
+  return entry ? parseModelEntry(entry) : undefined;
+}
+
+const cache = new Map<string, Promise<ModelCapabilities>>();
+
+/** Resolve capabilities for one model; cached per endpoint+model for the process lifetime. */
+export function probeModelCapabilities(t: ProbeTarget): Promise<ModelCapabilities> {
+  const key = `${t.baseUrl}|${t.model}`;
+  let hit = cache.get(key);
+  if (!hit) {
+    hit = resolve(t);
+    cache.set(key, hit);
+  }
+  return hit;
+}
+
+async function resolve(t: ProbeTarget): Promise<ModelCapabilities> {
+  const enabled = !t.offline && R.env.ORG_MODEL_PROBE !== "0";
+  const probed = enabled ? await probe(t) : undefined;
+  const profile = profileFor(t.model);
+
+  const defined = (c?: ProfileCaps) =>
+    Object.fromEntries(Object.entries(c ?? {}).filter(([, v]) => v !== undefined)) as ProfileCaps;
+
+  const caps: ModelCapabilities = {
+    ...DEFAULT_CAPABILITIES,
+    ...defined(profile),
+    ...defined(probed),
+    source: probed && Object.keys(defined(probed)).length ? "probe" : profile ? "profile" : "default",
+  };
+  // A server that says "no tools" gets a text parser even when the profile had none.
+  if (!caps.nativeTools && !caps.toolParser) caps.toolParser = "hermes";
+
+  Logger.debug(`capabilities ${t.model}`, caps);
+  return caps;
+}
+
+/** Test hook: forget cached probe results. */
+export function clearCapabilityCache(): void {
+  cache.clear();
+}
@@ -0,0 +1,61 @@
+// qwen-toolcall-parser.ts
+// Parse Qwen tool calls. Qwen2.5 / Qwen3 use the Hermes JSON form:
+//
+//   <tool_call>
+//   {"name": "sh", "arguments": {"cmd": "ls"}}
+//   </tool_call>
+//
+// Qwen3-Coder uses an XML body instead of JSON:
+//
+//   <tool_call>
+//   <function=sh>
+//   <parameter=cmd>
+//   ls -la
+//   </parameter>
+//   </function>
+//   </tool_call>
+//
+// XML parameter values are kept as strings unless they are JSON objects or arrays.
+
+import { randomUUID as uuid } from "node:crypto";
+import type { ChatToolCall } from "./types";
+import type { ToolCallSpan } from "./toolcall-parser-registry";
+import { stringifyStable, toolCallsFromJsonText } from "./toolcall-json";
+
+function parseXmlFunction(body: string): ChatToolCall[] {
+  const out: ChatToolCall[] = [];
+  const fnRe = /<function=([^>\s]+)>([\s\S]*?)(?:<\/function>|$)/g;
+  let f: RegExpExecArray | null;
+  while ((f = fnRe.exec(body))) {
+    const args: Record<string, unknown> = {};
+    const pRe = /<parameter=([^>\s]+)>\n?([\s\S]*?)\n?<\/parameter>/g;
+    let p: RegExpExecArray | null;
+    while ((p = pRe.exec(f[2]))) {
+      const raw = p[2];
+      let value: unknown = raw;
+      try { value = JSON.parse(raw); } catch { /* plain string */ }
+      // Only structured values are decoded; "007" or "true" typed as text stays text.
+      args[p[1]] = typeof value === "object" && value !== null ? value : raw;
+    }
+    out.push({ id: uuid(), type: "function", function: { name: f[1], arguments: stringifyStable(args) } });
+  }
+  return out;
+}
+
+export class QwenToolcallParser {
+  parseAll(text: string): ChatToolCall[] {
+    return this.findAll(text).flatMap((s) => s.calls);
+  }
+
+  findAll(text: string): ToolCallSpan[] {
+    const out: ToolCallSpan[] = [];
+    const re = /<tool_call>([\s\S]*?)(?:<\/tool_call>|$)/g;
+    let m: RegExpExecArray | null;
+    while ((m = re.exec(text))) {
+      const body = m[1];
+      const calls = /<function=/.test(body) ? parseXmlFunction(body) : toolCallsFromJsonText(body);
+      if (calls.length) out.push({ start: m.index, end: re.lastIndex, calls });
+    }
+    return out;
+  }
+}
@@ -0,0 +1,185 @@
+// retry-driver.ts
+//
+// Retry + failover around one or more ChatDrivers.
+//
+// - Each target (endpoint/model) is tried with exponential backoff on HTTP 429/5xx
+//   and on connection failures (refused/reset/socket closed).
+// - Retries only happen before the first streamed delta: once tokens have reached the
+//   caller, replaying the request would duplicate output, so the error is surfaced.
+// - When a target exhausts its retries the next one in the list is used, wrapping
+//   around to the primary when the later ones fail too. The last healthy target
+//   stays preferred for `cooldownMs`, after which the primary is tried first again
+//   (so a restarted LM Studio instance is picked back up).
+// - Credentials are per target: a failover endpoint on another host never sees the
+//   primary's key or headers (see auth.ts resolveEndpointAuth).
+
+import { Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import { sleep } from "../utils/sleep";
+import { resolveEndpointAuth, type DriverAuth } from "./auth";
+import type { ChatDriver, ChatMessage, ChatOutput } from "./types";
+
+export interface RetryPolicy {
+  /** Retries per target after the first attempt. */
+  maxRetries: number;
+  baseDelayMs: number;
+  maxDelayMs: number;
+  /** How long a failed-over-to target stays preferred before the primary is retried. */
+  cooldownMs: number;
+}
+
+export const DEFAULT_RETRY_POLICY: RetryPolicy = {
+  maxRetries: 2,
+  baseDelayMs: 500,
+  maxDelayMs: 30_000,
+  cooldownMs: 5 * 60_000,
+};
+
+export interface DriverTarget {
+  baseUrl: string;
+  model: string;
+  /** Key and headers for this endpoint only. */
+  auth?: DriverAuth;
+}
+
+export type FailoverTarget = DriverTarget & { driver: ChatDriver };
+
+const CONNECTION_CODES = new Set([
+  "ECONNRESET", "ECONNREFUSED", "EPIPE", "ETIMEDOUT", "EHOSTUNREACH", "ENETUNREACH", "EAI_AGAIN",
+  "UND_ERR_SOCKET", "UND_ERR_CONNECT_TIMEOUT", "ConnectionRefused", "ConnectionClosed",
+]);
+
+const CONNECTION_RE = /fetch failed|socket hang up|ECONNRESET|ECONNREFUSED|connection (?:reset|refused|closed)|unable to connect/i;
+
+/** 429/5xx from the driver, or a transport failure anywhere in the cause chain. */
+export function isRetryableChatError(e: any): boolean {
+  for (let cur = e, depth = 0; cur && depth < 5; cur = cur.cause, depth++) {
+    const status = Number(cur.status);
+    if (status === 429 || (status >= 500 && status <= 599)) return true;
+    if (typeof cur.code === "string" && CONNECTION_CODES.has(cur.code)) return true;
+    if (typeof cur.message === "string" && CONNECTION_RE.test(cur.message)) return true;
+  }
+  return false;
+}
+
+export function backoffDelay(attempt: number, policy: RetryPolicy, random: () => number = Math.random): number {
+  const ceiling = Math.min(policy.maxDelayMs, policy.baseDelayMs * 2 ** attempt);
+  // "Equal jitter": at least half the ceiling so retries never stampede at 0ms.
+  return Math.round(ceiling / 2 + random() * (ceiling / 2));
+}
+
+export function makeRetryingDriver(
+  targets: FailoverTarget[],
+  policy: RetryPolicy = DEFAULT_RETRY_POLICY,
+  label = "llm"
+): ChatDriver {
+  if (targets.length === 0) throw new Error("retry driver needs at least one target");
+
+  let preferred = 0;
+  let preferredSince = 0;
+
+  async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    if (preferred !== 0 && Date.now() - preferredSince > policy.cooldownMs) preferred = 0;
+
+    let streamed = false;
+    const mark = <A extends any[]>(fn?: (...a: A) => void) =>
+      (...a: A) => { streamed = true; fn?.(...a); };
+
+    // From the preferred target to the end of the list, then back round from the primary.
+    const order = targets.map((_, i) => (preferred + i) % targets.length);
+
+    let lastErr: unknown;
+    for (let i = 0; i < order.length; i++) {
+      const t = order[i];
+      const target = targets[t];
+      const callOpts = {
+        ...opts,
+        // Failover targets may serve a different model than the agent's primary.
+        model: t === 0 ? (opts?.model ?? target.model) : target.model,
+        onToken: mark(opts?.onToken),
+        onReasoningToken: mark(opts?.onReasoningToken),
+        onToolCallDelta: mark(opts?.onToolCallDelta),
+      };
+
+      for (let attempt = 0; attempt <= policy.maxRetries; attempt++) {
+        try {
+          const out = await target.driver.chat(messages, callOpts);
+          if (t !== preferred) {
+            preferred = t;
+            preferredSince = Date.now();
+          }
+          return out;
+        } catch (e: any) {
+          lastErr = e;
+          if (streamed || opts?.signal?.aborted || !isRetryableChatError(e)) throw e;
+          if (attempt === policy.maxRetries) break;
+
+          const delay = backoffDelay(attempt, policy);
+          Logger.warn(`[${label}] ${target.model} @ ${target.baseUrl} failed (${e?.status ?? e?.message ?? e}); retry ${attempt + 1}/${policy.maxRetries} in ${delay}ms`);
+          await sleep(delay);
+        }
+      }
+
+      if (i + 1 < order.length) {
+        const nxt = targets[order[i + 1]];
+        Logger.warn(`[${label}] giving up on ${target.model} @ ${target.baseUrl}; failing over to ${nxt.model} @ ${nxt.baseUrl}`);
+      }
+    }
+    throw lastErr;
+  }
+
+  // A failover target without schema support may answer any call.
+  return { chat, structuredOutput: targets.every((t) => t.driver.structuredOutput) };
+}
+
+function envInt(name: string, def: number): number {
+  const v = R.env[name];
+  if (v === undefined || v.trim() === "") return def;
+  const n = Number(v);
+  return Number.isFinite(n) && n >= 0 ? Math.floor(n) : def;
+}
+
+/** Policy from ORG_LLM_RETRIES / ORG_LLM_RETRY_BASE_MS / ORG_LLM_RETRY_MAX_MS / ORG_LLM_FAILOVER_COOLDOWN_MS. */
+export function retryPolicyFromEnv(): RetryPolicy {
+  return {
+    maxRetries: envInt("ORG_LLM_RETRIES", DEFAULT_RETRY_POLICY.maxRetries),
+    baseDelayMs: envInt("ORG_LLM_RETRY_BASE_MS", DEFAULT_RETRY_POLICY.baseDelayMs),
+    maxDelayMs: envInt("ORG_LLM_RETRY_MAX_MS", DEFAULT_RETRY_POLICY.maxDelayMs),
+    cooldownMs: envInt("ORG_LLM_FAILOVER_COOLDOWN_MS", DEFAULT_RETRY_POLICY.cooldownMs),
+  };
+}
+
+/**
+ * Parse a failover list: comma-separated `model@baseUrl` entries.
+ * Either side may be omitted (`model` keeps the primary endpoint, `@url` keeps the model).
+ */
+export function parseFailoverList(spec: string | undefined, primary: DriverTarget): DriverTarget[] {
+  if (!spec) return [];
+  return spec.split(",").map((s) => s.trim()).filter(Boolean).map((entry) => {
+    const at = entry.indexOf("@");
+    const model = (at >= 0 ? entry.slice(0, at) : entry).trim();
+    const baseUrl = (at >= 0 ? entry.slice(at + 1) : "").trim();
+    return { model: model || primary.model, baseUrl: baseUrl || primary.baseUrl };
+  });
+}
+
+/**
+ * Build the agent's driver chain: primary target plus failovers from
+ * ORG_LLM_FAILOVER_<AGENT> (or ORG_LLM_FAILOVER for every agent). `make` must
+ * authenticate with `t.auth`, which is scoped to each target's endpoint.
+ * Drivers for another API than the shared list's OpenAI-compatible endpoints
+ * (Anthropic) pass `sharedFailover: false` and fail over only to their own list.
+ */
+export function withRetries(
+  agentId: string,
+  primary: DriverTarget,
+  make: (t: DriverTarget) => ChatDriver,
+  opts: { sharedFailover?: boolean } = {}
+): ChatDriver {
+  const key = `ORG_LLM_FAILOVER_${agentId.toUpperCase().replace(/[^A-Z0-9]/g, "_")}`;
+  const shared = opts.sharedFailover === false ? undefined : R.env.ORG_LLM_FAILOVER;
+  const failovers = parseFailoverList(R.env[key] ?? shared, primary)
+    .map((t) => ({ ...t, auth: resolveEndpointAuth(t.baseUrl, primary) }));
+  const targets: FailoverTarget[] = [primary, ...failovers].map((t) => ({ ...t, driver: make(t) }));
+  return makeRetryingDriver(targets, retryPolicyFromEnv(), agentId);
+}
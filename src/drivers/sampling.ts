@@ -0,0 +1,32 @@
+// sampling.ts
+// Per-agent sampling parameters and their wire names per API.
+// Unset fields are left out of the request so the server default applies.
+
+export interface SamplingParams {
+  temperature?: number;
+  topP?: number;
+  seed?: number;
+  /** Cap on generated tokens per call. */
+  maxTokens?: number;
+  /** Stop sequences. */
+  stop?: string[];
+}
+
+function defined(o: Record<string, unknown>): Record<string, unknown> {
+  return Object.fromEntries(Object.entries(o).filter(([, v]) => v !== undefined && !(Array.isArray(v) && v.length === 0)));
+}
+
+/** OpenAI chat completions (LM Studio, Ollama /v1, vLLM, llama.cpp, OpenRouter). */
+export function openAiSampling(s: SamplingParams): Record<string, unknown> {
+  return defined({ temperature: s.temperature, top_p: s.topP, seed: s.seed, max_tokens: s.maxTokens, stop: s.stop });
+}
+
+/** Ollama native `options`. */
+export function ollamaSampling(s: SamplingParams): Record<string, unknown> {
+  return defined({ temperature: s.temperature, top_p: s.topP, seed: s.seed, num_predict: s.maxTokens, stop: s.stop });
+}
+
+/** Anthropic Messages API. It has no seed; `max_tokens` is required and set by the driver. */
+export function anthropicSampling(s: SamplingParams): Record<string, unknown> {
+  return defined({ temperature: s.temperature, top_p: s.topP, stop_sequences: s.stop });
+}
@@ -0,0 +1,263 @@
+// scenario-driver.ts
+//
+// A scripted stand-in for a model, for deterministic end-to-end tests without a
+// model server. Each chat() call consumes the next step of a scenario: it checks
+// what the agent was sent, waits if asked to, and answers with canned text and
+// tool calls. The agent around it is a real LlmAgent, so routing (@@tags,
+// ##file blocks), tool execution, guardrails and patch review all run for real.
+//
+//   version: 1
+//   agent: alice                  # optional; must match the agent using it
+//   steps:
+//     - expect: { from: User, contains: "fix the test" }
+//       say: Looking.
+//       tools:
+//         - sh: bun test          # shorthand for { name: sh, args: { cmd: ... } }
+//     - expect: { role: tool, matches: '"exit_code":\s*1' }
+//       delayMs: 200
+//       say: |
+//         ##file:src/slug.ts
+//         export const slug = (s: string) => s.toLowerCase();
+//     - say: "@@user fixed"
+//
+// Use it with `driver: mock` and the scenario file as the model, e.g.
+// `--agents "alice^test/scenarios/fix.yaml^mock"`.
+//
+// `expect` passes when one message that is new since the previous call meets all
+// of its conditions. A failed expectation, or a call with no step left, throws a
+// ScenarioError naming the file and step; `failures` keeps them for tests.
+// Memory side-calls (structured output) get a minimal valid answer and no step.
+
+import * as fs from "fs";
+import * as path from "path";
+import { parseYaml } from "../config/yaml";
+import { Logger } from "../logger";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall, JsonSchema } from "./types";
+
+export const SCENARIO_VERSION = 1;
+
+/** Conditions one message new since the previous call must meet together. */
+export type ScenarioExpect = {
+  /** user, tool, system. */
+  role?: string;
+  /** User, an agent id, System. */
+  from?: string;
+  contains?: string;
+  notContains?: string;
+  /** Regular expression tested against the content. */
+  matches?: string;
+};
+
+export type ScenarioStep = {
+  expect?: ScenarioExpect;
+  delayMs?: number;
+  say?: string;
+  reasoning?: string;
+  tools?: { name: string; args: Record<string, unknown> }[];
+};
+
+export type Scenario = { source: string; agent?: string; steps: ScenarioStep[] };
+
+export class ScenarioError extends Error {
+  constructor(readonly source: string, readonly field: string, message: string) {
+    super(field ? `${source}: ${field}: ${message}` : `${source}: ${message}`);
+    this.name = "ScenarioError";
+  }
+}
+
+type Obj = Record<string, unknown>;
+
+function isObj(v: unknown): v is Obj {
+  return typeof v === "object" && v !== null && !Array.isArray(v);
+}
+
+/** Validate a parsed scenario document; errors name the file and field. */
+export function scenarioFromObject(doc: unknown, source: string): Scenario {
+  const fail = (field: string, msg: string): never => { throw new ScenarioError(source, field, msg); };
+  const known = (o: Obj, field: string, keys: string[]) => {
+    for (const k of Object.keys(o)) {
+      if (!keys.includes(k)) fail(field ? `${field}.${k}` : k, `unknown field (known: ${keys.join(", ")})`);
+    }
+  };
+  const str = (v: unknown, field: string): string => (typeof v === "string" ? v : fail(field, "expected a string"));
+
+  if (!isObj(doc)) return fail("", "expected a mapping with version and steps");
+  known(doc, "", ["version", "agent", "steps"]);
+  if (doc.version !== SCENARIO_VERSION) fail("version", `expected ${SCENARIO_VERSION}`);
+  if (!Array.isArray(doc.steps) || doc.steps.length === 0) fail("steps", "expected a non-empty list of steps");
+
+  const steps = (doc.steps as unknown[]).map((raw, i): ScenarioStep => {
+    const f = `steps[${i}]`;
+    if (!isObj(raw)) return fail(f, "expected a mapping");
+    known(raw, f, ["expect", "delayMs", "say", "reasoning", "tools"]);
+    const step: ScenarioStep = {};
+    if (raw.expect !== undefined) {
+      if (!isObj(raw.expect)) fail(`${f}.expect`, "expected a mapping");
+      const e = raw.expect as Obj;
+      known(e, `${f}.expect`, ["role", "from", "contains", "notContains", "matches"]);
+      step.expect = {};
+      for (const k of ["role", "from", "contains", "notContains", "matches"] as const) {
+        if (e[k] !== undefined) step.expect[k] = str(e[k], `${f}.expect.${k}`);
+      }
+      if (step.expect.matches !== undefined) {
+        try { new RegExp(step.expect.matches); } catch (err) { fail(`${f}.expect.matches`, `invalid regular expression (${(err as Error).message})`); }
+      }
+    }
+    if (raw.delayMs !== undefined) {
+      if (typeof raw.delayMs !== "number" || raw.delayMs < 0) fail(`${f}.delayMs`, "expected a number >= 0");
+      step.delayMs = raw.delayMs as number;
+    }
+    if (raw.say !== undefined) step.say = str(raw.say, `${f}.say`);
+    if (raw.reasoning !== undefined) step.reasoning = str(raw.reasoning, `${f}.reasoning`);
+    if (raw.tools !== undefined) {
+      if (!Array.isArray(raw.tools)) fail(`${f}.tools`, "expected a list of tool calls");
+      step.tools = (raw.tools as unknown[]).map((t, j) => {
+        const tf = `${f}.tools[${j}]`;
+        if (!isObj(t)) return fail(tf, "expected { sh: cmd } or { name, args }");
+        if ("sh" in t) {
+          known(t, tf, ["sh"]);
+          return { name: "sh", args: { cmd: str(t.sh, `${tf}.sh`) } };
+        }
+        known(t, tf, ["name", "args"]);
+        const args = t.args ?? {};
+        if (!isObj(args)) fail(`${tf}.args`, "expected a mapping");
+        return { name: str(t.name, `${tf}.name`), args: args as Obj };
+      });
+    }
+    if (step.say === undefined && !step.tools?.length) fail(f, "a step needs `say`, `tools` or both");
+    return step;
+  });
+
+  return { source, agent: doc.agent === undefined ? undefined : str(doc.agent, "agent"), steps };
+}
+
+/** Read a scenario file (.yaml, .yml or .json). */
+export function loadScenario(file: string): Scenario {
+  let text: string;
+  try {
+    text = fs.readFileSync(file, "utf8");
+  } catch {
+    throw new ScenarioError(file, "", "cannot read scenario file");
+  }
+  let doc: unknown;
+  if (path.extname(file).toLowerCase() === ".json") {
+    try { doc = JSON.parse(text); } catch (e) { throw new ScenarioError(file, "", `invalid JSON (${(e as Error).message})`); }
+  } else {
+    doc = parseYaml(text, file);
+  }
+  return scenarioFromObject(doc, file);
+}
+
+/** Whether a mock agent's model names a scenario file rather than the built-in rotation. */
+export function isScenarioPath(model: string): boolean {
+  return /\.(ya?ml|json)$/i.test(model);
+}
+
+const keyOf = (m: ChatMessage) => `${m.role}\u0000${m.from}\u0000${m.tool_call_id ?? ""}\u0000${m.content}`;
+
+function describeAll(ms: ChatMessage[]): string {
+  if (!ms.length) return "no new messages";
+  return ms.slice(-3).map(m => {
+    const text = String(m.content ?? "");
+    return `${m.role} from ${m.from || "?"}: ${JSON.stringify(text.length > 200 ? text.slice(0, 200) + "…" : text)}`;
+  }).join("; ");
+}
+
+/** The smallest value that satisfies `schema`, for structured side-calls the script doesn't cover. */
+function placeholderFor(schema: JsonSchema): unknown {
+  if (schema.enum?.length) return schema.enum[0];
+  const type = Array.isArray(schema.type) ? schema.type[0] : schema.type;
+  switch (type) {
+    case "object": {
+      const out: Record<string, unknown> = {};
+      for (const k of schema.required ?? []) out[k] = placeholderFor(schema.properties?.[k] ?? {});
+      return out;
+    }
+    case "array": return Array.from({ length: schema.minItems ?? 0 }, () => placeholderFor(schema.items ?? {}));
+    case "string": return "x".repeat(schema.minLength ?? 0);
+    case "number": case "integer": return schema.minimum ?? 0;
+    case "boolean": return false;
+    default: return null;
+  }
+}
+
+function abortError(): Error {
+  const e = new Error("aborted");
+  e.name = "AbortError";
+  return e;
+}
+
+function delay(ms: number, signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve, reject) => {
+    if (signal?.aborted) return reject(abortError());
+    const t = setTimeout(() => { signal?.removeEventListener("abort", onAbort); resolve(); }, ms);
+    const onAbort = () => { clearTimeout(t); reject(abortError()); };
+    signal?.addEventListener("abort", onAbort, { once: true });
+  });
+}
+
+export class ScenarioDriver implements ChatDriver {
+  /** Every expectation that failed, in order (each also threw). */
+  readonly failures: string[] = [];
+  private next = 0;
+  private readonly seen = new Set<string>();
+
+  constructor(readonly scenario: Scenario, readonly agentId?: string) {
+    if (scenario.agent && agentId && scenario.agent !== agentId) {
+      throw new ScenarioError(scenario.source, "agent", `written for "${scenario.agent}", used by "${agentId}"`);
+    }
+  }
+
+  /** Whether every step has been played. */
+  done(): boolean {
+    return this.next >= this.scenario.steps.length;
+  }
+
+  async chat(messages: ChatMessage[], opts?: Parameters<ChatDriver["chat"]>[1]): Promise<ChatOutput> {
+    if (opts?.responseFormat) return { text: JSON.stringify(placeholderFor(opts.responseFormat.schema)), toolCalls: [] };
+
+    // Memory orders messages by lane, so "what the agent was just sent" is what is new since the last call.
+    const fresh = messages.filter(m => !this.seen.has(keyOf(m)));
+    for (const m of messages) this.seen.add(keyOf(m));
+
+    const i = this.next++;
+    const step = this.scenario.steps[i];
+    if (!step) this.fail("", `no step left for call ${i + 1} (the scenario has ${this.scenario.steps.length}); got ${describeAll(fresh)}`);
+
+    this.check(step.expect, fresh, `steps[${i}].expect`);
+    if (step.delayMs) await delay(step.delayMs, opts?.signal);
+
+    const toolCalls: ChatToolCall[] = (step.tools ?? []).map((t, j) => ({
+      id: `scenario-${i}-${j}`,
+      type: "function",
+      function: { name: t.name, arguments: JSON.stringify(t.args) },
+    }));
+    if (step.reasoning) opts?.onReasoningToken?.(step.reasoning);
+    if (step.say) opts?.onToken?.(step.say);
+    for (const c of toolCalls) opts?.onToolCallDelta?.(c);
+    return { text: step.say ?? "", toolCalls, ...(step.reasoning ? { reasoning: step.reasoning } : {}) };
+  }
+
+  /** Passes when one new message meets every condition of `e`. */
+  private check(e: ScenarioExpect | undefined, fresh: ChatMessage[], field: string): void {
+    if (!e) return;
+    const matches = (m: ChatMessage) => {
+      const text = String(m.content ?? "");
+      return (e.role === undefined || m.role === e.role)
+        && (e.from === undefined || (m.from ?? "").toLowerCase() === e.from.toLowerCase())
+        && (e.contains === undefined || text.includes(e.contains))
+        && (e.notContains === undefined || !text.includes(e.notContains))
+        && (e.matches === undefined || new RegExp(e.matches).test(text));
+    };
+    if (fresh.some(matches)) return;
+    const want = Object.entries(e).map(([k, v]) => `${k} ${JSON.stringify(v)}`).join(", ");
+    this.fail(field, `no new message with ${want}; got ${describeAll(fresh)}`);
+  }
+
+  private fail(field: string, msg: string): never {
+    const err = new ScenarioError(this.scenario.source, field, msg);
+    this.failures.push(err.message);
+    Logger.error(err.message);
+    throw err;
+  }
+}
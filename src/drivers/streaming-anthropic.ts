@@ -0,0 +1,429 @@
+// streaming-anthropic.ts
+import { Logger } from "../logger";
+import { rateLimiter } from "../utils/rate-limiter";
+import { timedFetch } from "../utils/timed-fetch";
+import { type DriverAuth, redactHeaders } from "./auth";
+
+import type { ChatDriver, ChatMessage, ChatOutput, ChatThinkingBlock, ChatToolCall, ChatUsage } from "./types";
+import { StreamClock, usageFromPayload } from "./usage";
+import { anthropicSampling, type SamplingParams } from "./sampling";
+
+interface AnthropicDriverConfig extends DriverAuth {
+  baseUrl: string;    // e.g. https://api.anthropic.com
+  model: string;
+  /**
+   * If set, enables extended thinking with this budget (streamed via onReasoningToken).
+   * `max_tokens` must exceed it, so a smaller output cap is raised to budget + cap.
+   */
+  thinkingBudgetTokens?: number;
+  /** Per-agent sampling; `maxTokens` is the required output cap (default 4096). No seed in this API. */
+  sampling?: SamplingParams;
+  /** Value for the `anthropic-version` header. */
+  apiVersion?: string;
+  timeoutMs?: number; // default 2h (aligns with the OpenAI-compatible drivers)
+}
+
+/** Give the event loop a chance to run key handlers / UI. */
+function yieldToLoop(): Promise<void> {
+  return new Promise<void>((resolve) =>
+    typeof (globalThis as any).setImmediate === "function"
+      ? (globalThis as any).setImmediate(resolve)
+      : setTimeout(resolve, 0)
+  );
+}
+
+/** Cooperative scheduler: yield after ~1 KiB processed or ~8ms elapsed. */
+class YieldBudget {
+  private bytesSince = 0;
+  private last = Date.now();
+  constructor(
+    private readonly byteBudget = 1024,
+    private readonly msBudget = 8
+  ) { }
+  async maybe(extraBytes = 0): Promise<void> {
+    this.bytesSince += extraBytes;
+    const now = Date.now();
+    if (this.bytesSince >= this.byteBudget || (now - this.last) >= this.msBudget) {
+      this.bytesSince = 0;
+      this.last = now;
+      await yieldToLoop();
+    }
+  }
+}
+
+type AnthropicBlock =
+  | ChatThinkingBlock
+  | { type: "text"; text: string }
+  | { type: "tool_use"; id: string; name: string; input: unknown }
+  | { type: "tool_result"; tool_use_id: string; content: string | AnthropicBlock[] }
+  | { type: "image"; source: { type: "base64"; media_type: string; data: string } };
+
+type AnthropicMessage = { role: "user" | "assistant"; content: AnthropicBlock[] };
+
+/** Convert OpenAI-style tool definitions ({type:"function", function:{...}}) to Anthropic tools. */
+export function toAnthropicTools(tools: any[]): any[] {
+  return tools
+    .map((t) => t?.function ?? t)
+    .filter((f) => f && typeof f.name === "string")
+    .map((f) => ({
+      name: f.name,
+      description: f.description ?? "",
+      input_schema: f.parameters ?? { type: "object", properties: {} },
+    }));
+}
+
+function parseArgs(s: string | undefined): unknown {
+  try { return JSON.parse(s || "{}"); } catch { return {}; }
+}
+
+/**
+ * Map our flat ChatMessage[] onto the Messages API shape:
+ *  - system messages are hoisted into the top-level `system` string
+ *  - role:"tool" becomes a `tool_result` block, but only when it answers a `tool_use`
+ *    we emitted earlier (the API rejects orphans); otherwise it is inlined as text
+ *  - an assistant turn's thinking blocks go back first, ahead of its text and tool_use
+ *    (with thinking on, the API rejects a tool_result whose turn lost them)
+ *  - consecutive messages with the same role are merged into one turn
+ *  - images become `image` blocks (inside the tool_result when they came with one)
+ */
+export function toAnthropicMessages(messages: ChatMessage[]): { system: string; messages: AnthropicMessage[] } {
+  const system: string[] = [];
+  const out: AnthropicMessage[] = [];
+  const knownToolUseIds = new Set<string>();
+
+  const push = (role: "user" | "assistant", block: AnthropicBlock) => {
+    const last = out[out.length - 1];
+    if (last && last.role === role) last.content.push(block);
+    else out.push({ role, content: [block] });
+  };
+
+  for (const m of messages) {
+    const content = String(m.content ?? "");
+    const images: AnthropicBlock[] = (m.images ?? []).map((i) => ({
+      type: "image",
+      source: { type: "base64", media_type: i.mimeType, data: i.data },
+    }));
+    if (m.role === "system") {
+      if (content.trim()) system.push(content);
+      // The system prompt is text-only; show the pictures in the conversation instead.
+      for (const b of images) push("user", b);
+      continue;
+    }
+
+    if (m.role === "assistant") {
+      for (const b of m.thinking ?? []) push("assistant", { ...b });
+      if (content.trim()) push("assistant", { type: "text", text: content });
+      const calls: ChatToolCall[] = Array.isArray((m as any).tool_calls) ? (m as any).tool_calls : [];
+      for (const c of calls) {
+        knownToolUseIds.add(c.id);
+        push("assistant", { type: "tool_use", id: c.id, name: c.function.name, input: parseArgs(c.function.arguments) });
+      }
+      // Assistant turns cannot carry images; they follow as user content.
+      for (const b of images) push("user", b);
+      continue;
+    }
+
+    if (m.role === "tool") {
+      if (m.tool_call_id && knownToolUseIds.has(m.tool_call_id)) {
+        const result = images.length ? [{ type: "text" as const, text: content }, ...images] : content;
+        push("user", { type: "tool_result", tool_use_id: m.tool_call_id, content: result });
+      } else {
+        push("user", { type: "text", text: `[tool result${m.name ? ` ${m.name}` : ""}] ${content}` });
+        for (const b of images) push("user", b);
+      }
+      continue;
+    }
+
+    if (content.trim()) push("user", { type: "text", text: content });
+    for (const b of images) push("user", b);
+  }
+
+  return { system: system.join("\n\n"), messages: out };
+}
+
+/**
+ * Streaming Anthropic Messages API driver.
+ * Streams tokens via optional callbacks while returning the final ChatOutput.
+ *
+ * Extra opts supported (all optional, ignored if unused):
+ *   - model?: string
+ *   - tools?: any[]           (OpenAI-style definitions; converted to input_schema)
+ *   - onToken?(t: string): void
+ *   - onReasoningToken?(t: string): void   (thinking_delta)
+ *   - onToolCallDelta?(delta: ChatToolCall): void   (input_json_delta)
+ *   - signal?: AbortSignal
+ *   (responseFormat is not sent: the Messages API has no schema mode; callers validate locally)
+ */
+export function makeStreamingAnthropic(cfg: AnthropicDriverConfig): ChatDriver {
+  const base = cfg.baseUrl.replace(/\/+$/, "");
+  const endpoint = `${base}/v1/messages`;
+  const defaultTimeout = cfg.timeoutMs ?? 2 * 60 * 60 * 1000;
+  const thinkingBudget = cfg.thinkingBudgetTokens && cfg.thinkingBudgetTokens > 0 ? cfg.thinkingBudgetTokens : 0;
+  const outputCap = cfg.sampling?.maxTokens ?? 4096;
+  const maxTokens = thinkingBudget && outputCap <= thinkingBudget ? thinkingBudget + outputCap : outputCap;
+
+  async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    await rateLimiter.limit("llm-ask", 1);
+    Logger.debug("streaming messages out", messages);
+
+    const controller = new AbortController();
+    const userSignal: AbortSignal | undefined = opts?.signal;
+    const linkAbort = () => controller.abort();
+    if (userSignal) {
+      if (userSignal.aborted) controller.abort();
+      else userSignal.addEventListener("abort", linkAbort, { once: true });
+    }
+    const timer = setTimeout(() => controller.abort(), defaultTimeout);
+
+    const model = opts?.model ?? cfg.model;
+    const tools = Array.isArray(opts?.tools) && opts.tools.length ? toAnthropicTools(opts.tools) : undefined;
+
+    const onToken: ((t: string) => void) | undefined = opts?.onToken;
+    const onReasoningToken: ((t: string) => void) | undefined = opts?.onReasoningToken;
+    const onToolCallDelta: ((t: ChatToolCall) => void) | undefined = opts?.onToolCallDelta;
+
+    const t0 = Date.now();
+    const clock = new StreamClock(t0);
+    let usage: ChatUsage | undefined;
+    const converted = toAnthropicMessages(messages);
+
+    Logger.debug("POST /v1/messages (stream)", {
+      model,
+      messages: converted.messages.length,
+      systemChars: converted.system.length,
+      tools: tools ? tools.length : 0,
+      timeoutMs: defaultTimeout
+    });
+
+    const payload: any = {
+      model,
+      messages: converted.messages,
+      max_tokens: maxTokens,
+      stream: true
+    };
+    if (converted.system) payload.system = converted.system;
+    if (tools) {
+      payload.tools = tools;
+      payload.tool_choice = { type: "auto" };
+    }
+    Object.assign(payload, anthropicSampling(cfg.sampling ?? {}));
+    if (thinkingBudget) {
+      payload.thinking = { type: "enabled", budget_tokens: thinkingBudget };
+    }
+
+    const headers: Record<string, string> = {
+      "Content-Type": "application/json",
+      "anthropic-version": cfg.apiVersion ?? "2023-06-01",
+    };
+    if (cfg.apiKey) headers["x-api-key"] = cfg.apiKey;
+    Object.assign(headers, cfg.headers);
+    Logger.debug("headers", redactHeaders(headers));
+
+    try {
+      const res = await timedFetch(endpoint, {
+        method: "POST",
+        headers,
+        body: JSON.stringify(payload),
+        signal: controller.signal,
+        where: "driver:anthropic:stream",
+        timeoutMs: defaultTimeout
+      });
+
+      Logger.debug("resp(stream)", { status: res.status, ms: Date.now() - t0 });
+
+      if (!res.ok) {
+        const text = await res.text().catch(() => "");
+        const err = new Error(`Anthropic messages (stream) failed (${res.status}): ${text}`);
+        (err as any).status = res.status;
+        throw err;
+      }
+
+      // Non-streaming JSON fallback (e.g. a proxy that ignores stream:true)
+      const ct = (res.headers.get("content-type") || "").toLowerCase();
+      if (!ct.includes("text/event-stream")) {
+        const data = await res.json().catch(() => ({}));
+        const blocks: any[] = Array.isArray(data?.content) ? data.content : [];
+        let text = "";
+        let reasoning = "";
+        const thinking: ChatThinkingBlock[] = [];
+        const toolCalls: ChatToolCall[] = [];
+        for (const b of blocks) {
+          if (b?.type === "text" && typeof b.text === "string") text += b.text;
+          else if (b?.type === "thinking" && typeof b.thinking === "string") {
+            reasoning += b.thinking;
+            thinking.push({ type: "thinking", thinking: b.thinking, signature: String(b.signature ?? "") });
+          } else if (b?.type === "redacted_thinking" && typeof b.data === "string") thinking.push({ type: "redacted_thinking", data: b.data });
+          else if (b?.type === "tool_use") {
+            toolCalls.push({ id: String(b.id ?? ""), type: "function", function: { name: String(b.name ?? ""), arguments: JSON.stringify(b.input ?? {}) } });
+          }
+        }
+        if (text && onToken) onToken(text);
+        return { text, reasoning: reasoning || undefined, ...(thinking.length ? { thinking } : {}), toolCalls, usage: usageFromPayload(data?.usage), timing: clock.timing() };
+      }
+
+      // SSE streaming path
+      const decoder = new TextDecoder("utf-8");
+      const yb = new YieldBudget(1024, 8); // 1 KiB or 8ms
+      let buf = "";
+      let fullText = "";
+      let fullReasoning = "";
+
+      // Content blocks are addressed by index; tool_use and thinking blocks are kept here.
+      const toolByIndex = new Map<number, ChatToolCall>();
+      const thinkingByIndex = new Map<number, ChatThinkingBlock>();
+
+      const emitChunks = async (s: string, sink?: (t: string) => void) => {
+        if (!sink) { await yb.maybe(s.length); return; }
+        while (s.length) {
+          const piece = s.slice(0, 512);
+          s = s.slice(512);
+          try { sink(piece); } catch { /* ignore sink errors */ }
+          await yb.maybe(piece.length);
+        }
+      };
+
+      const pumpEvent = async (rawEvent: string) => {
+        const dataLines = rawEvent
+          .split("\n")
+          .map((l) => l.trim())
+          .filter((l) => l.startsWith("data:"))
+          .map((l) => l.replace(/^data:\s?/, ""));
+
+        if (!dataLines.length) return;
+        const joined = dataLines.join("\n").trim();
+        if (!joined) return;
+
+        let ev: any;
+        try {
+          ev = JSON.parse(joined);
+        } catch {
+          return; // ignore malformed
+        }
+
+        switch (ev?.type) {
+          case "message_start": {
+            usage = usageFromPayload(ev.message?.usage) ?? usage;
+            return;
+          }
+          case "message_delta": {
+            // Cumulative output count for the whole message.
+            const u = usageFromPayload(ev.usage);
+            if (u?.completionTokens !== undefined) usage = { ...usage, completionTokens: u.completionTokens };
+            return;
+          }
+          case "content_block_start": {
+            const block = ev.content_block ?? {};
+            if (block.type === "tool_use" || (block.type === "text" && block.text)) clock.mark();
+            if (block.type === "tool_use") {
+              const call: ChatToolCall = {
+                id: String(block.id ?? ""),
+                type: "function",
+                function: { name: String(block.name ?? ""), arguments: "" }
+              };
+              toolByIndex.set(Number(ev.index ?? 0), call);
+              if (onToolCallDelta) {
+                try { onToolCallDelta(call); } catch { /* ignore */ }
+              }
+            } else if (block.type === "thinking") {
+              thinkingByIndex.set(Number(ev.index ?? 0), { type: "thinking", thinking: String(block.thinking ?? ""), signature: String(block.signature ?? "") });
+            } else if (block.type === "redacted_thinking") {
+              thinkingByIndex.set(Number(ev.index ?? 0), { type: "redacted_thinking", data: String(block.data ?? "") });
+            } else if (block.type === "text" && typeof block.text === "string" && block.text) {
+              fullText += block.text;
+              await emitChunks(block.text, onToken);
+            }
+            return;
+          }
+          case "content_block_delta": {
+            const d = ev.delta ?? {};
+            clock.mark();
+            if (d.type === "text_delta" && typeof d.text === "string") {
+              fullText += d.text;
+              await emitChunks(d.text, onToken);
+            } else if (d.type === "thinking_delta" && typeof d.thinking === "string") {
+              fullReasoning += d.thinking;
+              const block = thinkingByIndex.get(Number(ev.index ?? 0));
+              if (block?.type === "thinking") block.thinking += d.thinking;
+              await emitChunks(d.thinking, onReasoningToken);
+            } else if (d.type === "signature_delta" && typeof d.signature === "string") {
+              const block = thinkingByIndex.get(Number(ev.index ?? 0));
+              if (block?.type === "thinking") block.signature += d.signature;
+            } else if (d.type === "input_json_delta" && typeof d.partial_json === "string") {
+              const call = toolByIndex.get(Number(ev.index ?? 0));
+              if (!call) return;
+              call.function.arguments += d.partial_json;
+              if (onToolCallDelta) {
+                try { onToolCallDelta(call); } catch { /* ignore */ }
+              }
+              await yb.maybe(64); // small budget for tool deltas
+            }
+            return;
+          }
+          case "error": {
+            const msg = ev.error?.message ?? JSON.stringify(ev.error ?? ev);
+            throw new Error(`Anthropic stream error: ${msg}`);
+          }
+          default:
+            // content_block_stop, message_stop, ping
+            return;
+        }
+      };
+
+      // Stream reader: WHATWG or Node stream
+      const body: any = res.body;
+
+      if (body && typeof body.getReader === "function") {
+        const reader = body.getReader();
+        while (true) {
+          const { value, done } = await reader.read();
+          if (done) break;
+          buf += decoder.decode(value, { stream: true }).replace(/\r\n/g, "\n");
+          await yb.maybe((value as Uint8Array)?.byteLength ?? 0);
+
+          let sepIdx: number;
+          while ((sepIdx = buf.indexOf("\n\n")) !== -1) {
+            const rawEvent = buf.slice(0, sepIdx).trim();
+            buf = buf.slice(sepIdx + 2);
+            if (rawEvent) await pumpEvent(rawEvent);
+          }
+        }
+        if (buf.trim()) await pumpEvent(buf.trim());
+      } else if (body && typeof body[Symbol.asyncIterator] === "function") {
+        for await (const chunk of body as AsyncIterable<Uint8Array>) {
+          const bytes = chunk as Uint8Array;
+          buf += decoder.decode(bytes, { stream: true }).replace(/\r\n/g, "\n");
+          await yb.maybe(bytes.byteLength);
+
+          let sepIdx: number;
+          while ((sepIdx = buf.indexOf("\n\n")) !== -1) {
+            const rawEvent = buf.slice(0, sepIdx).trim();
+            buf = buf.slice(sepIdx + 2);
+            if (rawEvent) await pumpEvent(rawEvent);
+          }
+        }
+        if (buf.trim()) await pumpEvent(buf.trim());
+      } else {
+        const txt = (await res.text()).replace(/\r\n/g, "\n");
+        for (const part of txt.split("\n\n").map((s) => s.trim())) {
+          if (part) await pumpEvent(part);
+        }
+      }
+
+      const toolCalls: ChatToolCall[] = Array.from(toolByIndex.entries())
+        .sort((a, b) => a[0] - b[0])
+        .map(([, v]) => ({ ...v, function: { ...v.function, arguments: v.function.arguments || "{}" } }));
+
+      const thinking = Array.from(thinkingByIndex.entries()).sort((a, b) => a[0] - b[0]).map(([, v]) => v);
+
+      return { text: fullText, reasoning: fullReasoning || undefined, ...(thinking.length ? { thinking } : {}), toolCalls, usage, timing: clock.timing() };
+    } catch (e: any) {
+      if (e?.name === "AbortError") Logger.debug("timeout(stream)", { ms: defaultTimeout });
+      throw e;
+    } finally {
+      clearTimeout(timer);
+      if (userSignal) userSignal.removeEventListener("abort", linkAbort);
+    }
+  }
+
+  return { chat };
+}
@@ -2,14 +2,23 @@
 import { Logger } from "../logger";
 import { rateLimiter } from "../utils/rate-limiter";
 import { timedFetch } from "../utils/timed-fetch";
-import { GemmaToolcallParser } from "./gemma-toolcall-parser";
+import { type DriverAuth, redactHeaders, requestHeaders } from "./auth";
+import { toOpenAiMessages } from "./image-parts";
+import { extractToolCalls, getToolCallParser } from "./toolcall-parser-registry";
 
-import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall } from "./types";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall, ChatUsage } from "./types";
+import { StreamClock, usageFromPayload } from "./usage";
+import { toOpenAiResponseFormat } from "./structured-output";
+import { openAiSampling, type SamplingParams } from "./sampling";
 
-interface GoogleDriverConfig {
+interface GoogleDriverConfig extends DriverAuth {
   baseUrl: string;    // e.g. http://127.0.0.1:11434
   model: string;
   timeoutMs?: number; // default 2h (aligns with non-streaming driver)
+  /** Text tool-call parser name (see toolcall-parser-registry); default "gemma". */
+  toolParser?: string;
+  /** Per-agent sampling (temperature, top_p, seed, max_tokens, stop); server defaults when unset. */
+  sampling?: SamplingParams;
 }
 
 /** Give the event loop a chance to run key handlers / UI. */
@@ -51,14 +60,16 @@ class YieldBudget {
  *   - onReasoningToken?(t: string): void
  *   - onToolCallDelta?(delta: ChatToolCall): void
  *   - signal?: AbortSignal
+ *   - responseFormat?: ResponseFormat   (sent as response_format json_schema)
  */
 export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver {
   const base = cfg.baseUrl.replace(/\/+$/, "");
   const endpoint = `${base}/v1/chat/completions`;
   const defaultTimeout = cfg.timeoutMs ?? 2 * 60 * 60 * 1000;
+  const headers = requestHeaders(cfg);
 
   async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
-    const parser = new GemmaToolcallParser();
+    const parser = getToolCallParser(cfg.toolParser ?? "gemma");
 
     await rateLimiter.limit("llm-ask", 1);
     Logger.debug("streaming messages out", messages);
@@ -80,6 +91,8 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
     const onToolCallDelta: ((t: ChatToolCall) => void) | undefined = opts?.onToolCallDelta;
 
     const t0 = Date.now();
+    const clock = new StreamClock(t0);
+    let usage: ChatUsage | undefined;
     const approxChars =
       Array.isArray(messages)
         ? messages.reduce((s, m) => s + String((m as any).content ?? "").length, 0)
@@ -87,6 +100,7 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
 
     Logger.debug("POST /chat (stream)", {
       model,
+      headers: redactHeaders(headers),
       messages: Array.isArray(messages) ? messages.length : 0,
       approxChars,
       tools: tools ? tools.length : 0,
@@ -96,9 +110,12 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
     // Base payload for google-compatible streaming
     const payload: any = {
       model,
-      messages,
-      stream: true
+      messages: toOpenAiMessages(messages),
+      stream: true,
+      stream_options: { include_usage: true }
     };
+    Object.assign(payload, openAiSampling(cfg.sampling ?? {}));
+    if (opts?.responseFormat) payload.response_format = toOpenAiResponseFormat(opts.responseFormat);
     if (tools) {
       payload.tools = tools;
       payload.tool_choice = "auto";
@@ -107,7 +124,7 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
     try {
       const res = await timedFetch(endpoint, {
         method: "POST",
-        headers: { "Content-Type": "application/json" },
+        headers,
         body: JSON.stringify(payload),
         signal: controller.signal,
         where: "driver:openai-lmstudio:stream",
@@ -118,7 +135,9 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
 
       if (!res.ok) {
         const text = await res.text().catch(() => "");
-        throw new Error(`LM Studio / OpenAI chat (stream) failed (${res.status}): ${text}`);
+        const err = new Error(`LM Studio / OpenAI chat (stream) failed (${res.status}): ${text}`);
+        (err as any).status = res.status;
+        throw err;
       }
 
       // Some servers may fall back to non-streaming JSON despite stream:true
@@ -129,9 +148,9 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
         const msg = choice?.message || {};
         const content = typeof msg?.content === "string" ? msg.content : "";
         const nativeToolCalls: ChatToolCall[] = Array.isArray(msg?.tool_calls) ? msg.tool_calls : [];
-        const structuredToolCalls: ChatToolCall[] = parser.parseAll(content);
+        const fromText = extractToolCalls(parser, content);
         if (content && onToken) onToken(content);
-        return { text: content, reasoning: msg?.reasoning || undefined, toolCalls: nativeToolCalls.concat(structuredToolCalls) };
+        return { text: fromText.text, reasoning: msg?.reasoning || undefined, toolCalls: nativeToolCalls.concat(fromText.toolCalls), usage: usageFromPayload(data?.usage), timing: clock.timing() };
       }
 
       // SSE streaming path
@@ -164,8 +183,13 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
           return; // ignore malformed
         }
 
+        // Final chunk (stream_options.include_usage) carries usage and no choices.
+        const reported = usageFromPayload(payload?.usage);
+        if (reported) usage = reported;
+
         const delta = payload?.choices?.[0]?.delta;
         if (!delta) return;
+        if (delta.content || delta.reasoning || delta.tool_calls) clock.mark();
 
         // Content tokens (may arrive as large CoT chunks; stream cooperatively)
         if (typeof delta.content === "string" && delta.content.length) {
@@ -280,9 +304,9 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
       const toolCalls: ChatToolCall[] = Array.from(toolByIndex.entries())
         .sort((a, b) => a[0] - b[0])
         .map(([, v]) => v);
-      const structuredToolCalls: ChatToolCall[] = parser.parseAll(fullText);
+      const fromText = extractToolCalls(parser, fullText);
 
-      return { text: fullText, reasoning: fullReasoning || undefined, toolCalls: toolCalls.concat(structuredToolCalls) };
+      return { text: fromText.text, reasoning: fullReasoning || undefined, toolCalls: toolCalls.concat(fromText.toolCalls), usage, timing: clock.timing() };
     } catch (e: any) {
       if (e?.name === "AbortError") Logger.debug("timeout(stream)", { ms: defaultTimeout });
       throw e;
@@ -292,5 +316,5 @@ export function makeStreamingGoogleLmStudio(cfg: GoogleDriverConfig): ChatDriver
     }
   }
 
-  return { chat };
+  return { chat, structuredOutput: true };
 }
\ No newline at end of file
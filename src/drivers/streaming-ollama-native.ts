@@ -0,0 +1,328 @@
+// streaming-ollama-native.ts
+import { Logger } from "../logger";
+import { rateLimiter } from "../utils/rate-limiter";
+import { timedFetch } from "../utils/timed-fetch";
+import { type DriverAuth, redactHeaders, requestHeaders } from "./auth";
+
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall, ChatUsage } from "./types";
+import { StreamClock } from "./usage";
+import { extractToolCalls, getToolCallParser } from "./toolcall-parser-registry";
+import { ollamaSampling, type SamplingParams } from "./sampling";
+
+/** Subset of Ollama's model `options` we expose; anything else is passed through untouched. */
+export interface OllamaOptions {
+  num_ctx?: number;
+  temperature?: number;
+  seed?: number;
+  [k: string]: unknown;
+}
+
+interface OllamaDriverConfig extends DriverAuth {
+  baseUrl: string;    // e.g. http://192.168.5.2:11434 (a trailing /v1 is stripped)
+  model: string;
+  options?: OllamaOptions;
+  /** Per-agent sampling; merged over `options` (temperature, top_p, seed, num_predict, stop). */
+  sampling?: SamplingParams;
+  /** How long the server keeps the model loaded after the request, e.g. "5m" or -1. */
+  keepAlive?: string | number;
+  timeoutMs?: number; // default 2h (aligns with the OpenAI-compatible drivers)
+  /** Text tool-call parser name (see toolcall-parser-registry); merged with native tool_calls. */
+  toolParser?: string;
+}
+
+/** Give the event loop a chance to run key handlers / UI. */
+function yieldToLoop(): Promise<void> {
+  return new Promise<void>((resolve) =>
+    typeof (globalThis as any).setImmediate === "function"
+      ? (globalThis as any).setImmediate(resolve)
+      : setTimeout(resolve, 0)
+  );
+}
+
+/** Cooperative scheduler: yield after ~1 KiB processed or ~8ms elapsed. */
+class YieldBudget {
+  private bytesSince = 0;
+  private last = Date.now();
+  constructor(
+    private readonly byteBudget = 1024,
+    private readonly msBudget = 8
+  ) { }
+  async maybe(extraBytes = 0): Promise<void> {
+    this.bytesSince += extraBytes;
+    const now = Date.now();
+    if (this.bytesSince >= this.byteBudget || (now - this.last) >= this.msBudget) {
+      this.bytesSince = 0;
+      this.last = now;
+      await yieldToLoop();
+    }
+  }
+}
+
+type OllamaToolCall = { function: { name: string; arguments: Record<string, unknown> } };
+
+type OllamaMessage = {
+  role: "system" | "user" | "assistant" | "tool";
+  content: string;
+  tool_calls?: OllamaToolCall[];
+  tool_name?: string;
+  images?: string[];          // base64, for vision models
+};
+
+function parseArgs(s: string | undefined): Record<string, unknown> {
+  try {
+    const v = JSON.parse(s || "{}");
+    return v && typeof v === "object" && !Array.isArray(v) ? v : {};
+  } catch {
+    return {};
+  }
+}
+
+/** Ollama's native API lives at the server root, not under the OpenAI-compatible /v1 prefix. */
+export function ollamaNativeBase(baseUrl: string): string {
+  return baseUrl.replace(/\/+$/, "").replace(/\/v1$/, "");
+}
+
+/**
+ * Map our ChatMessage[] onto /api/chat messages:
+ *  - assistant tool calls carry their arguments as objects, not JSON strings
+ *  - tool results name the tool via `tool_name` (the native API has no call ids)
+ *  - images ride on user messages as base64 `images`; images attached to any
+ *    other role (e.g. a tool result) follow in their own user message
+ */
+export function toOllamaMessages(messages: ChatMessage[]): OllamaMessage[] {
+  return messages.flatMap((m): OllamaMessage[] => {
+    const content = String(m.content ?? "");
+    const images = (m.images ?? []).map((i) => i.data);
+    const followUp: OllamaMessage[] = images.length
+      ? [{ role: "user", content: `[attached images: ${(m.images ?? []).map((i) => i.path).join(", ")}]`, images }]
+      : [];
+
+    if (m.role === "assistant") {
+      const out: OllamaMessage = { role: "assistant", content };
+      const calls: ChatToolCall[] = Array.isArray((m as any).tool_calls) ? (m as any).tool_calls : [];
+      if (calls.length) {
+        out.tool_calls = calls.map((c) => ({ function: { name: c.function.name, arguments: parseArgs(c.function.arguments) } }));
+      }
+      return [out, ...followUp];
+    }
+    if (m.role === "tool") {
+      return [m.name ? { role: "tool", content, tool_name: m.name } : { role: "tool", content }, ...followUp];
+    }
+    if (m.role === "system") return [{ role: "system", content }, ...followUp];
+    return [images.length ? { role: "user", content, images } : { role: "user", content }];
+  });
+}
+
+/**
+ * Streaming driver for Ollama's native /api/chat endpoint (NDJSON, one JSON object per line).
+ * Streams tokens via optional callbacks while returning the final ChatOutput.
+ *
+ * Extra opts supported (all optional, ignored if unused):
+ *   - model?: string
+ *   - tools?: any[]           (OpenAI-style definitions are accepted as-is)
+ *   - onToken?(t: string): void
+ *   - onReasoningToken?(t: string): void   (message.thinking)
+ *   - onToolCallDelta?(delta: ChatToolCall): void
+ *   - signal?: AbortSignal
+ *   - responseFormat?: ResponseFormat   (schema sent as `format`)
+ */
+export function makeStreamingOllamaNative(cfg: OllamaDriverConfig): ChatDriver {
+  const endpoint = `${ollamaNativeBase(cfg.baseUrl)}/api/chat`;
+  const defaultTimeout = cfg.timeoutMs ?? 2 * 60 * 60 * 1000;
+  const headers = requestHeaders(cfg);
+
+  async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    const parser = cfg.toolParser ? getToolCallParser(cfg.toolParser) : null;
+
+    await rateLimiter.limit("llm-ask", 1);
+    Logger.debug("streaming messages out", messages);
+
+    const controller = new AbortController();
+    const userSignal: AbortSignal | undefined = opts?.signal;
+    const linkAbort = () => controller.abort();
+    if (userSignal) {
+      if (userSignal.aborted) controller.abort();
+      else userSignal.addEventListener("abort", linkAbort, { once: true });
+    }
+    const timer = setTimeout(() => controller.abort(), defaultTimeout);
+
+    const model = opts?.model ?? cfg.model;
+    const tools = Array.isArray(opts?.tools) && opts.tools.length ? opts.tools : undefined;
+
+    const onToken: ((t: string) => void) | undefined = opts?.onToken;
+    const onReasoningToken: ((t: string) => void) | undefined = opts?.onReasoningToken;
+    const onToolCallDelta: ((t: ChatToolCall) => void) | undefined = opts?.onToolCallDelta;
+
+    const t0 = Date.now();
+    const clock = new StreamClock(t0);
+
+    const options = { ...cfg.options, ...ollamaSampling(cfg.sampling ?? {}) };
+
+    Logger.debug("POST /api/chat (stream)", {
+      model,
+      headers: redactHeaders(headers),
+      messages: messages.length,
+      tools: tools ? tools.length : 0,
+      options,
+      keepAlive: cfg.keepAlive,
+      timeoutMs: defaultTimeout
+    });
+
+    const payload: any = {
+      model,
+      messages: toOllamaMessages(messages),
+      stream: true
+    };
+    if (tools) payload.tools = tools;
+    // Ollama takes the bare JSON schema as `format` (structured outputs).
+    if (opts?.responseFormat) payload.format = opts.responseFormat.schema;
+    if (Object.keys(options).length) payload.options = options;
+    if (cfg.keepAlive !== undefined) payload.keep_alive = cfg.keepAlive;
+
+    try {
+      const res = await timedFetch(endpoint, {
+        method: "POST",
+        headers,
+        body: JSON.stringify(payload),
+        signal: controller.signal,
+        where: "driver:ollama-native:stream",
+        timeoutMs: 2 * 60 * 60 * 1000
+      });
+
+      Logger.debug("resp(stream)", { status: res.status, ms: Date.now() - t0 });
+
+      if (!res.ok) {
+        const text = await res.text().catch(() => "");
+        const err = new Error(`Ollama chat (stream) failed (${res.status}): ${text}`);
+        (err as any).status = res.status;
+        throw err;
+      }
+
+      // NDJSON streaming path. A server that ignores stream:true returns a single
+      // JSON object, which is just a one-line NDJSON stream.
+      const decoder = new TextDecoder("utf-8");
+      const yb = new YieldBudget(1024, 8); // 1 KiB or 8ms
+      let buf = "";
+      let fullText = "";
+      let fullReasoning = "";
+      const toolCalls: ChatToolCall[] = [];
+      let usage: ChatUsage | undefined;
+
+      const emitChunks = async (s: string, sink?: (t: string) => void) => {
+        if (!sink) { await yb.maybe(s.length); return; }
+        while (s.length) {
+          const piece = s.slice(0, 512);
+          s = s.slice(512);
+          try { sink(piece); } catch { /* ignore sink errors */ }
+          await yb.maybe(piece.length);
+        }
+      };
+
+      const pumpLine = async (line: string) => {
+        let obj: any;
+        try {
+          obj = JSON.parse(line);
+        } catch {
+          return; // ignore malformed
+        }
+
+        if (obj?.error) {
+          throw new Error(`Ollama stream error: ${typeof obj.error === "string" ? obj.error : JSON.stringify(obj.error)}`);
+        }
+
+        const msg = obj?.message ?? {};
+        if (msg.thinking || msg.content || (Array.isArray(msg.tool_calls) && msg.tool_calls.length)) clock.mark();
+        if (typeof msg.thinking === "string" && msg.thinking) {
+          fullReasoning += msg.thinking;
+          await emitChunks(msg.thinking, onReasoningToken);
+        }
+        if (typeof msg.content === "string" && msg.content) {
+          fullText += msg.content;
+          await emitChunks(msg.content, onToken);
+        }
+        // Tool calls arrive whole (arguments already an object), never as fragments.
+        if (Array.isArray(msg.tool_calls)) {
+          for (const tc of msg.tool_calls) {
+            const fn = tc?.function ?? {};
+            const args = fn.arguments;
+            const call: ChatToolCall = {
+              id: String(tc?.id ?? `call_${toolCalls.length}`),
+              type: "function",
+              function: {
+                name: String(fn.name ?? ""),
+                arguments: typeof args === "string" ? (args || "{}") : JSON.stringify(args ?? {})
+              }
+            };
+            toolCalls.push(call);
+            if (onToolCallDelta) {
+              try { onToolCallDelta(call); } catch { /* ignore */ }
+            }
+          }
+        }
+
+        if (obj?.done) {
+          const prompt = Number(obj.prompt_eval_count);
+          const completion = Number(obj.eval_count);
+          if (Number.isFinite(prompt) || Number.isFinite(completion)) {
+            usage = {
+              promptTokens: Number.isFinite(prompt) ? prompt : undefined,
+              completionTokens: Number.isFinite(completion) ? completion : undefined,
+            };
+          }
+          Logger.debug("ollama done", { reason: obj.done_reason, usage, totalNs: obj.total_duration });
+        }
+      };
+
+      const drainLines = async () => {
+        let nl: number;
+        while ((nl = buf.indexOf("\n")) !== -1) {
+          const line = buf.slice(0, nl).trim();
+          buf = buf.slice(nl + 1);
+          if (line) await pumpLine(line);
+        }
+      };
+
+      // Stream reader: WHATWG or Node stream
+      const body: any = res.body;
+
+      if (body && typeof body.getReader === "function") {
+        const reader = body.getReader();
+        while (true) {
+          const { value, done } = await reader.read();
+          if (done) break;
+          buf += decoder.decode(value, { stream: true });
+          await yb.maybe((value as Uint8Array)?.byteLength ?? 0);
+          await drainLines();
+        }
+      } else if (body && typeof body[Symbol.asyncIterator] === "function") {
+        for await (const chunk of body as AsyncIterable<Uint8Array>) {
+          const bytes = chunk as Uint8Array;
+          buf += decoder.decode(bytes, { stream: true });
+          await yb.maybe(bytes.byteLength);
+          await drainLines();
+        }
+      } else {
+        buf = await res.text();
+        await drainLines();
+      }
+      if (buf.trim()) await pumpLine(buf.trim());
+
+      let text = fullText;
+      if (parser) {
+        const fromText = extractToolCalls(parser, fullText);
+        toolCalls.push(...fromText.toolCalls);
+        text = fromText.text;
+      }
+
+      return { text, reasoning: fullReasoning || undefined, toolCalls, usage, timing: clock.timing() };
+    } catch (e: any) {
+      if (e?.name === "AbortError") Logger.debug("timeout(stream)", { ms: defaultTimeout });
+      throw e;
+    } finally {
+      clearTimeout(timer);
+      if (userSignal) userSignal.removeEventListener("abort", linkAbort);
+    }
+  }
+
+  return { chat, structuredOutput: true };
+}
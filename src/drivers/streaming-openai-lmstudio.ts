@@ -2,13 +2,23 @@
 import { Logger } from "../logger";
 import { rateLimiter } from "../utils/rate-limiter";
 import { timedFetch } from "../utils/timed-fetch";
+import { type DriverAuth, redactHeaders, requestHeaders } from "./auth";
+import { toOpenAiMessages } from "./image-parts";
 
-import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall } from "./types";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall, ChatUsage } from "./types";
+import { StreamClock, usageFromPayload } from "./usage";
+import { toOpenAiResponseFormat } from "./structured-output";
+import { openAiSampling, type SamplingParams } from "./sampling";
+import { extractToolCalls, getToolCallParser } from "./toolcall-parser-registry";
 
-interface OpenAiDriverConfig {
+interface OpenAiDriverConfig extends DriverAuth {
   baseUrl: string;    // e.g. http://127.0.0.1:11434
   model: string;
   timeoutMs?: number; // default 2h (aligns with non-streaming driver)
+  /** Text tool-call parser name (see toolcall-parser-registry); merged with native tool_calls. */
+  toolParser?: string;
+  /** Per-agent sampling (temperature, top_p, seed, max_tokens, stop); server defaults when unset. */
+  sampling?: SamplingParams;
 }
 
 /** Give the event loop a chance to run key handlers / UI. */
@@ -53,13 +63,17 @@ const sanitizeCtrl = (s: string) =>
  *   - onReasoningToken?(t: string): void
  *   - onToolCallDelta?(delta: ChatToolCall): void
  *   - signal?: AbortSignal
+ *   - responseFormat?: ResponseFormat   (sent as response_format json_schema)
  */
 export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver {
   const base = cfg.baseUrl.replace(/\/+$/, "");
   const endpoint = `${base}/v1/chat/completions`;
   const defaultTimeout = cfg.timeoutMs ?? 2 * 60 * 60 * 1000;
+  const headers = requestHeaders(cfg);
 
   async function chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    const parser = cfg.toolParser ? getToolCallParser(cfg.toolParser) : null;
+
     await rateLimiter.limit("llm-ask", 1);
     Logger.debug("streaming messages out", messages);
 
@@ -80,6 +94,8 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
     const onToolCallDelta: ((t: ChatToolCall) => void) | undefined = opts?.onToolCallDelta;
 
     const t0 = Date.now();
+    const clock = new StreamClock(t0);
+    let usage: ChatUsage | undefined;
     const approxChars =
       Array.isArray(messages)
         ? messages.reduce((s, m) => s + String((m as any).content ?? "").length, 0)
@@ -87,6 +103,7 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
 
     Logger.debug("POST /chat (stream)", {
       model,
+      headers: redactHeaders(headers),
       messages: Array.isArray(messages) ? messages.length : 0,
       approxChars,
       tools: tools ? tools.length : 0,
@@ -96,9 +113,12 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
     // Base payload for OpenAI-compatible streaming
     const payload: any = {
       model,
-      messages,
-      stream: true
+      messages: toOpenAiMessages(messages),
+      stream: true,
+      stream_options: { include_usage: true }
     };
+    Object.assign(payload, openAiSampling(cfg.sampling ?? {}));
+    if (opts?.responseFormat) payload.response_format = toOpenAiResponseFormat(opts.responseFormat);
     if (tools) {
       payload.tools = tools;
       payload.tool_choice = "auto";
@@ -107,7 +127,7 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
     try {
       const res = await timedFetch(endpoint, {
         method: "POST",
-        headers: { "Content-Type": "application/json" },
+        headers,
         body: JSON.stringify(payload),
         signal: controller.signal,
         where: "driver:openai-lmstudio:stream",
@@ -118,7 +138,9 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
 
       if (!res.ok) {
         const text = await res.text().catch(() => "");
-        throw new Error(`LM Studio / OpenAI chat (stream) failed (${res.status}): ${text}`);
+        const err = new Error(`LM Studio / OpenAI chat (stream) failed (${res.status}): ${text}`);
+        (err as any).status = res.status;
+        throw err;
       }
 
       // Some servers may fall back to non-streaming JSON despite stream:true
@@ -130,7 +152,8 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
         const content = typeof msg?.content === "string" ? msg.content : "";
         const toolCalls: ChatToolCall[] = Array.isArray(msg?.tool_calls) ? msg.tool_calls : [];
         if (content && onToken) onToken(content);
-        return { text: content, reasoning: msg?.reasoning || undefined, toolCalls };
+        const fromText = parser ? extractToolCalls(parser, content) : { text: content, toolCalls: [] };
+        return { text: fromText.text, reasoning: msg?.reasoning || undefined, toolCalls: toolCalls.concat(fromText.toolCalls), usage: usageFromPayload(data?.usage), timing: clock.timing() };
       }
 
       // SSE streaming path
@@ -163,8 +186,13 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
           return; // ignore malformed
         }
 
+        // Final chunk (stream_options.include_usage) carries usage and no choices.
+        const reported = usageFromPayload(payload?.usage);
+        if (reported) usage = reported;
+
         const delta = payload?.choices?.[0]?.delta;
         if (!delta) return;
+        if (delta.content || delta.reasoning || delta.tool_calls) clock.mark();
 
         // Content tokens (may arrive as large CoT chunks; stream cooperatively)
         if (typeof delta.content === "string" && delta.content.length) {
@@ -280,7 +308,9 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
         .sort((a, b) => a[0] - b[0])
         .map(([, v]) => v);
 
-      return { text: fullText, reasoning: fullReasoning || undefined, toolCalls };
+      const fromText = parser ? extractToolCalls(parser, fullText) : { text: fullText, toolCalls: [] };
+
+      return { text: fromText.text, reasoning: fullReasoning || undefined, toolCalls: toolCalls.concat(fromText.toolCalls), usage, timing: clock.timing() };
     } catch (e: any) {
       if (e?.name === "AbortError") Logger.debug("timeout(stream)", { ms: defaultTimeout });
       throw e;
@@ -290,5 +320,5 @@ export function makeStreamingOpenAiLmStudio(cfg: OpenAiDriverConfig): ChatDriver
     }
   }
 
-  return { chat };
+  return { chat, structuredOutput: true };
 }
\ No newline at end of file
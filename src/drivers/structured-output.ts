@@ -0,0 +1,137 @@
+// structured-output.ts
+// JSON-schema constrained replies.
+//
+// Drivers map `opts.responseFormat` onto the wire (OpenAI `response_format`,
+// Ollama `format`) and say so with `ChatDriver.structuredOutput`. The reply is
+// validated here either way. Only a driver that sends the schema gets one re-ask
+// with the validation errors; for the rest a prose reply is the answer, and
+// callers fall back to it as free text.
+
+import { Logger } from "../logger";
+import type { ChatDriver, ChatMessage, ChatOutput, JsonSchema, ResponseFormat } from "./types";
+
+/** OpenAI-compatible `response_format` body (LM Studio, vLLM, llama.cpp, Ollama /v1). */
+export function toOpenAiResponseFormat(fmt: ResponseFormat) {
+  return {
+    type: "json_schema",
+    json_schema: { name: fmt.name, schema: fmt.schema, ...(fmt.strict !== undefined ? { strict: fmt.strict } : {}) },
+  };
+}
+
+/** Parse a JSON reply, tolerating code fences and chatter around a single object/array. */
+export function parseJsonText(text: string): unknown {
+  const trimmed = text.trim().replace(/^```(?:json)?\s*([\s\S]*?)\s*```$/i, "$1");
+  try {
+    return JSON.parse(trimmed);
+  } catch { /* fall through */ }
+  const m = trimmed.match(/\{[\s\S]*\}|\[[\s\S]*\]/);
+  if (!m) return undefined;
+  try {
+    return JSON.parse(m[0]);
+  } catch {
+    return undefined;
+  }
+}
+
+function typeOf(v: unknown): string {
+  if (v === null) return "null";
+  if (Array.isArray(v)) return "array";
+  if (typeof v === "number") return Number.isInteger(v) ? "integer" : "number";
+  return typeof v;
+}
+
+/**
+ * Validate `value` against the subset of JSON Schema we use for model output:
+ * type, enum, properties/required/additionalProperties, items, min/max(Length|Items), minimum/maximum.
+ * Returns human-readable errors (empty when valid) that can be fed back to the model.
+ */
+export function validateJson(value: unknown, schema: JsonSchema, at = "$"): string[] {
+  const errors: string[] = [];
+  const actual = typeOf(value);
+
+  if (schema.type !== undefined) {
+    const allowed = Array.isArray(schema.type) ? schema.type : [schema.type];
+    const ok = allowed.some((t) => t === actual || (t === "number" && actual === "integer"));
+    if (!ok) return [`${at}: expected ${allowed.join("|")}, got ${actual}`];
+  }
+  if (schema.enum && !schema.enum.some((e) => JSON.stringify(e) === JSON.stringify(value))) {
+    errors.push(`${at}: must be one of ${schema.enum.map((e) => JSON.stringify(e)).join(", ")}`);
+  }
+
+  if (actual === "object") {
+    const obj = value as Record<string, unknown>;
+    for (const key of schema.required ?? []) {
+      if (!(key in obj)) errors.push(`${at}.${key}: required`);
+    }
+    for (const [key, v] of Object.entries(obj)) {
+      const sub = schema.properties?.[key];
+      if (sub) errors.push(...validateJson(v, sub, `${at}.${key}`));
+      else if (schema.additionalProperties === false) errors.push(`${at}.${key}: not allowed`);
+    }
+  } else if (actual === "array") {
+    const arr = value as unknown[];
+    if (schema.minItems !== undefined && arr.length < schema.minItems) errors.push(`${at}: at least ${schema.minItems} items`);
+    if (schema.maxItems !== undefined && arr.length > schema.maxItems) errors.push(`${at}: at most ${schema.maxItems} items`);
+    if (schema.items) arr.forEach((v, i) => errors.push(...validateJson(v, schema.items!, `${at}[${i}]`)));
+  } else if (actual === "string") {
+    const s = value as string;
+    if (schema.minLength !== undefined && s.length < schema.minLength) errors.push(`${at}: at least ${schema.minLength} chars`);
+    if (schema.maxLength !== undefined && s.length > schema.maxLength) errors.push(`${at}: at most ${schema.maxLength} chars`);
+  } else if (actual === "number" || actual === "integer") {
+    const n = value as number;
+    if (schema.minimum !== undefined && n < schema.minimum) errors.push(`${at}: must be >= ${schema.minimum}`);
+    if (schema.maximum !== undefined && n > schema.maximum) errors.push(`${at}: must be <= ${schema.maximum}`);
+  }
+  return errors;
+}
+
+export type StructuredResult<T> = {
+  /** Parsed, schema-valid reply; null when every attempt failed. */
+  value: T | null;
+  /** The first raw driver output: the free-text fallback (a re-ask reply answers the correction, not the question). */
+  output: ChatOutput;
+  /** Validation errors of the last attempt. */
+  errors: string[];
+};
+
+/**
+ * Ask for a JSON reply matching `responseFormat`. On a parse/validation failure a
+ * driver with `structuredOutput` shows the model its reply plus the errors and gives
+ * it exactly one more try; other drivers are called once.
+ */
+export async function chatStructured<T = unknown>(
+  driver: ChatDriver,
+  messages: ChatMessage[],
+  opts: { model?: string; responseFormat: ResponseFormat; signal?: AbortSignal },
+): Promise<StructuredResult<T>> {
+  const check = (out: ChatOutput): { value: T | null; errors: string[] } => {
+    const parsed = parseJsonText(out.text || "");
+    if (parsed === undefined) return { value: null, errors: ["reply is not valid JSON"] };
+    const errors = validateJson(parsed, opts.responseFormat.schema);
+    return { value: errors.length ? null : (parsed as T), errors };
+  };
+
+  const output = await driver.chat(messages, opts);
+  const first = check(output);
+  if (first.value !== null || !driver.structuredOutput) return { ...first, output };
+
+  Logger.debug(`structured output "${opts.responseFormat.name}" invalid; re-asking`, first.errors);
+  const retry: ChatMessage[] = [
+    ...messages,
+    { role: "assistant", from: "Me", content: output.text || "" },
+    {
+      role: "user",
+      from: "System",
+      content: [
+        `Your reply did not match the required JSON schema "${opts.responseFormat.name}":`,
+        ...first.errors.slice(0, 10).map((e) => `- ${e}`),
+        "Reply again with ONLY the corrected JSON.",
+      ].join("\n"),
+    },
+  ];
+  const result = check(await driver.chat(retry, opts));
+  if (result.value === null) {
+    Logger.warn(`structured output "${opts.responseFormat.name}" still invalid after re-ask: ${result.errors.slice(0, 3).join("; ")}`);
+  }
+  return { ...result, output };
+}
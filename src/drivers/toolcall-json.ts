@@ -0,0 +1,63 @@
+// toolcall-json.ts
+// Shared JSON → ChatToolCall coercion for the text tool-call parsers.
+// Accepts the shapes models actually emit:
+//   { name, arguments }            (Hermes / Qwen / Mistral)
+//   { name, parameters }           (Llama 3.x / DeepSeek)
+//   { function: { name, arguments } }  (OpenAI-style echo)
+
+import { randomUUID as uuid } from "node:crypto";
+import type { ChatToolCall } from "./types";
+
+function isObject(x: unknown): x is Record<string, unknown> {
+  return typeof x === "object" && x !== null && !Array.isArray(x);
+}
+
+function str(o: Record<string, unknown>, key: string): string | undefined {
+  const v = o[key];
+  return typeof v === "string" ? v : undefined;
+}
+
+/** JSON.stringify with sorted object keys, so equal arguments serialize identically. */
+export function stringifyStable(v: unknown): string {
+  return JSON.stringify(v, (_key, value) => {
+    if (isObject(value)) {
+      return Object.keys(value).sort().reduce<Record<string, unknown>>((acc, k) => {
+        acc[k] = value[k];
+        return acc;
+      }, {});
+    }
+    return value;
+  });
+}
+
+/** Build a tool call from a parsed JSON value, or null when it does not name a tool. */
+export function toolCallFromJson(v: unknown): ChatToolCall | null {
+  if (!isObject(v)) return null;
+  const fn = isObject(v["function"]) ? (v["function"] as Record<string, unknown>) : undefined;
+
+  const name = str(v, "name") ?? str(v, "tool") ?? str(v, "tool_name") ?? (fn ? str(fn, "name") : undefined);
+  if (!name || !name.trim()) return null;
+
+  const args = v["arguments"] ?? v["args"] ?? v["parameters"] ?? v["input"] ?? (fn ? fn["arguments"] : undefined);
+
+  return {
+    id: str(v, "id") ?? str(v, "call_id") ?? uuid(),
+    type: "function",
+    function: {
+      name: name.trim(),
+      arguments: typeof args === "string" ? args : stringifyStable(args ?? {}),
+    },
+  };
+}
+
+/** Parse `raw` as JSON (object or array of objects) into tool calls; malformed input yields []. */
+export function toolCallsFromJsonText(raw: string): ChatToolCall[] {
+  let value: unknown;
+  try {
+    value = JSON.parse(raw.trim());
+  } catch {
+    return [];
+  }
+  const items = Array.isArray(value) ? value : [value];
+  return items.map(toolCallFromJson).filter((c): c is ChatToolCall => c !== null);
+}
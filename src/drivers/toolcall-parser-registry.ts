@@ -0,0 +1,78 @@
+// toolcall-parser-registry.ts
+// Named text tool-call parsers for models that write tool calls into their
+// reply text instead of the structured `tool_calls` field.
+//
+// Drivers look a parser up by name (agent spec: `alice^qwen2.5^ollama.openai+hermes`),
+// merge whatever it finds in the final text with the native tool calls, and drop the
+// parsed markup from the text (see extractToolCalls).
+
+import type { ChatToolCall } from "./types";
+import { GemmaToolcallParser } from "./gemma-toolcall-parser";
+import { DeepseekToolcallParser } from "./deepseek-toolcall-parser";
+import { HermesToolcallParser } from "./hermes-toolcall-parser";
+import { Llama3ToolcallParser } from "./llama3-toolcall-parser";
+import { QwenToolcallParser } from "./qwen-toolcall-parser";
+import { MistralToolcallParser } from "./mistral-toolcall-parser";
+
+/** A stretch of reply text (`start` inclusive, `end` exclusive) and the tool calls parsed from it. */
+export interface ToolCallSpan {
+  start: number;
+  end: number;
+  calls: ChatToolCall[];
+}
+
+export interface TextToolCallParser {
+  /** Every tool call found in `text`; malformed blocks are skipped, never thrown. */
+  parseAll(text: string): ChatToolCall[];
+  /** Where those calls sit in `text`, in order; blocks that yield no call are left out. */
+  findAll?(text: string): ToolCallSpan[];
+}
+
+const factories = new Map<string, () => TextToolCallParser>([
+  ["gemma", () => new GemmaToolcallParser()],
+  ["deepseek", () => new DeepseekToolcallParser()],
+  ["hermes", () => new HermesToolcallParser()],
+  ["llama3", () => new Llama3ToolcallParser()],
+  ["qwen", () => new QwenToolcallParser()],
+  ["mistral", () => new MistralToolcallParser()],
+]);
+
+export function registerToolCallParser(name: string, factory: () => TextToolCallParser): void {
+  factories.set(name.toLowerCase(), factory);
+}
+
+export function hasToolCallParser(name: string): boolean {
+  return factories.has(name.toLowerCase());
+}
+
+export function toolCallParserNames(): string[] {
+  return [...factories.keys()];
+}
+
+/** Fresh parser instance for `name`; throws with the list of known names when unknown. */
+export function getToolCallParser(name: string): TextToolCallParser {
+  const factory = factories.get(name.toLowerCase());
+  if (!factory) {
+    throw new Error(`unknown tool-call parser "${name}" (known: ${toolCallParserNames().join(", ")})`);
+  }
+  return factory();
+}
+
+/**
+ * The tool calls in `text`, and `text` with their markup cut out. Parsers without
+ * findAll() leave the text as it is.
+ */
+export function extractToolCalls(parser: TextToolCallParser, text: string): { text: string; toolCalls: ChatToolCall[] } {
+  if (!parser.findAll) return { text, toolCalls: parser.parseAll(text) };
+  const spans = parser.findAll(text);
+  if (!spans.length) return { text, toolCalls: [] };
+
+  let kept = "", at = 0;
+  for (const s of spans) {
+    if (s.start < at) continue; // overlaps a span already cut
+    kept += text.slice(at, s.start);
+    at = s.end;
+  }
+  kept += text.slice(at);
+  return { text: kept.replace(/\n{3,}/g, "\n\n").trim(), toolCalls: spans.flatMap((s) => s.calls) };
+}
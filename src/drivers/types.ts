@@ -1,8 +1,24 @@
+/** An image attached to a message (see io/image-attachments.ts). */
+export interface ChatImage {
+  path: string;               // where it was attached from (shown to the model as text)
+  mimeType: string;           // image/png, image/jpeg, image/gif or image/webp
+  data: string;               // base64, without a data: prefix
+}
+
+/** An Anthropic extended-thinking block, kept verbatim (signature included) so it can be sent back. */
+export type ChatThinkingBlock =
+  | { type: "thinking"; thinking: string; signature: string }
+  | { type: "redacted_thinking"; data: string };
+
 export interface ChatMessage {
   role: string;
   from: string;
   content: string;
+  /** Image parts sent alongside `content` by drivers with vision support; others reject them. */
+  images?: ChatImage[];
   reasoning?: string;
+  /** Assistant turns (Anthropic): the thinking blocks that led to its tool calls, replayed before them. */
+  thinking?: ChatThinkingBlock[];
   /** When role==="tool", the id of the tool call being answered (OpenAI). */
   tool_call_id?: string;
   /** Optional: tool/function name for clarity */
@@ -16,17 +32,59 @@ export interface ChatToolCall {
   raw?: string;
 }
 
+export interface ChatUsage {
+  promptTokens?: number;      // tokens consumed by the prompt (Ollama: prompt_eval_count)
+  completionTokens?: number;  // tokens generated (Ollama: eval_count)
+  estimated?: boolean;        // true when derived from character counts, not reported by the server
+}
+
+export interface ChatTiming {
+  ttftMs?: number;            // request start -> first streamed delta (text, reasoning or tool call)
+  totalMs: number;            // request start -> stream end
+}
+
 export interface ChatOutput {
   text: string;               // assistant text (may be empty)
   toolCalls: ChatToolCall[];  // zero or more tool calls requested by the model
   reasoning?: string;
+  thinking?: ChatThinkingBlock[]; // Anthropic thinking blocks, for the assistant message that carries the tool calls
+  usage?: ChatUsage;          // only present when the backend reports it
+  timing?: ChatTiming;
+}
+
+/** The JSON Schema subset used to constrain model output (see structured-output.ts). */
+export interface JsonSchema {
+  type?: string | string[];
+  enum?: unknown[];
+  properties?: Record<string, JsonSchema>;
+  required?: string[];
+  additionalProperties?: boolean;
+  items?: JsonSchema;
+  minItems?: number;
+  maxItems?: number;
+  minLength?: number;
+  maxLength?: number;
+  minimum?: number;
+  maximum?: number;
+  description?: string;
+}
+
+export interface ResponseFormat {
+  name: string;               // schema name sent to the server (OpenAI `json_schema.name`)
+  schema: JsonSchema;
+  strict?: boolean;           // OpenAI strict mode; requires every property to be required
 }
 
 export interface ChatDriver {
+  /** The driver sends `responseFormat` to the server, so replies are schema-constrained (see structured-output.ts). */
+  structuredOutput?: boolean;
   chat(messages: ChatMessage[], opts?: {
     model?: string; tools?: any[],
+    /** Constrain the reply to JSON matching this schema (OpenAI `response_format`, Ollama `format`). */
+    responseFormat?: ResponseFormat,
     onToken?: (s: string) => void,
     onReasoningToken?: (s: string) => void
     onToolCallDelta?: (s: ChatToolCall) => void
+    signal?: AbortSignal
   }): Promise<ChatOutput>;
 }
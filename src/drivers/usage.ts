@@ -0,0 +1,66 @@
+// usage.ts
+// Shared token-usage / latency helpers for the streaming drivers.
+
+import type { ChatMessage, ChatOutput, ChatTiming, ChatUsage } from "./types";
+
+/** Measures time-to-first-delta and total stream time for one request. */
+export class StreamClock {
+  private firstAt: number | undefined;
+  constructor(private readonly t0: number = Date.now()) { }
+
+  /** Call on every streamed delta; only the first one counts. */
+  mark(): void {
+    if (this.firstAt === undefined) this.firstAt = Date.now();
+  }
+
+  timing(): ChatTiming {
+    const now = Date.now();
+    return {
+      ttftMs: this.firstAt !== undefined ? this.firstAt - this.t0 : undefined,
+      totalMs: now - this.t0,
+    };
+  }
+}
+
+function count(v: unknown): number | undefined {
+  const n = Number(v);
+  return v != null && Number.isFinite(n) && n >= 0 ? n : undefined;
+}
+
+/** OpenAI-style `usage` ({prompt_tokens, completion_tokens}) or Anthropic-style ({input_tokens, output_tokens}). */
+export function usageFromPayload(u: any): ChatUsage | undefined {
+  if (!u || typeof u !== "object") return undefined;
+  const promptTokens = count(u.prompt_tokens ?? u.input_tokens);
+  const completionTokens = count(u.completion_tokens ?? u.output_tokens);
+  if (promptTokens === undefined && completionTokens === undefined) return undefined;
+  return { promptTokens, completionTokens };
+}
+
+/** Same ~4 chars/token heuristic the memory layer budgets with. */
+export function estimateTokens(s: string, avgCharsPerToken = 4): number {
+  return s ? Math.ceil(s.length / avgCharsPerToken) : 0;
+}
+
+/**
+ * Fill in whatever the server did not report from character counts.
+ * Reported numbers are kept as-is; the result is flagged `estimated` if anything was guessed.
+ */
+export function usageOrEstimate(messages: ChatMessage[], out: ChatOutput): ChatUsage {
+  const reported = out.usage ?? {};
+  let estimated = !!reported.estimated;
+
+  let promptTokens = reported.promptTokens;
+  if (promptTokens === undefined) {
+    promptTokens = messages.reduce((a, m) => a + estimateTokens(String(m.content ?? "")), 0);
+    estimated = true;
+  }
+
+  let completionTokens = reported.completionTokens;
+  if (completionTokens === undefined) {
+    const toolArgs = (out.toolCalls ?? []).map((c) => `${c.function.name}${c.function.arguments}`).join("");
+    completionTokens = estimateTokens(`${out.reasoning ?? ""}${out.text ?? ""}${toolArgs}`);
+    estimated = true;
+  }
+
+  return estimated ? { promptTokens, completionTokens, estimated } : { promptTokens, completionTokens };
+}
@@ -4,6 +4,7 @@ import { GuardRail } from "../guardrails/guardrail";
 import { Logger, C } from "../logger";
 import { AgentMemory } from "../memory";
 import { runSh, ToolResult } from "../tools/sh";
+import { attachImages } from "../io/image-attachments";
 import type { ExecuteToolsParams, ExecuteToolsResult } from "./tool-executor";
 import { ToolExecutor } from "./tool-executor";
 
@@ -17,13 +18,13 @@ interface ToolHandlerResult {
     forceEndTurn: boolean;
 }
 
-type ToolHandler = (agentId: string, toolcall: ChatToolCall, text: string, memory: AgentMemory, guard: GuardRail) => Promise<ToolHandlerResult>;
+type ToolHandler = (agentId: string, toolcall: ChatToolCall, text: string, memory: AgentMemory, guard: GuardRail, cwd?: string) => Promise<ToolHandlerResult>;
 
 const formatToolResult = (tr: ToolResult): string => {
     return `ok: ${tr.ok} code: ${tr.exit_code} stdout: ${tr.stdout} stderr: ${tr.stderr}`;
 }
 
-const shHandler = async (agentId: string, toolcall: ChatToolCall, text: string, memory: AgentMemory, guard: GuardRail): Promise<ToolHandlerResult> => {
+const shHandler = async (agentId: string, toolcall: ChatToolCall, text: string, memory: AgentMemory, guard: GuardRail, cwd?: string): Promise<ToolHandlerResult> => {
     Logger.debug("shHanlder", toolcall);
 
     let args: any = {};
@@ -72,7 +73,7 @@ const shHandler = async (agentId: string, toolcall: ChatToolCall, text: string,
 
     Logger.debug(`${agentId} tool ->`, { name, cmd: cmd.slice(0, 160) });
     const t = Date.now();
-    const result = await runSh(cmd)
+    const result = await runSh(cmd, { cwd })
     Logger.debug(C.bold(formatToolResult(result)) );
     Logger.debug(`${agentId} tool <-`, { name, ms: Date.now() - t, exit: result.exit_code, outChars: result.stdout.length, errChars: result.stderr.length });
 
@@ -98,7 +99,12 @@ const shHandler = async (agentId: string, toolcall: ChatToolCall, text: string,
     }
 
     const content = JSON.stringify(result);
-    await memory.add({ role: "tool", content, tool_call_id: toolcall.id, name: "sh", from: "Tool" });
+    // `##image:path` lines in stdout attach those files so the model can look at them.
+    const { images, errors } = attachImages(result.stdout || "");
+    await memory.add({ role: "tool", content, tool_call_id: toolcall.id, name: "sh", from: "Tool", ...(images.length ? { images } : {}) });
+    if (errors.length) {
+        await memory.add({ role: "system", content: errors.join("\n"), from: "System" });
+    }
 
     if (!result.ok) {
         Logger.error(C.magenta("sh error: "), {stderr: result.stderr, stdout: result.stdout, exit_code: result.exit_code});
@@ -142,11 +148,16 @@ const shHandler = async (agentId: string, toolcall: ChatToolCall, text: string,
 //    return { toolsUsed: 1, forceEndTurn: false, stdout: "vimdiff completed", stderr: '', ok: true, exit_code: 0 };
 //}
 
+/** Alternate names models use for a tool; the allowlist is checked against the canonical name. */
+const TOOL_ALIASES: Record<string, string> = {
+    exec: "sh",
+};
+
 /**
  * StandardToolExecutor
  * Extracted from LlmAgent: executes "sh" (and alias "exec") tool calls,
  * streams outputs into memory, and consults the GuardRail.
- * Pure refactor — no functional changes.
+ * Calls to tools outside `allowedTools` are refused (never run) and reported to the guard rail.
  */
 export class StandardToolExecutor extends ToolExecutor {
 
@@ -156,6 +167,18 @@ export class StandardToolExecutor extends ToolExecutor {
         //vimdiff: vimdiffHandler //FIXME
     };
 
+    /** undefined = every tool with a handler. */
+    private readonly allowedTools?: readonly string[];
+
+    constructor(opts: { allowedTools?: readonly string[] } = {}) {
+        super();
+        this.allowedTools = opts.allowedTools;
+    }
+
+    private isAllowed(name: string): boolean {
+        return !this.allowedTools || this.allowedTools.includes(TOOL_ALIASES[name] ?? name);
+    }
+
     async execute(params: ExecuteToolsParams): Promise<ExecuteToolsResult> {
         const {
             calls,
@@ -165,6 +188,8 @@ export class StandardToolExecutor extends ToolExecutor {
             memory,
             finalText,
             agentId,
+            cwd,
+            observer,
         } = params;
 
         let toolsUsed = 0;
@@ -187,6 +212,22 @@ export class StandardToolExecutor extends ToolExecutor {
 
             const handler = this.toolHandlers[name];
 
+            if (handler && !this.isAllowed(name)) {
+                Logger.warn(`\n${agentId} requested tool ${name}, which it is not allowed to use`);
+                const content = JSON.stringify({ ok: false, stdout: "", stderr: `tool not allowed: ${name}`, exit_code: 126 });
+                await memory.add({ role: "tool", content, tool_call_id: tc.id, name, from: "Tool" });
+
+                const decision = guard.noteBadToolCall({ name, reason: "forbidden", allowedTools: this.allowedTools });
+                if (decision?.nudge) {
+                    await memory.add({ role: "system", content: decision.nudge, from: "System" });
+                }
+                if (decision?.endTurn) {
+                    forceEndTurn = true;
+                    break;
+                }
+                continue;
+            }
+
             if (!handler) {
                 const cmd = String(args?.cmd ?? "");
                 Logger.warn(`\nUnknown tool ${name} requested`, tc);
@@ -196,7 +237,11 @@ export class StandardToolExecutor extends ToolExecutor {
                 return { toolsUsed, forceEndTurn: false };
             }
 
-            const result = await handler(agentId, tc, finalText, memory, guard);
+            const call = { callId: tc.id ?? "", tool: name };
+            observer?.started({ ...call, args: tc.function?.arguments ?? "" });
+            const t = Date.now();
+            const result = await handler(agentId, tc, finalText, memory, guard, cwd);
+            observer?.finished({ ...call, ok: result.ok, exitCode: result.exit_code, ms: Date.now() - t });
             toolsUsed++;
 
             if (result.forceEndTurn) {
@@ -11,6 +11,14 @@ export interface ExecuteToolsParams {
   memory: AgentMemory;
   finalText: string; // used for assistant memory in certain branches
   agentId: string;   // for logging context
+  cwd?: string;      // the agent's workspace; default the current directory
+  observer?: ToolObserver; // told about each call that runs (scheduler events)
+}
+
+/** Hears about each tool call as it starts and finishes. */
+export interface ToolObserver {
+  started(call: { callId: string; tool: string; args: string }): void;
+  finished(call: { callId: string; tool: string; ok: boolean; exitCode: number; ms: number }): void;
 }
 
 export interface ExecuteToolsResult {
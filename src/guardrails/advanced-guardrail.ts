@@ -33,6 +33,7 @@ export class AdvancedGuardRail implements GuardRail {
   private assistantTurnsThisRound = 0;
 
   private badToolMissingArgCount = 0;
+  private forbiddenToolCount = 0;
   private toolSigCounts: Map<string, number> = new Map();
   private lastResultBySig: Map<string, string> = new Map();
   private noChangeCountBySig: Map<string, number> = new Map();
@@ -52,6 +53,7 @@ export class AdvancedGuardRail implements GuardRail {
   beginTurn(ctx: { maxToolHops: number }): void {
     this.assistantTurnsThisRound = 0;
     this.badToolMissingArgCount = 0;
+    this.forbiddenToolCount = 0;
     this.toolSigCounts.clear();
     this.noChangeCountBySig.clear();
     this.failStreakBySig.clear();
@@ -77,7 +79,22 @@ export class AdvancedGuardRail implements GuardRail {
     name: string;
     reason: Reason;
     missingArgs?: string[];
+    allowedTools?: readonly string[];
   }): GuardDecision | null {
+    if (info.reason === "forbidden") {
+      // Not a typo the model can fix by retrying: stop after the first repeat.
+      this.forbiddenToolCount++;
+      const allowed = info.allowedTools?.length
+        ? `Your tools: ${info.allowedTools.join(", ")}.`
+        : "You have no tools in this session; answer in chat.";
+      const nudge = `SYSTEM WARNING:
+Tool "${info.name}" is not available to you. ${allowed}
+Do not call it again. If the work needs it, ask a peer who has it (@@peer) or the user (@@user).`;
+      if (this.forbiddenToolCount >= 2) {
+        return { nudge, endTurn: true, muteMs: this.defaultMuteMs, warnings: ["forbidden-tool-limit-reached"] };
+      }
+      return { nudge, warnings: ["forbidden-tool"] };
+    }
     if (info.reason === "missing-arg") {
       this.badToolMissingArgCount++;
       const remaining = Math.max(
@@ -34,11 +34,13 @@ export interface GuardRail {
   /** Called after each assistant output. */
   noteAssistantTurn(info: { text: string; toolCalls: number }): void;
 
-  /** Called when a tool call is syntactically bad (e.g., missing args). */
+  /** Called when a tool call is syntactically bad (e.g., missing args) or not allowed ("forbidden"). */
   noteBadToolCall(info: {
     name: string;
     reason: Reason;
     missingArgs?: string[];
+    /** For "forbidden": the tools this agent may use. */
+    allowedTools?: readonly string[];
   }): GuardDecision | null;
 
   /** Called after a tool returns. */
@@ -5,10 +5,10 @@
 /**
  * A very small TTY controller used by tests and the interactive app.
  * - Hotkeys (interactive only):
- *   • ESC     during streaming -> print ACK to feedback, finalize+exit after stream end
+ *   • ESC     during streaming -> print ACK, cancel the stream, finalize+exit after stream end
  *   • ESC     when idle        -> finalize immediately, then exit(0)
  *   • Ctrl+C                    -> print SIGINT banner, exit(130)
- *   • 'i'     during streaming -> print ACK, open prompt after stream end
+ *   • 'i'     during streaming -> print ACK, cancel the stream, open prompt after stream end
  *   • 'i'     when idle        -> open prompt immediately and enqueue the answer
  *
  * Notes for tests:
@@ -129,10 +129,11 @@ export class TtyController {
   /* state */
   private readonly modes: ModeController;
   private started = false;
-  private streaming = false;
+  private streaming = false; // some stream is open (several with --concurrency)
   private inPrompt = false;
   private pendingEsc = false;
   private pendingInterject = false;
+  private readonly streamAborts = new Set<AbortController>();
   private onDataRef: (chunk: Buffer | string) => void;
 
   /* scheduler sink (tests introspect what was enqueued) */
@@ -205,12 +206,24 @@ export class TtyController {
     }
   }
 
-  /** streaming lifecycle (driven by tests) */
-  onStreamStart() {
+  /**
+   * streaming lifecycle (driven by tests)
+   * Returns the signal the agent hands to its driver; ESC / interject abort it.
+   * With concurrent turns several streams are open at once: each gets its own
+   * signal, and a deferred ESC / interject runs when the last one ends.
+   */
+  onStreamStart(): AbortSignal {
+    const abort = new AbortController();
+    this.streamAborts.add(abort);
     this.streaming = true;
+    return abort.signal;
   }
-  async onStreamEnd() {
-    this.streaming = false;
+  /** `signal` is the one onStreamStart returned; without it the oldest stream is closed. */
+  async onStreamEnd(signal?: AbortSignal) {
+    const ended = [...this.streamAborts].find((a) => a.signal === signal) ?? this.streamAborts.values().next().value;
+    if (ended) this.streamAborts.delete(ended);
+    this.streaming = this.streamAborts.size > 0;
+    if (this.streaming) return;
     if (this.pendingEsc) {
       this.pendingEsc = false;
       await this.finalizeThenExit();
@@ -222,6 +235,11 @@ export class TtyController {
     }
   }
 
+  /** Abort every in-flight model stream. Safe to call repeatedly. */
+  cancelStream() {
+    for (const abort of this.streamAborts) if (!abort.signal.aborted) abort.abort();
+  }
+
   /** prompt helper used in both idle-'i' and deferred interjection */
   async askUser(banner = this.interjectBanner) {
     const line = await this.readUserLine(banner);
@@ -334,6 +352,7 @@ export class TtyController {
             this.pendingEsc = true;
             this.feedback.write(ESC_PRESSED_MSG);
           }
+          this.cancelStream();
           return;
         }
         // Idle -> finalize now
@@ -349,6 +368,7 @@ export class TtyController {
             this.pendingInterject = true;
             this.feedback.write(I_PRESSED_MSG);
           }
+          this.cancelStream();
           return;
         }
         // Ask immediately when idle
@@ -0,0 +1,103 @@
+// image-attachments.ts
+// `##image:path` tags: attach a picture (screenshot, chart, visual-diff PNG) to a message.
+//
+// Used for user input and for sh tool output, so an agent can look at a file it
+// produced with e.g. `echo ##image:out/diff.png`. Images are read and base64-encoded
+// when attached, so later turns don't depend on the file still being there.
+//
+// Agents control those paths, so only real image files inside the project are read:
+// the path is resolved through symlinks and must stay under the project dir, and the
+// file must start with PNG, JPEG, GIF or WebP magic bytes.
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { R } from "../runtime/runtime";
+import type { ChatImage } from "../drivers/types";
+
+const IMAGE_TAG_RE = /##image:(\S+)/g;
+
+const MIME_BY_EXT: Record<string, string> = {
+  ".png": "image/png",
+  ".jpg": "image/jpeg",
+  ".jpeg": "image/jpeg",
+  ".gif": "image/gif",
+  ".webp": "image/webp",
+};
+
+/** Magic bytes at the start of each supported format (WebP: "RIFF" <size> "WEBP"). */
+function sniffImageType(head: Buffer): string | undefined {
+  const at = (offset: number, bytes: number[]) => bytes.every((b, i) => head[offset + i] === b);
+  if (at(0, [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a])) return "image/png";
+  if (at(0, [0xff, 0xd8, 0xff])) return "image/jpeg";
+  if (at(0, [0x47, 0x49, 0x46, 0x38]) && (head[4] === 0x37 || head[4] === 0x39) && head[5] === 0x61) return "image/gif";
+  if (at(0, [0x52, 0x49, 0x46, 0x46]) && at(8, [0x57, 0x45, 0x42, 0x50])) return "image/webp";
+  return undefined;
+}
+
+const DEFAULT_MAX_BYTES = 5 * 1024 * 1024;
+
+function maxBytes(): number {
+  const n = Number(R.env.ORG_IMAGE_MAX_BYTES);
+  return Number.isFinite(n) && n > 0 ? n : DEFAULT_MAX_BYTES;
+}
+
+/**
+ * Resolve a tag path on the host: relative to the project, with the sandbox's /work
+ * mapped onto it. Returns the real path (symlinks followed); throws when the file is
+ * missing or lies outside the project.
+ */
+export function resolveImagePath(p: string, root: string = R.cwd()): string {
+  const candidate = p.startsWith("/work/") && !fs.existsSync(p) ? path.resolve(root, p.slice("/work/".length)) : path.resolve(root, p);
+  let real: string;
+  try {
+    real = fs.realpathSync(candidate);
+  } catch {
+    throw new Error(`${p}: no such file`);
+  }
+  const base = fs.realpathSync(root);
+  if (real !== base && !real.startsWith(base + path.sep)) {
+    throw new Error(`${p}: outside the project; only files under the project directory can be attached`);
+  }
+  return real;
+}
+
+/** Read one image file; throws with a message fit to show the user or the model. */
+export function loadImage(p: string, root: string = R.cwd()): ChatImage {
+  if (!MIME_BY_EXT[path.extname(p).toLowerCase()]) {
+    throw new Error(`${p}: unsupported image type (use ${Object.keys(MIME_BY_EXT).join(", ")})`);
+  }
+  const file = resolveImagePath(p, root);
+  const stat = fs.statSync(file);
+  if (!stat.isFile()) throw new Error(`${p}: not a file`);
+  if (stat.size > maxBytes()) throw new Error(`${p}: ${stat.size} bytes exceeds the ${maxBytes()} byte image limit`);
+
+  const data = fs.readFileSync(file);
+  // The extension only picks candidates; the bytes decide what the file is.
+  const mimeType = sniffImageType(data.subarray(0, 12));
+  if (!mimeType) throw new Error(`${p}: not a PNG, JPEG, GIF or WebP image`);
+  return { path: p, mimeType, data: data.toString("base64") };
+}
+
+export type ImageAttachments = {
+  /** The text with the tags replaced by a short `[image: path]` marker. */
+  text: string;
+  images: ChatImage[];
+  /** One message per tag that could not be attached. */
+  errors: string[];
+};
+
+/** Pull every `##image:path` tag out of `text` and load the files. */
+export function attachImages(text: string): ImageAttachments {
+  const images: ChatImage[] = [];
+  const errors: string[] = [];
+  const out = text.replace(IMAGE_TAG_RE, (_tag, p: string) => {
+    try {
+      images.push(loadImage(p));
+      return `[image: ${p}]`;
+    } catch (e: any) {
+      errors.push(`[image] ${e?.message ?? e}`);
+      return `[image not attached: ${p}]`;
+    }
+  });
+  return { text: out, images, errors };
+}
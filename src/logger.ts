@@ -29,13 +29,22 @@ function writeRaw(s: string) {
 const Colors = { Reset, Dim, FgCyan, FgGreen, FgMagenta, FgYellow, FgBlue };
 
 export class Logger {
-  static info(...a: any[]) { console.log(...a); }
-  static warn(...a: any[]) { console.warn(...a); }
-  static error(...a: any[]) { console.error(...a); } // This must remain as process to prevent cyclic imports
-  static debug(...a: any[]) { if ((process.env.ORG_LOG_LEVEL ?? '').toUpperCase()==='DEBUG') console.log(...a); }
+  /**
+   * When set, every write goes through it; it may run the write now or later
+   * (see ui/output-gate: concurrent agents take turns on the terminal).
+   */
+  static gate: ((write: () => void) => void) | null = null;
+
+  /** Run a terminal write through the gate, if any. */
+  static emit(write: () => void) { if (Logger.gate) Logger.gate(write); else write(); }
+
+  static info(...a: any[]) { Logger.emit(() => console.log(...a)); }
+  static warn(...a: any[]) { Logger.emit(() => console.warn(...a)); }
+  static error(...a: any[]) { Logger.emit(() => console.error(...a)); } // This must remain as process to prevent cyclic imports
+  static debug(...a: any[]) { if ((process.env.ORG_LOG_LEVEL ?? '').toUpperCase()==='DEBUG') Logger.emit(() => console.log(...a)); }
 
   /** stream without newline */
-  static streamInfo(s: string) { writeRaw(s); }
-  static endStreamLine(suffix = "") { writeRaw(suffix + "\n"); }
+  static streamInfo(s: string) { Logger.emit(() => writeRaw(s)); }
+  static endStreamLine(suffix = "") { Logger.emit(() => writeRaw(suffix + "\n")); }
 }
 
@@ -2,6 +2,8 @@ import type { ChatDriver, ChatMessage } from "../drivers/types";
 import { R } from "../runtime/runtime";
 import { AgentMemory } from "./agent-memory";
 import { MemoryPersisitence } from "./memory-persistence";
+import { chatStructured } from "../drivers/structured-output";
+import { LANE_SUMMARY_FORMAT, PERSONA_UPDATE_FORMAT, renderLaneSummary, type LaneSummary } from "./memory-schemas";
 import path from "path";
 
 /**
@@ -358,10 +360,11 @@ export class DynamicAdvancedMemory extends AgentMemory {
       ].join("\n\n"),
     };
 
-    const out = await this.driver.chat([sys, usr], { model: this.model });
-    const text = this.extractText(out);
-    const obj = this.safeParseJson(text);
-    if (!obj || typeof obj !== "object") return null;
+    const { value: obj } = await chatStructured<Record<string, any>>(this.driver, [sys, usr], {
+      model: this.model,
+      responseFormat: PERSONA_UPDATE_FORMAT,
+    });
+    if (!obj) return null;
 
     // Validate & clamp
     try {
@@ -638,7 +641,7 @@ export class DynamicAdvancedMemory extends AgentMemory {
       from: "System",
       content: [
         "You are a precise summarizer.",
-        "Output concise bullet points; preserve facts, tasks, file paths, commands, constraints.",
+        'Output concise bullet points as JSON: {"bullets": ["..."]}; preserve facts, tasks, file paths, commands, constraints.',
         `Hard limit: ~${approxChars} characters total.`,
         "Avoid fluff; keep actionable details.",
       ].join(" "),
@@ -650,9 +653,12 @@ export class DynamicAdvancedMemory extends AgentMemory {
       content: `${header}\n\nTranscript:\n${acc}`,
     };
 
-    const out = await this.driver.chat([sys, user], { model: this.model });
-    const text = this.extractText(out);
-    return text.trim();
+    const { value, output } = await chatStructured<LaneSummary>(this.driver, [sys, user], {
+      model: this.model,
+      responseFormat: LANE_SUMMARY_FORMAT,
+    });
+    // Servers that ignore the schema and still answer in prose keep working as before.
+    return value ? renderLaneSummary(value) : this.extractText(output).trim();
   }
 
   // ------------------------------- Utilities ---------------------------------
@@ -666,17 +672,6 @@ export class DynamicAdvancedMemory extends AgentMemory {
     return "";
   }
 
-  private safeParseJson(text: string): unknown {
-    try {
-      const trimmed = text.trim();
-      if (trimmed.startsWith("{")) return JSON.parse(trimmed);
-      const m = text.match(/\{[\s\S]*\}/);
-      return m ? JSON.parse(m[0]) : null;
-    } catch {
-      return null;
-    }
-  }
-
   private clamp01(x: number): number {
     if (Number.isNaN(x)) return 0;
     return Math.max(0, Math.min(1, x));
@@ -21,6 +21,9 @@ export interface IMemoryPersisitence<T = unknown> {
 
   /** Load the last saved state for the given agent (or null if none). */
   load(id: string): Promise<T | null>;
+
+  /** Forget the saved state for the given agent (no-op if none). */
+  remove(id: string): Promise<void>;
 }
 
 export class MemoryPersisitence<T = unknown> implements IMemoryPersisitence<T> {
@@ -83,10 +86,14 @@ export class MemoryPersisitence<T = unknown> implements IMemoryPersisitence<T> {
     }
   }
 
+  public async remove(id: string): Promise<void> {
+    await fs.rm(this.pathFor(id), { force: true });
+  }
+
   // ---------------- internal helpers ----------------
 
   /** Compute the on-disk path for an agent id, enforcing a safe filename. */
-  private pathFor(id: string): string {
+  public pathFor(id: string): string {
     const safe = this.sanitizeId(id);
     return path.join(this.dirPath, `memory-${safe}.txt`);
   }
@@ -0,0 +1,61 @@
+// memory-schemas.ts
+// Response formats for the memory side-calls (lane summaries, persona mining).
+// Kept structural on purpose: clamping/length limits are applied after parsing,
+// so a slightly long facet is trimmed rather than costing a re-ask.
+
+import type { JsonSchema, ResponseFormat } from "../drivers/types";
+
+const facets: JsonSchema = {
+  type: "array",
+  items: {
+    type: "object",
+    properties: { text: { type: "string" }, weight: { type: "number" } },
+    required: ["text", "weight"],
+    additionalProperties: false,
+  },
+};
+
+export type LaneSummary = { bullets: string[] };
+
+export const LANE_SUMMARY_FORMAT: ResponseFormat = {
+  name: "lane_summary",
+  strict: true,
+  schema: {
+    type: "object",
+    properties: { bullets: { type: "array", items: { type: "string" } } },
+    required: ["bullets"],
+    additionalProperties: false,
+  },
+};
+
+export const PERSONA_UPDATE_FORMAT: ResponseFormat = {
+  name: "persona_update",
+  strict: true,
+  schema: {
+    type: "object",
+    properties: {
+      friction: { type: "number" },
+      confidence: { type: "number" },
+      persona: {
+        type: "object",
+        properties: {
+          roles: facets,
+          style: facets,
+          heuristics: facets,
+          goals: facets,
+          antigoals: facets,
+          languages: facets,
+        },
+        required: ["roles", "style", "heuristics", "goals", "antigoals", "languages"],
+        additionalProperties: false,
+      },
+    },
+    required: ["friction", "confidence", "persona"],
+    additionalProperties: false,
+  },
+};
+
+/** Render a lane summary as the bullet text the memory blocks have always held. */
+export function renderLaneSummary(s: LaneSummary): string {
+  return s.bullets.map((b) => b.trim()).filter(Boolean).map((b) => (b.startsWith("-") ? b : `- ${b}`)).join("\n");
+}
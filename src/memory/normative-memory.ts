@@ -2,10 +2,13 @@ import type { ChatDriver, ChatMessage, ChatToolCall } from "../drivers/types";
 import { C, Logger } from "../logger";
 import { RunMetrics } from "../metrics/runtime-metrics";
 import { R } from "../runtime/runtime";
+import { currentRunId } from "../runtime/run-dir";
 import { createPDAStreamFilterHeuristic } from "../utils/filter-passes/llm-pda-stream-heuristic";
 import { sanitizeContent } from "../utils/sanitize-content";
 import { AgentMemory } from "./agent-memory";
 import { MemoryPersisitence } from "./memory-persistence";
+import { chatStructured } from "../drivers/structured-output";
+import { LANE_SUMMARY_FORMAT, PERSONA_UPDATE_FORMAT, renderLaneSummary, type LaneSummary } from "./memory-schemas";
 import path from "path";
 
 /**
@@ -309,7 +312,7 @@ export class NormativeMemory extends AgentMemory {
         typeof lastAssistant?.content === "string" ? lastAssistant.content : undefined;
 
       await RunMetrics.emitStep({
-        runId: process.env.ORG_RUN_ID || "default",
+        runId: currentRunId(),
         turn: this.turnCounter,
         agent: (this as any).name || "agent",
         phase: (this as any).phase || undefined,
@@ -413,17 +416,11 @@ export class NormativeMemory extends AgentMemory {
       ].join("\n\n"),
     };
 
-    const out = await this.driver.chat([sys, usr], { model: this.model, 
-      onReasoningToken: t => {
-      },
-      onToken: t => {
-      },
-      onToolCallDelta: tcd => {}
-
+    const { value: obj } = await chatStructured<Record<string, any>>(this.driver, [sys, usr], {
+      model: this.model,
+      responseFormat: PERSONA_UPDATE_FORMAT,
     });
-    const text = this.extractText(out);
-    const obj = this.safeParseJson(text);
-    if (!obj || typeof obj !== "object") return null;
+    if (!obj) return null;
 
     // Validate & clamp
     try {
@@ -741,7 +738,7 @@ export class NormativeMemory extends AgentMemory {
       from: "System",
       content: [
         "You are a precise synthesizer.",
-        "Output concise bullet points; preserve rules, constraints, and defaults.",
+        'Output concise bullet points as JSON: {"bullets": ["..."]}; preserve rules, constraints, and defaults.',
         `Hard limit: ~${approxChars} characters total.`,
         "Avoid fluff; avoid new policy outside the transcript; keep within BASE constraints.",
       ].join(" "),
@@ -753,9 +750,12 @@ export class NormativeMemory extends AgentMemory {
       content: `${header}\n\nTranscript:\n${acc}`,
     };
 
-    const out = await this.driver.chat([sys, user], { model: this.model });
-    const text = this.extractText(out);
-    return text.trim();
+    const { value, output } = await chatStructured<LaneSummary>(this.driver, [sys, user], {
+      model: this.model,
+      responseFormat: LANE_SUMMARY_FORMAT,
+    });
+    // Servers that ignore the schema and still answer in prose keep working as before.
+    return value ? renderLaneSummary(value) : this.extractText(output).trim();
   }
 
   // ------------------------------- Utilities ---------------------------------
@@ -769,17 +769,6 @@ export class NormativeMemory extends AgentMemory {
     return "";
   }
 
-  private safeParseJson(text: string): unknown {
-    try {
-      const trimmed = text.trim();
-      if (trimmed.startsWith("{")) return JSON.parse(trimmed);
-      const m = text.match(/\{[\s\S]*\}/);
-      return m ? JSON.parse(m[0]) : null;
-    } catch {
-      return null;
-    }
-  }
-
   private clamp01(x: number): number {
     if (Number.isNaN(x)) return 0;
     return Math.max(0, Math.min(1, x));
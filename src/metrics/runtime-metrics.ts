@@ -7,11 +7,13 @@
 //   import { RunMetrics } from "../metrics/run-metrics";
 //   await RunMetrics.emitStep({...});   // (A) per-turn hook
 //   await RunMetrics.emitTool({...});   // (B) optional; if omitted, summarizer will estimate
+//   await RunMetrics.emitLlm({...});    // (C) per model call: tokens + latency
 // CLI:
 //   bun tsx src/metrics/run-metrics.ts summarize [.org/metrics.jsonl]
 
 import { promises as fs } from "fs";
 import * as path from "path";
+import type { SchedulerEvents } from "../scheduler/events";
 
 export type Iso8601 = string & { __brand: "Iso8601" };
 
@@ -76,8 +78,22 @@ export type UserInterjectionEvent = {
   timestamp: Iso8601;
 };
 
+export type LlmCallEvent = {
+  kind: "llm";
+  runId: string;
+  agent: string;
+  model?: string;
+  promptTokens: number;
+  completionTokens: number;
+  estimated?: boolean;            // usage guessed from character counts (server omitted it)
+  ttftMs?: number;                // request -> first streamed delta
+  totalMs: number;                // request -> stream end
+  timestamp: Iso8601;
+};
+
 export type MetricEvent =
   | StepEvent
+  | LlmCallEvent
   | ToolEvent
   | PatchEvent
   | PolicyConflictEvent
@@ -128,6 +144,12 @@ export class RunMetrics {
     await this.write({ kind: "tool", timestamp: nowIso(), ...e });
   }
 
+  static async emitLlm(e: Omit<LlmCallEvent, "kind" | "timestamp">): Promise<void> {
+    if (!isFinitePosInt(e.promptTokens) || !isFinitePosInt(e.completionTokens))
+      throw new Error("emitLlm: invalid token counts");
+    await this.write({ kind: "llm", timestamp: nowIso(), ...e });
+  }
+
   static async emitPatch(e: Omit<PatchEvent, "kind" | "timestamp">): Promise<void> {
     await this.write({ kind: "patch", timestamp: nowIso(), ...e });
   }
@@ -150,6 +172,17 @@ export class RunMetrics {
     await this.write({ kind: "user_interjection", timestamp: nowIso(), ...e });
   }
 
+  /** Log tool calls and patches from a scheduler's event bus; `turn` counts agent turns started so far. */
+  static follow(events: SchedulerEvents, runId: string): () => void {
+    let turn = 0;
+    const off = [
+      events.on("turn-started", () => { turn++; }),
+      events.on("tool-finished", (e) => { this.emitTool({ runId, turn, tool: e.tool, ok: e.ok }).catch(() => { /* ignore */ }); }),
+      events.on("patch-produced", (e) => { this.emitPatch({ runId, turn, proposed: true, applied: e.merged }).catch(() => { /* ignore */ }); }),
+    ];
+    return () => off.forEach((f) => f());
+  }
+
   // ---- Summarizer (optional CLI) -------------------------------------------
 
   static async summarize(file?: string): Promise<void> {
@@ -167,6 +200,7 @@ export class RunMetrics {
     const conflicts: PolicyConflictEvent[] = [];
     const pros: ProspectiveEvent[] = [];
     const userInt: UserInterjectionEvent[] = [];
+    const llm: LlmCallEvent[] = [];
 
     for (const line of lines) {
       try {
@@ -178,6 +212,7 @@ export class RunMetrics {
           case "policy_conflict": conflicts.push(ev); break;
           case "prospective": pros.push(ev); break;
           case "user_interjection": userInt.push(ev); break;
+          case "llm": llm.push(ev); break;
         }
       } catch { /* ignore bad lines */ }
     }
@@ -274,10 +309,68 @@ export class RunMetrics {
     console.log("Tool success:", fmt(toolSuccess), toolSuccessNote ? `  (${toolSuccessNote})` : "");
     console.log("Patch ok:", fmt(patchOk), "   Intervention interval (median turns):", fmt(medianIntervention));
     console.log("IAC (Norm vs Base):", fmt(IAC));
+
+    if (llm.length) {
+      const row = (label: string, u: UsageRollup) =>
+        console.log(
+          `  ${label.padEnd(24)} calls ${String(u.calls).padStart(4)}  in ${String(u.promptTokens).padStart(8)}  ` +
+          `out ${String(u.completionTokens).padStart(7)}  tok/s ${fmtRate(u.tokensPerSec).padStart(6)}  ` +
+          `TTFT p50 ${fmtMs(u.ttftP50).padStart(7)}${u.estimatedCalls ? `  (${u.estimatedCalls} est.)` : ""}`
+        );
+      console.log("LLM usage per agent:");
+      for (const [agent, u] of rollupBy(llm, e => e.agent)) row(agent, u);
+      console.log("LLM usage per run:");
+      for (const [runId, u] of rollupBy(llm, e => e.runId)) row(runId, u);
+    }
     console.log("==========================\n");
   }
 }
 
+// ---- LLM usage rollups --------------------------------------------------------
+
+export type UsageRollup = {
+  calls: number;
+  promptTokens: number;
+  completionTokens: number;
+  estimatedCalls: number;
+  tokensPerSec: number;           // completion tokens over generation time (after first token)
+  ttftP50: number;
+};
+
+export function rollupUsage(events: LlmCallEvent[]): UsageRollup {
+  let promptTokens = 0, completionTokens = 0, estimatedCalls = 0, genMs = 0;
+  const ttfts: number[] = [];
+  for (const e of events) {
+    promptTokens += e.promptTokens;
+    completionTokens += e.completionTokens;
+    if (e.estimated) estimatedCalls++;
+    if (Number.isFinite(e.ttftMs)) ttfts.push(e.ttftMs!);
+    genMs += Math.max(0, e.totalMs - (e.ttftMs ?? 0));
+  }
+  ttfts.sort((a, b) => a - b);
+  return {
+    calls: events.length,
+    promptTokens,
+    completionTokens,
+    estimatedCalls,
+    tokensPerSec: genMs > 0 ? completionTokens / (genMs / 1000) : NaN,
+    ttftP50: ttfts.length ? ttfts[Math.floor((ttfts.length - 1) / 2)] : NaN,
+  };
+}
+
+export function rollupBy(events: LlmCallEvent[], key: (e: LlmCallEvent) => string): Map<string, UsageRollup> {
+  const groups = new Map<string, LlmCallEvent[]>();
+  for (const e of events) {
+    const k = key(e);
+    if (!groups.has(k)) groups.set(k, []);
+    groups.get(k)!.push(e);
+  }
+  return new Map([...groups].map(([k, evs]) => [k, rollupUsage(evs)]));
+}
+
+const fmtRate = (x: number) => (Number.isFinite(x) ? x.toFixed(1) : "—");
+const fmtMs = (x: number) => (Number.isFinite(x) ? `${Math.round(x)}ms` : "—");
+
 // ---- Heuristic tool estimator (no hooks; aggregates across ALL agents) -----
 
 type ToolEst = { ok: number; fail: number; unknown: number };
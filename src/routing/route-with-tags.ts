@@ -1,12 +1,12 @@
 import { Logger } from "../logger";
 import { Responder } from "../scheduler";
 import { createPDAStreamFilter } from "../utils/filter-passes/llm-pda-stream";
-import { TagPart } from "../utils/tag-parser";
-import { TagSplitter } from "../utils/tag-splitter";
+import { TagSplitter, type TagPart } from "../utils/tag-splitter";
 
 type Delivery =
   | { kind: "group"; content: string }
   | { kind: "agent"; to: string; content: string }
+  | { kind: "agents"; to: string[]; cc: string[]; content: string }
   | { kind: "user"; content: string }
   | { kind: "file"; name: string; content: string };
 
@@ -17,16 +17,29 @@ type RouteOutcome = {
   sawTags: { user: boolean; group: boolean; file: boolean; agent: boolean };
 };
 
+/**
+ * Role tags for a team: each agent's `role` and its plural ("reviewer" and
+ * "reviewers") stand for every agent with that role.
+ */
+export function roleTags(agents: { id: string; role?: string }[]): Record<string, string[]> {
+  const out: Record<string, string[]> = {};
+  for (const a of agents) {
+    if (!a.role) continue;
+    for (const tag of [a.role, `${a.role}s`]) (out[tag.toLowerCase()] ??= []).push(a.id);
+  }
+  return out;
+}
+
 /**
  * Parse & route textual content. Also returns yield hints:
  *  - yieldForUser: message contains @@user
  *  - yieldForGroup: message contains @@group
  */
-export function routeWithTags(raw: string, agentTokens: string[]): RouteOutcome {
+export function routeWithTags(raw: string, agentTokens: string[], roleTokens: Record<string, string[]> = {}): RouteOutcome {
   const filter = createPDAStreamFilter();
   let s = filter.feed(raw) + filter.flush();
 
-  const parts: TagPart[] = TagSplitter.split(s, { agentTokens });
+  const parts: TagPart[] = TagSplitter.split(s, { agentTokens, roleTokens });
   const deliveries: Delivery[] = [];
   let sawUser = false, sawGroup = false, sawFile = false, sawAgent = false;
 
@@ -44,6 +57,9 @@ export function routeWithTags(raw: string, agentTokens: string[]): RouteOutcome
     } else if (p.kind === "agent") {
       deliveries.push({ kind: "agent", to: p.tag, content: p.content });
       sawAgent = true;
+    } else if (p.kind === "agents") {
+      deliveries.push({ kind: "agents", to: p.to, cc: p.cc, content: p.content });
+      sawAgent = true;
     }
   }
 
@@ -64,6 +80,8 @@ export function routeWithTags(raw: string, agentTokens: string[]): RouteOutcome
 type RouterCallbacks = {
   onGroup?: (from: string, content: string) => Promise<void> | void;
   onAgent?: (from: string, to: string, content: string) => Promise<void> | void;
+  /** A recipient list or role tag; without it each recipient goes to onAgent. */
+  onAgents?: (from: string, to: string[], cc: string[], content: string) => Promise<void> | void;
   onUser?: (from: string, content: string) => Promise<void> | void;
   onFile?: (from: string, name: string, content: string) => Promise<void> | void;
 };
@@ -74,12 +92,16 @@ type RouterCallbacks = {
  */
 export function makeRouter(cb: RouterCallbacks, agents: Responder[]) {
   return async (from: string, text: string): Promise<RouteOutcome> => {
-    const outcome = routeWithTags(text || "", agents.map(a => a.id));
+    const outcome = routeWithTags(text || "", agents.map(a => a.id), roleTags(agents));
     for (const d of outcome.deliveries) {
       if (d.kind === "group" && cb.onGroup) {
         await cb.onGroup(from, d.content);
       } else if (d.kind === "agent" && cb.onAgent) {
         await cb.onAgent(from, d.to, d.content);
+      } else if (d.kind === "agents" && cb.onAgents) {
+        await cb.onAgents(from, d.to, d.cc, d.content);
+      } else if (d.kind === "agents" && cb.onAgent) {
+        for (const to of [...d.to, ...d.cc]) await cb.onAgent(from, to, d.content);
       } else if (d.kind === "user" && cb.onUser) {
         await cb.onUser(from, d.content);
       } else if (d.kind === "file" && cb.onFile) {
@@ -0,0 +1,35 @@
+// src/runtime/run-dir.ts
+//
+// One id per process for everything a run writes under .org/runs/<id>/.
+// Mirrors scripts/org-patch-create (UTC timestamp + pid) and is exported as
+// ORG_RUN_ID so other writers (metrics, memory) tag their output consistently.
+
+import * as path from "path";
+import { R } from "./runtime";
+
+function stamp(d = new Date()): string {
+  const p = (n: number) => String(n).padStart(2, "0");
+  return `${d.getUTCFullYear()}${p(d.getUTCMonth() + 1)}${p(d.getUTCDate())}` +
+    `${p(d.getUTCHours())}${p(d.getUTCMinutes())}${p(d.getUTCSeconds())}`;
+}
+
+/** Current run id: ORG_RUN_ID if set, otherwise generated once and exported. */
+export function currentRunId(): string {
+  const existing = R.env.ORG_RUN_ID;
+  if (existing && existing.trim()) return existing.trim();
+  const pid = (globalThis as any).process?.pid ?? 0;
+  const id = `${stamp()}-${pid}`;
+  R.env.ORG_RUN_ID = id;
+  return id;
+}
+
+/** Whether `id` names a run directory: letters, digits, `.`, `_` and `-`, and not `.` or `..`. */
+export function isRunId(id: string): boolean {
+  return /^[\w.-]+$/.test(id) && id !== "." && id !== "..";
+}
+
+/** Absolute path of a run directory (defaults to the current run under the cwd); throws on an id that would leave .org/runs. */
+export function runDir(id: string = currentRunId(), root: string = R.cwd()): string {
+  if (!isRunId(id)) throw new Error(`invalid run id "${id}" (letters, digits, ".", "_" and "-" only)`);
+  return path.join(root, ".org", "runs", id);
+}
@@ -0,0 +1,157 @@
+// src/runtime/session-state.ts
+//
+// Everything `org --resume <run-id>` needs to pick a session up where it stopped:
+//
+//   .org/runs/<run-id>/session.json         team, scheduler state, unfinished review
+//   .org/runs/<run-id>/memory/memory-<id>.txt  copies of each agent's memory
+//
+// The scheduler checkpoints after every turn and every user input, so a crash or
+// a laptop going to sleep loses at most the turn in flight. Agent memory lives in
+// .orgmemories/ (shared by all runs); the copies here are put back on resume so a
+// later run with the same agent ids can't overwrite what this one remembered.
+
+import * as fs from "fs";
+import * as path from "path";
+import { R } from "./runtime";
+import { runDir } from "./run-dir";
+import { MemoryPersisitence } from "../memory/memory-persistence";
+import type { AgentDefinition } from "../agents/agent-definition";
+import type { SchedulerSnapshot } from "../scheduler/types";
+
+export const SESSION_FILE = "session.json";
+export const SESSION_VERSION = 1;
+
+export type SessionState = {
+  version: number;
+  runId: string;
+  /** ISO time of the last checkpoint. */
+  savedAt: string;
+  team: { source: string; definitions: AgentDefinition[]; templates: AgentDefinition[] };
+  /** CLI flags that shape the session (recipe, budgets, review mode); flags given on resume win. */
+  args: Record<string, string | boolean>;
+  /** Ids whose memory is copied into the run directory. */
+  agents: string[];
+  scheduler: SchedulerSnapshot | null;
+  /** Patches shown for review but not yet decided. */
+  review: { patches: string[] } | null;
+};
+
+export class SessionError extends Error {
+  constructor(message: string) {
+    super(`[resume] ${message}`);
+    this.name = "SessionError";
+  }
+}
+
+function writeAtomic(file: string, text: string): void {
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  const tmp = `${file}.${process.pid}.tmp`;
+  fs.writeFileSync(tmp, text, "utf8");
+  fs.renameSync(tmp, file);
+}
+
+/** The most recent run under `root` that left a session file. */
+export function latestSessionRunId(root: string = R.cwd()): string | undefined {
+  const runs = path.join(root, ".org", "runs");
+  let best: { id: string; mtime: number } | undefined;
+  let entries: string[] = [];
+  try { entries = fs.readdirSync(runs); } catch { return undefined; }
+  for (const id of entries) {
+    try {
+      const mtime = fs.statSync(path.join(runs, id, SESSION_FILE)).mtimeMs;
+      if (!best || mtime > best.mtime) best = { id, mtime };
+    } catch { /* no session in this run */ }
+  }
+  return best?.id;
+}
+
+export class SessionStore {
+  private constructor(readonly dir: string, private state: SessionState) { }
+
+  /** Start recording a new session for `runId`. */
+  static create(runId: string, team: SessionState["team"], args: SessionState["args"] = {}, root: string = R.cwd()): SessionStore {
+    return new SessionStore(runDir(runId, root), {
+      version: SESSION_VERSION,
+      runId,
+      savedAt: new Date().toISOString(),
+      team,
+      args,
+      agents: team.definitions.map(d => d.id),
+      scheduler: null,
+      review: null,
+    });
+  }
+
+  /** Load a session to resume. Throws a SessionError naming what is wrong. */
+  static load(runId: string, root: string = R.cwd()): SessionStore {
+    const dir = runDir(runId, root);
+    const file = path.join(dir, SESSION_FILE);
+    let text: string;
+    try {
+      text = fs.readFileSync(file, "utf8");
+    } catch {
+      throw new SessionError(`no saved session for run "${runId}" (looked for ${file})`);
+    }
+    let state: SessionState;
+    try {
+      state = JSON.parse(text);
+    } catch (e) {
+      throw new SessionError(`${file}: not valid JSON (${e instanceof Error ? e.message : String(e)})`);
+    }
+    if (state?.version !== SESSION_VERSION) {
+      throw new SessionError(`${file}: unsupported session version ${JSON.stringify(state?.version)} (expected ${SESSION_VERSION})`);
+    }
+    if (!Array.isArray(state.team?.definitions) || state.team.definitions.length === 0) {
+      throw new SessionError(`${file}: no agents recorded`);
+    }
+    return new SessionStore(dir, state);
+  }
+
+  get runId(): string { return this.state.runId; }
+  get team(): SessionState["team"] { return this.state.team; }
+  get args(): SessionState["args"] { return this.state.args ?? {}; }
+  get scheduler(): SchedulerSnapshot | null { return this.state.scheduler; }
+  get review(): SessionState["review"] { return this.state.review; }
+
+  /** Record scheduler state and copy the memory of every live agent. */
+  checkpoint(snapshot: SchedulerSnapshot): void {
+    const ids = [...this.state.team.definitions.map(d => d.id), ...snapshot.workers.map(w => w.id)];
+    const store = new MemoryPersisitence();
+    for (const id of ids) {
+      const src = store.pathFor(id);
+      if (fs.existsSync(src)) fs.copyFileSync(src, path.join(this.memoryDir(), path.basename(src)));
+    }
+    this.state.agents = ids;
+    this.state.scheduler = snapshot;
+    this.write();
+  }
+
+  /** Remember which patches still need a decision; `null` when the review is done. */
+  setReview(patches: string[] | null): void {
+    this.state.review = patches && patches.length ? { patches: [...patches] } : null;
+    this.write();
+  }
+
+  /** Put the saved agent memories back into .orgmemories/ before the agents are created. */
+  restoreMemories(): void {
+    const store = new MemoryPersisitence();
+    for (const id of this.state.agents) {
+      const dest = store.pathFor(id);
+      const src = path.join(this.memoryDir(), path.basename(dest));
+      if (!fs.existsSync(src)) continue;
+      fs.mkdirSync(path.dirname(dest), { recursive: true });
+      fs.copyFileSync(src, dest);
+    }
+  }
+
+  write(): void {
+    this.state.savedAt = new Date().toISOString();
+    writeAtomic(path.join(this.dir, SESSION_FILE), JSON.stringify(this.state, null, 2) + "\n");
+  }
+
+  private memoryDir(): string {
+    const dir = path.join(this.dir, "memory");
+    fs.mkdirSync(dir, { recursive: true });
+    return dir;
+  }
+}
@@ -0,0 +1,78 @@
+// src/runtime/transcript.ts
+// Every delivery in a run, appended to .org/runs/<id>/transcript.jsonl as it happens:
+// inbox messages (user, agents, system notes), @@user messages, ##file writes, tool
+// calls, and broadcasts a guard suppressed. Built from the scheduler's event bus
+// (see scheduler/events); `org transcript <run-id>` renders it (see cli/transcript).
+
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { createHash } from "node:crypto";
+import { runDir } from "./run-dir";
+import type { GuardDecision } from "../guardrails/guardrail";
+import type { SchedulerEvents } from "../scheduler/events";
+
+export type TranscriptEntry =
+  /**
+   * A message delivered to `to` ("user" for @@user). `id`, `threadId` and `replyTo` come
+   * from the inbox (none for @@user); `guard` is the speaker's guard decision, if any.
+   */
+  | {
+      type: "message"; at: string; from: string; to: string;
+      kind: "dm" | "group" | "cc" | "system" | "user";
+      id?: string; threadId?: string; replyTo?: string;
+      sha256: string; content: string; guard?: GuardDecision;
+    }
+  | { type: "file"; at: string; from: string; path: string; bytes: number }
+  | { type: "tool"; at: string; from: string; callId: string; tool: string; args: string; ok: boolean; exitCode: number; ms: number }
+  /** A broadcast the speaker's guard kept from the group. */
+  | { type: "suppressed"; at: string; from: string; guard: GuardDecision };
+
+export const sha256 = (text: string) => createHash("sha256").update(text).digest("hex");
+
+/** Append every delivery on `events` to `file`; returns the unsubscriber. */
+export function recordTranscript(events: SchedulerEvents, file: string = path.join(runDir(), "transcript.jsonl")): () => void {
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  const write = (e: TranscriptEntry) => fs.appendFileSync(file, JSON.stringify(e) + "\n");
+  const now = () => new Date().toISOString();
+  const args = new Map<string, string>(); // tool call id -> its arguments, until it finishes
+
+  const off = [
+    events.on("message-enqueued", ({ to, message: m, meta, guard }) => write({
+      type: "message",
+      at: new Date(meta.at).toISOString(),
+      from: m.from,
+      to,
+      kind: m.role === "system" ? "system" : meta.cc ? "cc" : meta.direct ? "dm" : "group",
+      id: meta.id,
+      ...(meta.threadId ? { threadId: meta.threadId } : {}),
+      ...(meta.replyTo ? { replyTo: meta.replyTo } : {}),
+      sha256: sha256(m.content),
+      content: m.content,
+      ...(guard ? { guard } : {}),
+    })),
+    events.on("user-addressed", (e) => write({ type: "message", at: now(), from: e.agentId, to: "user", kind: "user", sha256: sha256(e.content), content: e.content })),
+    events.on("file-written", (e) => write({ type: "file", at: now(), from: e.agentId, path: e.path, bytes: e.bytes })),
+    events.on("tool-started", (e) => { args.set(`${e.agentId}/${e.callId}`, e.args); }),
+    events.on("tool-finished", (e) => {
+      const key = `${e.agentId}/${e.callId}`;
+      write({ type: "tool", at: now(), from: e.agentId, callId: e.callId, tool: e.tool, args: args.get(key) ?? "", ok: e.ok, exitCode: e.exitCode, ms: e.ms });
+      args.delete(key);
+    }),
+    events.on("guard-decision", (e) => { if (e.decision.suppressBroadcast) write({ type: "suppressed", at: now(), from: e.agentId, guard: e.decision }); }),
+  ];
+  return () => off.forEach((f) => f());
+}
+
+/** The entries of a transcript file; lines that don't parse are skipped. */
+export function readTranscript(file: string): TranscriptEntry[] {
+  const out: TranscriptEntry[] = [];
+  for (const line of fs.readFileSync(file, "utf8").split("\n")) {
+    if (!line.trim()) continue;
+    try {
+      out.push(JSON.parse(line));
+    } catch {
+      // a line cut short by a crash
+    }
+  }
+  return out;
+}
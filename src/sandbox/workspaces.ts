@@ -0,0 +1,195 @@
+// src/sandbox/workspaces.ts
+// Where each agent works when turns run concurrently (--concurrency N, N > 1).
+//
+//   worktree  every agent gets its own git worktree of the work dir, seeded with
+//             its current state (uncommitted and untracked files included), under
+//             .org/worktrees/<run-id>/<agent>. Agents don't see each other's edits
+//             until finalize merges every worktree back into the work dir.
+//   paths     agents share the work dir and declare the files they write with
+//             `paths:` globs in org.yaml. Two agents run at once only when their
+//             globs are disjoint; ##file blocks outside an agent's globs are refused.
+//             Shell commands are not confined, so keep them to the declared paths.
+
+import { execFileSync } from "node:child_process";
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { C, Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import { currentRunId, runDir } from "../runtime/run-dir";
+import { matchAny } from "./glob";
+import type { Agent } from "../agents/agent";
+
+export const ISOLATION_MODES = ["worktree", "paths"] as const;
+export type IsolationMode = typeof ISOLATION_MODES[number];
+
+export class WorkspaceError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "WorkspaceError";
+  }
+}
+
+/** --concurrency N, else ORG_CONCURRENCY, else 1 (one turn at a time). */
+export function concurrencyLevel(arg: unknown, env: Record<string, string | undefined> = R.env): number {
+  const raw = arg !== undefined && arg !== true ? arg : env.ORG_CONCURRENCY;
+  if (raw === undefined || String(raw).trim() === "") return 1;
+  const n = Number(raw);
+  if (!Number.isInteger(n) || n < 1) throw new WorkspaceError(`--concurrency: expected a whole number >= 1, got "${raw}"`);
+  return n;
+}
+
+/** --isolation worktree|paths, else ORG_ISOLATION, else worktree. */
+export function isolationMode(arg: unknown, env: Record<string, string | undefined> = R.env): IsolationMode {
+  const raw = typeof arg === "string" && arg.trim() ? arg : env.ORG_ISOLATION;
+  const mode = String(raw ?? "worktree").trim().toLowerCase() || "worktree";
+  if (!(ISOLATION_MODES as readonly string[]).includes(mode)) {
+    throw new WorkspaceError(`--isolation: unknown mode "${mode}" (known: ${ISOLATION_MODES.join(", ")})`);
+  }
+  return mode as IsolationMode;
+}
+
+/** How one worktree came back: merged into the work dir, or left as a patch to apply by hand. */
+export type MergeResult = { agentId: string; patch: string; merged: boolean; error?: string };
+
+/** The glob up to its first wildcard, cut back to whole path segments ("src/a*" -> "src"). */
+function literalPrefix(glob: string): string {
+  const g = glob.replace(/\\/g, "/").replace(/^\.\//, "");
+  const wild = g.search(/[*?[{]/);
+  if (wild < 0) return g;
+  const cut = g.slice(0, wild);
+  return cut.slice(0, cut.lastIndexOf("/") + 1).replace(/\/$/, "");
+}
+
+function within(parent: string, child: string): boolean {
+  return parent === "" || child === parent || child.startsWith(parent + "/");
+}
+
+/** Whether two glob lists may name the same file. Conservative: "src/**" and "src/a.ts" overlap. */
+export function globsOverlap(a: readonly string[], b: readonly string[]): boolean {
+  for (const x of a) {
+    for (const y of b) {
+      const px = literalPrefix(x);
+      const py = literalPrefix(y);
+      if (within(px, py) || within(py, px)) return true;
+    }
+  }
+  return false;
+}
+
+const git = (cwd: string, args: string[], input?: string) =>
+  execFileSync("git", ["-C", cwd, "-c", "color.ui=false", ...args], { encoding: "utf8", input, stdio: ["pipe", "pipe", "pipe"], maxBuffer: 256 * 1024 * 1024 });
+
+export class Workspaces {
+  private readonly root: string;
+  private readonly bases = new Map<string, string>();
+  private failed = false;
+
+  constructor(
+    readonly mode: IsolationMode,
+    readonly baseDir: string = R.cwd(),
+    readonly runId: string = currentRunId(),
+  ) {
+    this.root = path.join(baseDir, ".org", "worktrees", runId);
+  }
+
+  /** Where `a`'s tools run and its ##file blocks land. */
+  dirFor(a: Agent): string {
+    if (this.mode !== "worktree" || this.failed) return this.baseDir;
+    const dir = path.join(this.root, a.id);
+    if (!this.bases.has(a.id)) {
+      try {
+        this.bases.set(a.id, fs.existsSync(dir) ? this.readBase(a.id) : this.create(a.id, dir));
+      } catch (e: any) {
+        // Without a worktree the agent would edit the shared tree next to others; stop isolating instead.
+        this.failed = true;
+        Logger.warn(C.yellow(`[workspace] cannot create a worktree for ${a.id} (${e?.message ?? e}); agents now run one at a time in ${this.baseDir}.`));
+        return this.baseDir;
+      }
+    }
+    return dir;
+  }
+
+  /** Whether `a` may take a turn while `running` are mid-turn. */
+  canRunBeside(a: Agent, running: readonly Agent[]): boolean {
+    if (running.length === 0) return true;
+    if (this.mode === "worktree") return !this.failed;
+    if (!a.paths?.length) return false;
+    return running.every((r) => !!r.paths?.length && !globsOverlap(a.paths!, r.paths!));
+  }
+
+  /** Why `a` may not write `rel`, or undefined when it may. */
+  refuseWrite(a: Agent, rel: string): string | undefined {
+    if (this.mode !== "paths" || !a.paths?.length) return undefined;
+    if (matchAny(a.paths, rel)) return undefined;
+    return `##file:${rel} is outside your paths (${a.paths.join(", ")}); it was not written.`;
+  }
+
+  /**
+   * Bring every worktree's changes back into the work dir, one agent at a time.
+   * Each agent's patch is kept under the run dir; one that does not apply (even
+   * three-way) is left for the user, named in the result.
+   */
+  merge(): MergeResult[] {
+    const out: MergeResult[] = [];
+    if (this.mode !== "worktree" || !fs.existsSync(this.root)) return out;
+    const dest = path.join(runDir(this.runId, this.baseDir), "agents");
+    for (const id of fs.readdirSync(this.root).filter((f) => !f.endsWith(".base")).sort()) {
+      const dir = path.join(this.root, id);
+      try {
+        const base = this.bases.get(id) ?? this.readBase(id);
+        git(dir, ["add", "-A", "--", ".", ":(exclude).org/**"]);
+        const diff = git(dir, ["diff", "--cached", "--binary", "-M", base, "--", ".", ":(exclude).org/**"]);
+        if (diff.trim()) {
+          fs.mkdirSync(dest, { recursive: true });
+          const patch = path.join(dest, `${id}.patch`);
+          fs.writeFileSync(patch, diff);
+          out.push(this.apply(id, patch));
+        }
+        git(this.baseDir, ["worktree", "remove", "--force", dir]);
+        fs.rmSync(path.join(this.root, `${id}.base`), { force: true });
+      } catch (e: any) {
+        out.push({ agentId: id, patch: dir, merged: false, error: String(e?.stderr || e?.message || e).trim() });
+      }
+    }
+    try { git(this.baseDir, ["worktree", "prune"]); } catch { /* ignore */ }
+    return out;
+  }
+
+  private apply(agentId: string, patch: string): MergeResult {
+    try {
+      git(this.baseDir, ["apply", "--binary", patch]);
+      return { agentId, patch, merged: true };
+    } catch {
+      try {
+        git(this.baseDir, ["apply", "--binary", "--3way", patch]);
+        return { agentId, patch, merged: true };
+      } catch (e: any) {
+        return { agentId, patch, merged: false, error: String(e?.stderr || e?.message || e).trim() };
+      }
+    }
+  }
+
+  /** A detached worktree at HEAD plus the work dir's uncommitted and untracked files, committed as the agent's base. */
+  private create(id: string, dir: string): string {
+    git(this.baseDir, ["rev-parse", "--is-inside-work-tree"]);
+    fs.mkdirSync(this.root, { recursive: true });
+    git(this.baseDir, ["worktree", "add", "--detach", "--quiet", dir, "HEAD"]);
+    const pending = git(this.baseDir, ["diff", "--binary", "HEAD", "--", ".", ":(exclude).org/**"]);
+    if (pending.trim()) git(dir, ["apply", "--binary", "-"], pending);
+    const untracked = git(this.baseDir, ["ls-files", "--others", "--exclude-standard", "-z", "--", ".", ":(exclude).org/**"]);
+    for (const rel of untracked.split("\0").filter(Boolean)) {
+      fs.mkdirSync(path.dirname(path.join(dir, rel)), { recursive: true });
+      fs.copyFileSync(path.join(this.baseDir, rel), path.join(dir, rel));
+    }
+    git(dir, ["add", "-A"]);
+    git(dir, ["-c", "user.name=org", "-c", "user.email=org@localhost", "commit", "--quiet", "--no-verify", "--allow-empty", "-m", `org: ${id} base`]);
+    const base = git(dir, ["rev-parse", "HEAD"]).trim();
+    fs.writeFileSync(path.join(this.root, `${id}.base`), base + "\n");
+    Logger.info(C.gray(`[workspace] ${id} works in ${path.relative(this.baseDir, dir)}`));
+    return base;
+  }
+
+  private readBase(id: string): string {
+    return fs.readFileSync(path.join(this.root, `${id}.base`), "utf8").trim();
+  }
+}
@@ -0,0 +1,192 @@
+// src/scheduler/budgets.ts
+// Spending limits on turns ("hops"), tokens, time and estimated cost.
+//
+//   Per agent:    `budgets:` in org.yaml (maxHops, maxTokens, maxTimeMs, maxCostUsd)
+//   Per session:  --max-hops, --max-tokens, --timeout-ms, --max-cost
+//                 (or ORG_MAX_HOPS, ORG_MAX_TOKENS, ORG_TIMEOUT_MS, ORG_MAX_COST_USD)
+//
+// A hop is one turn of one agent; time is time spent in turns, so a session
+// waiting on the user does not run out. Cost is tokens times the agent's
+// `pricing` (or ORG_PRICE_PROMPT_PER_MTOK / ORG_PRICE_COMPLETION_PER_MTOK).
+// Limits are checked before each turn; a turn already running finishes.
+
+import { R } from "../runtime/runtime";
+import type { Agent } from "../agents/agent";
+
+export type BudgetKind = "hops" | "tokens" | "time" | "cost";
+export type Limits = Partial<Record<BudgetKind, number>>;
+export type Spend = Record<BudgetKind, number>;
+
+/** A limit that has been reached; `agentId` is whose turn it was. */
+export type Exhausted = { scope: "agent" | "session"; agentId: string; kind: BudgetKind; used: number; limit: number };
+
+/** What `org --resume` keeps: spend so far and extensions the user granted. */
+export type BudgetSnapshot = {
+    session: Spend;
+    agents: Record<string, Spend>;
+    extra: { session: Limits; agents: Record<string, Limits> };
+};
+
+const KINDS: BudgetKind[] = ["hops", "tokens", "time", "cost"];
+
+const zero = (): Spend => ({ hops: 0, tokens: 0, time: 0, cost: 0 });
+
+function positive(v: unknown): number | undefined {
+    if (v === undefined || v === null || v === true || String(v).trim() === "") return undefined;
+    const n = Number(v);
+    return Number.isFinite(n) && n > 0 ? n : undefined;
+}
+
+/** Session limits from CLI flags, falling back to the environment (recipes set ORG_MAX_HOPS and ORG_TIMEOUT_MS). */
+export function sessionLimits(args: Record<string, string | boolean | undefined>, env: Record<string, string | undefined> = R.env): Limits {
+    const out: Limits = {};
+    const hops = positive(args["max-hops"] ?? env.ORG_MAX_HOPS);
+    const tokens = positive(args["max-tokens"] ?? env.ORG_MAX_TOKENS);
+    const time = positive(args["timeout-ms"] ?? env.ORG_TIMEOUT_MS);
+    const cost = positive(args["max-cost"] ?? env.ORG_MAX_COST_USD);
+    if (hops !== undefined) out.hops = Math.floor(hops);
+    if (tokens !== undefined) out.tokens = Math.floor(tokens);
+    if (time !== undefined) out.time = time;
+    if (cost !== undefined) out.cost = cost;
+    return out;
+}
+
+function agentLimits(a: Agent): Limits {
+    const b = a.budgets ?? {};
+    const out: Limits = {};
+    if (b.maxHops !== undefined) out.hops = b.maxHops;
+    if (b.maxTokens !== undefined) out.tokens = b.maxTokens;
+    if (b.maxTimeMs !== undefined) out.time = b.maxTimeMs;
+    if (b.maxCostUsd !== undefined) out.cost = b.maxCostUsd;
+    return out;
+}
+
+function formatDuration(ms: number): string {
+    const s = Math.round(ms / 1000);
+    if (s < 60) return `${s}s`;
+    const m = Math.floor(s / 60);
+    return s % 60 ? `${m}m ${s % 60}s` : `${m}m`;
+}
+
+/** "12 hops", "10,240 tokens", "10m 2s", "$2.01". */
+export function formatAmount(kind: BudgetKind, n: number): string {
+    switch (kind) {
+        case "hops": return `${n} hop${n === 1 ? "" : "s"}`;
+        case "tokens": return `${Math.round(n).toLocaleString("en-US")} tokens`;
+        case "time": return formatDuration(n);
+        case "cost": return `$${n.toFixed(2)}`;
+    }
+}
+
+/** "alice used its token budget (10,240 tokens of 10,000 tokens)"; told to the agent itself, "You used your …". */
+export function describeExhausted(ex: Exhausted, toAgent = false): string {
+    const who = ex.scope === "session" ? `${toAgent ? "The" : "the"} session used its` : toAgent ? "You used your" : `${ex.agentId} used its`;
+    const kind = ex.kind === "hops" ? "hop" : ex.kind === "tokens" ? "token" : ex.kind;
+    return `${who} ${kind} budget (${formatAmount(ex.kind, ex.used)} of ${formatAmount(ex.kind, ex.limit)})`;
+}
+
+/**
+ * Turn the user's answer to "extend or stop?" into an amount to add, in the
+ * budget's own units: "y" adds the original limit again, a number adds that
+ * much ("5m"/"30s" for time, "$2" for cost). null means stop; NaN means unreadable.
+ */
+export function parseExtension(answer: string, ex: Exhausted): number | null {
+    const s = answer.trim().toLowerCase();
+    if (s === "" || s === "n" || s === "no" || s === "stop") return null;
+    if (s === "y" || s === "yes") return ex.limit;
+    const m = s.match(/^\+?\$?\s*(\d+(?:\.\d+)?)\s*(ms|s|m|h)?$/);
+    if (!m) return NaN;
+    const n = Number(m[1]);
+    if (!(n > 0)) return NaN;
+    if (ex.kind !== "time") return m[2] ? NaN : n;
+    const unit = m[2] ?? "ms";
+    return n * ({ ms: 1, s: 1000, m: 60_000, h: 3_600_000 } as const)[unit as "ms" | "s" | "m" | "h"];
+}
+
+export class BudgetTracker {
+    private session = zero();
+    private readonly agents = new Map<string, Spend>();
+    private extraSession: Limits = {};
+    private readonly extraAgents = new Map<string, Limits>();
+
+    constructor(private readonly limits: Limits = {}, private readonly env: Record<string, string | undefined> = R.env) { }
+
+    /** Spend so far by one agent, or by the whole session. */
+    spent(agentId?: string): Spend {
+        return { ...(agentId === undefined ? this.session : this.agents.get(agentId) ?? zero()) };
+    }
+
+    /** The first limit `a` has reached, its own before the session's. */
+    check(a: Agent): Exhausted | undefined {
+        const own = this.agents.get(a.id) ?? zero();
+        const ownExtra = this.extraAgents.get(a.id) ?? {};
+        for (const [scope, limits, extra, used] of [
+            ["agent", agentLimits(a), ownExtra, own],
+            ["session", this.limits, this.extraSession, this.session],
+        ] as const) {
+            for (const kind of KINDS) {
+                const base = limits[kind];
+                if (base === undefined) continue;
+                const limit = base + (extra[kind] ?? 0);
+                if (used[kind] >= limit) return { scope, agentId: a.id, kind, used: used[kind], limit };
+            }
+        }
+        return undefined;
+    }
+
+    /** Raise the limit that ran out by `amount` (in its own units). */
+    extend(ex: Exhausted, amount: number): void {
+        const extra = ex.scope === "session" ? this.extraSession : this.extraAgents.get(ex.agentId) ?? {};
+        extra[ex.kind] = (extra[ex.kind] ?? 0) + amount;
+        if (ex.scope === "agent") this.extraAgents.set(ex.agentId, extra);
+    }
+
+    /** Run one turn of `a` and charge its hop, tokens, time and cost to the agent and the session. */
+    async charge<T>(a: Agent, turn: () => Promise<T>): Promise<T> {
+        const before = a.getUsage();
+        const t0 = Date.now();
+        try {
+            return await turn();
+        } finally {
+            const after = a.getUsage();
+            const prompt = Math.max(0, after.promptTokens - before.promptTokens);
+            const completion = Math.max(0, after.completionTokens - before.completionTokens);
+            const delta: Spend = {
+                hops: 1,
+                tokens: prompt + completion,
+                time: Date.now() - t0,
+                cost: (prompt * this.price(a, "prompt") + completion * this.price(a, "completion")) / 1_000_000,
+            };
+            const own = this.agents.get(a.id) ?? zero();
+            for (const k of KINDS) {
+                own[k] += delta[k];
+                this.session[k] += delta[k];
+            }
+            this.agents.set(a.id, own);
+        }
+    }
+
+    snapshot(): BudgetSnapshot {
+        return {
+            session: { ...this.session },
+            agents: Object.fromEntries([...this.agents].map(([id, s]) => [id, { ...s }])),
+            extra: { session: { ...this.extraSession }, agents: Object.fromEntries([...this.extraAgents].map(([id, l]) => [id, { ...l }])) },
+        };
+    }
+
+    restore(s: BudgetSnapshot): void {
+        this.session = { ...zero(), ...s.session };
+        this.agents.clear();
+        for (const [id, spend] of Object.entries(s.agents)) this.agents.set(id, { ...zero(), ...spend });
+        this.extraSession = { ...s.extra.session };
+        this.extraAgents.clear();
+        for (const [id, l] of Object.entries(s.extra.agents)) this.extraAgents.set(id, { ...l });
+    }
+
+    private price(a: Agent, side: "prompt" | "completion"): number {
+        const own = side === "prompt" ? a.pricing?.promptPerMTok : a.pricing?.completionPerMTok;
+        if (own !== undefined) return own;
+        const env = side === "prompt" ? this.env.ORG_PRICE_PROMPT_PER_MTOK : this.env.ORG_PRICE_COMPLETION_PER_MTOK;
+        return positive(env) ?? 0;
+    }
+}
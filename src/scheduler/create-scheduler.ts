@@ -0,0 +1,32 @@
+// src/scheduler/create-scheduler.ts
+// `--scheduler random|round-robin|priority|fsm` (or ORG_SCHEDULER): which turn-order policy runs the team.
+
+import { R } from "../runtime/runtime";
+import { FSMScheduler } from "./fsm-scheduler";
+import { PriorityScheduler } from "./priority-scheduler";
+import { RandomScheduler } from "./random-scheduler";
+import { RoundRobinScheduler } from "./round-robin-scheduler";
+
+export const SCHEDULER_KINDS = ["random", "round-robin", "priority", "fsm"] as const;
+export type SchedulerKind = typeof SCHEDULER_KINDS[number];
+
+type Options = ConstructorParameters<typeof RandomScheduler>[0];
+
+/** The scheduler named by the flag, else ORG_SCHEDULER, else random. */
+export function schedulerKind(arg: unknown, env: Record<string, string | undefined> = R.env): SchedulerKind {
+  const raw = typeof arg === "string" && arg.trim() ? arg : env.ORG_SCHEDULER;
+  const kind = String(raw ?? "random").trim().toLowerCase() || "random";
+  if (!(SCHEDULER_KINDS as readonly string[]).includes(kind)) {
+    throw new Error(`unknown scheduler "${kind}" (known: ${SCHEDULER_KINDS.join(", ")})`);
+  }
+  return kind as SchedulerKind;
+}
+
+export function createScheduler(kind: SchedulerKind, opts: Options): RandomScheduler {
+  switch (kind) {
+    case "random": return new RandomScheduler(opts);
+    case "round-robin": return new RoundRobinScheduler(opts);
+    case "priority": return new PriorityScheduler(opts);
+    case "fsm": return new FSMScheduler(opts);
+  }
+}
@@ -0,0 +1,71 @@
+// src/scheduler/events.ts
+// What a scheduler reports while it runs. Every scheduler owns a SchedulerEvents bus
+// (`scheduler.events`); front ends, metrics and plugins subscribe to it rather than
+// being passed in as constructor options. The options that remain (onAskUser,
+// readUserLine, onStreamStart/End, onCheckpoint) are the ones whose answer the
+// scheduler waits for.
+//
+// Listeners run synchronously, in subscription order, on the scheduler's own call
+// stack: keep them quick and hand slow work (disk, network) off. A listener that
+// throws is logged and skipped; it never breaks a turn.
+
+import { C, Logger } from "../logger";
+import type { GuardDecision } from "../guardrails/guardrail";
+import type { ChatMessage } from "../types";
+import type { SchedulerTransition } from "./fsm-scheduler";
+import type { MessageMeta } from "./inbox";
+
+export type SchedulerEvent =
+  /** A message was queued for `to`; `meta` carries its id, thread and reply link, `guard` what the speaker's guard decided. */
+  | { type: "message-enqueued"; to: string; message: ChatMessage; meta: MessageMeta; guard?: GuardDecision }
+  /** An agent's @@user message (it goes to the screen, not to an inbox). */
+  | { type: "user-addressed"; agentId: string; content: string }
+  | { type: "turn-started"; agentId: string; messages: number }
+  /** `replied` is false when the agent had nothing to say. */
+  | { type: "turn-ended"; agentId: string; replied: boolean; ms: number }
+  | { type: "tool-started"; agentId: string; callId: string; tool: string; args: string }
+  | { type: "tool-finished"; agentId: string; callId: string; tool: string; ok: boolean; exitCode: number; ms: number }
+  | { type: "guard-decision"; agentId: string; decision: GuardDecision }
+  /** A ##file block was written; `path` is where it landed. */
+  | { type: "file-written"; agentId: string; path: string; bytes: number }
+  /** A patch was written for review: an agent's worktree diff (`agentId` set) or the session patch. */
+  | { type: "patch-produced"; agentId?: string; path: string; merged?: boolean }
+  /** FSMScheduler only: it moved from one state to another. */
+  | { type: "state-changed"; transition: SchedulerTransition };
+
+export type SchedulerEventType = SchedulerEvent["type"];
+export type SchedulerEventOf<T extends SchedulerEventType> = Extract<SchedulerEvent, { type: T }>;
+
+type Listener = (e: SchedulerEvent) => void;
+
+export class SchedulerEvents {
+  private readonly listeners = new Map<SchedulerEventType | "*", Set<Listener>>();
+
+  /** Call `fn` for every `type` event; returns the unsubscriber. */
+  on<T extends SchedulerEventType>(type: T, fn: (e: SchedulerEventOf<T>) => void): () => void {
+    return this.add(type, fn as Listener);
+  }
+
+  /** Call `fn` for every event. */
+  onAny(fn: (e: SchedulerEvent) => void): () => void {
+    return this.add("*", fn);
+  }
+
+  emit(e: SchedulerEvent): void {
+    for (const key of [e.type, "*"] as const) {
+      for (const fn of this.listeners.get(key) ?? []) {
+        try {
+          fn(e);
+        } catch (err) {
+          Logger.warn(C.yellow(`[events] ${e.type} listener failed: ${err instanceof Error ? err.message : String(err)}`));
+        }
+      }
+    }
+  }
+
+  private add(key: SchedulerEventType | "*", fn: Listener): () => void {
+    if (!this.listeners.has(key)) this.listeners.set(key, new Set());
+    this.listeners.get(key)!.add(fn);
+    return () => { this.listeners.get(key)?.delete(fn); };
+  }
+}
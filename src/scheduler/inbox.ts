@@ -1,39 +1,128 @@
 import type { ChatMessage } from "../types";
+import type { GuardDecision } from "../guardrails/guardrail";
+
+/**
+ * What the Inbox keeps beside each queued message. The message itself stays a bare
+ * ChatMessage (it goes to the model as is); ids, threads and links live here.
+ */
+export type MessageMeta = {
+  /** Stable for the life of the run (and across --resume): "m1", "m2", ... */
+  id: string;
+  /** Queue time (ms since the epoch). */
+  at: number;
+  /** Whether it was a DM (addressed to this agent alone) rather than a broadcast. */
+  direct: boolean;
+  /**
+   * The conversation it belongs to: "group", or "dm:<a>+<b>" for a DM between
+   * two parties (sorted, lower case; the user is "user"), "dm:<a>+<b>+<c>" for one
+   * sent to several. System notes have none and go out with the agent's next
+   * batch, whatever its thread.
+   */
+  threadId?: string;
+  /** Id of the message this one answers. */
+  replyTo?: string;
+  /** Copied in (`cc:`): the agent should read it but isn't expected to reply, so it goes out with the agent's next batch rather than earning a turn. */
+  cc?: boolean;
+  /** Higher goes first when an agent has several threads waiting; default 0. */
+  priority: number;
+  /** Dropped unread after this time (ms since the epoch). */
+  expiresAt?: number;
+};
+
+/** Options for push(); everything not given is derived from the message. */
+export type PushOptions = {
+  direct?: boolean;
+  threadId?: string;
+  replyTo?: string;
+  cc?: boolean;
+  priority?: number;
+  /** Time to live in ms. */
+  ttlMs?: number;
+  /** The guard decision on the speaker's message; not kept, only handed to `onPush` (for the transcript). */
+  guard?: GuardDecision;
+};
+
+/** Inbox metadata for a session checkpoint: the id counter, and the metadata for snapshot() queue for queue. */
+export type InboxMeta = { seq: number; queues: Record<string, MessageMeta[]> };
+
+/** One thread's worth of messages for an agent, in queue order. */
+export type Batch = { threadId?: string; messages: ChatMessage[]; meta: MessageMeta[] };
+
+/** What is waiting for one agent (cc copies aside), for schedulers that pick by urgency. */
+export type Pending = {
+  count: number;
+  /** Queue time of the oldest message; undefined when nothing waits. */
+  oldestAt?: number;
+  /** DMs from the user. */
+  userDMs: number;
+  /** DMs from other agents. */
+  agentDMs: number;
+};
+
+/** The thread of a DM among `parties` (sender and recipients). */
+export function dmThread(...parties: string[]): string {
+  return "dm:" + [...new Set(parties.map((p) => p.toLowerCase()))].sort().join("+");
+}
+
+/** The thread a message from `from` to `to` belongs to (see MessageMeta.threadId). */
+export function threadOf(from: string, to: string, direct: boolean, role = "user"): string | undefined {
+  if (role === "system") return undefined;
+  if (!direct) return "group";
+  return dmThread(from, to);
+}
 
 /**
  * Per-agent inbox with simple FIFO queues.
- * Important: nextPromptFor() DRAINS the queue atomically.
+ * Important: nextPromptFor() and nextBatchFor() DRAIN what they return.
  */
 export class Inbox {
   private queues = new Map<string, ChatMessage[]>();
+  /** Parallel to `queues`: one MessageMeta per queued message. */
+  private metas = new Map<string, MessageMeta[]>();
+  private seq = 0;
+
+  /** `onPush` hears about every message queued with push() (not restore()). */
+  constructor(private readonly onPush?: (id: string, msg: ChatMessage, meta: MessageMeta, guard?: GuardDecision) => void) {}
 
   ensure(id: string): void {
     if (!this.queues.has(id)) this.queues.set(id, []);
+    if (!this.metas.has(id)) this.metas.set(id, []);
   }
 
-  /** Push a single message for an agent. */
-  push(id: string, msg: ChatMessage): void {
-    this.ensure(id);
-    this.queues.get(id)!.push(msg);
+  /** Push a single message for an agent; `direct` marks a DM rather than a broadcast. Returns its metadata. */
+  push(id: string, msg: ChatMessage, opts: PushOptions = {}): MessageMeta {
+    const direct = !!opts.direct;
+    const at = Date.now();
+    const meta: MessageMeta = {
+      id: `m${++this.seq}`,
+      at,
+      direct,
+      threadId: opts.threadId ?? threadOf(msg.from === "User" ? "user" : msg.from, id, direct, msg.role),
+      priority: opts.priority ?? 0,
+      ...(opts.replyTo ? { replyTo: opts.replyTo } : {}),
+      ...(opts.cc ? { cc: true } : {}),
+      ...(opts.ttlMs !== undefined ? { expiresAt: at + opts.ttlMs } : {}),
+    };
+    this.add(id, msg, meta);
+    this.onPush?.(id, msg, meta, opts.guard);
+    return meta;
   }
 
   /** Push many messages at once. */
   pushAll(id: string, msgs: ChatMessage[]): void {
     if (!msgs?.length) return;
-    this.ensure(id);
-    const q = this.queues.get(id)!;
-    for (const m of msgs) q.push(m);
+    for (const m of msgs) this.push(id, m);
   }
 
-  /** Whether an agent has any pending work. */
+  /** Whether an agent has any pending work (cc copies alone are none). */
   hasWork(id: string): boolean {
-    const q = this.queues.get(id);
-    return !!q && q.length > 0;
+    this.expire(id);
+    return !!this.metas.get(id)?.some((m) => !m.cc);
   }
 
   /** Whether any agent has work. */
   hasAnyWork(): boolean {
-    for (const [, q] of this.queues) if (q.length > 0) return true;
+    for (const id of this.queues.keys()) if (this.hasWork(id)) return true;
     return false;
   }
 
@@ -45,21 +134,125 @@ export class Inbox {
    * the items again. This prevents infinite reprocessing loops.
    */
   nextPromptFor(id: string): ChatMessage[] {
+    this.expire(id);
     const q = this.queues.get(id);
     if (!q || q.length === 0) return [];
     // Drain atomically
+    this.metas.get(id)!.length = 0;
     return q.splice(0, q.length);
   }
 
+  /**
+   * Drain one thread for an agent: the one holding its highest-priority message,
+   * else the one waiting longest. The agent's system notes and cc copies come
+   * along. The other threads stay queued for later turns, so two DMs and the
+   * group discussion reach the agent as separate contexts.
+   */
+  nextBatchFor(id: string): Batch {
+    this.expire(id);
+    const q = this.queues.get(id) ?? [];
+    const meta = this.metas.get(id) ?? [];
+    const threaded = meta.filter((m) => m.threadId !== undefined && !m.cc);
+    if (threaded.length === 0) return { messages: q.splice(0), meta: meta.splice(0) };
+    const first = threaded.reduce((best, m) => (m.priority > best.priority ? m : best));
+    const threadId = first.threadId;
+    const out: Batch = { threadId, messages: [], meta: [] };
+    for (let i = 0; i < q.length;) {
+      if (meta[i].threadId === threadId || meta[i].threadId === undefined || meta[i].cc) {
+        out.messages.push(...q.splice(i, 1));
+        out.meta.push(...meta.splice(i, 1));
+      } else {
+        i++;
+      }
+    }
+    return out;
+  }
+
+  /** Summary of an agent's queue without consuming it. */
+  pending(id: string): Pending {
+    this.expire(id);
+    const q = this.queues.get(id) ?? [];
+    const meta = this.metas.get(id) ?? [];
+    const out: Pending = { count: 0, userDMs: 0, agentDMs: 0 };
+    q.forEach((m, i) => {
+      const a = meta[i];
+      if (a?.cc) return;
+      out.count++;
+      if (!a) return;
+      if (out.oldestAt === undefined || a.at < out.oldestAt) out.oldestAt = a.at;
+      if (!a.direct) return;
+      if (m.from === "User") out.userDMs++;
+      else out.agentDMs++;
+    });
+    return out;
+  }
+
   /** Inspect (without consuming) the current queue length. Useful in tests. */
   size(id: string): number {
     return this.queues.get(id)?.length ?? 0;
   }
 
+  /** Copy of every non-empty queue (for session checkpoints). */
+  snapshot(): Record<string, ChatMessage[]> {
+    const out: Record<string, ChatMessage[]> = {};
+    for (const [id, q] of this.queues) if (q.length > 0) out[id] = [...q];
+    return out;
+  }
+
+  /** The metadata that goes with snapshot(). */
+  snapshotMeta(): InboxMeta {
+    const queues: Record<string, MessageMeta[]> = {};
+    for (const [id, m] of this.metas) if (m.length > 0) queues[id] = m.map((x) => ({ ...x }));
+    return { seq: this.seq, queues };
+  }
+
+  /**
+   * Replace the queues with a snapshot taken earlier. Without `meta` (sessions saved
+   * before messages had metadata) each message gets a fresh id and its thread is derived.
+   */
+  restore(queues: Record<string, ChatMessage[]>, meta?: InboxMeta): void {
+    for (const [, q] of this.queues) q.length = 0;
+    for (const [, m] of this.metas) m.length = 0;
+    this.seq = Math.max(this.seq, meta?.seq ?? 0); // new ids continue after the saved ones
+    for (const [id, msgs] of Object.entries(queues)) {
+      msgs.forEach((msg, i) => {
+        this.add(id, msg, meta?.queues[id]?.[i] ?? {
+          id: `m${++this.seq}`,
+          at: Date.now(),
+          direct: false,
+          threadId: threadOf(msg.from === "User" ? "user" : msg.from, id, false, msg.role),
+          priority: 0,
+        });
+      });
+    }
+  }
+
   /** Clear an agent queue (mainly for tests and safety). */
   clear(id: string): void {
     const q = this.queues.get(id);
     if (q) q.length = 0;
+    const m = this.metas.get(id);
+    if (m) m.length = 0;
+  }
+
+  private add(id: string, msg: ChatMessage, meta: MessageMeta): void {
+    this.ensure(id);
+    this.queues.get(id)!.push(msg);
+    this.metas.get(id)!.push(meta);
+  }
+
+  /** Drop messages whose TTL ran out. */
+  private expire(id: string): void {
+    const meta = this.metas.get(id);
+    if (!meta?.some((m) => m.expiresAt !== undefined)) return;
+    const now = Date.now();
+    const q = this.queues.get(id)!;
+    for (let i = meta.length - 1; i >= 0; i--) {
+      if (meta[i].expiresAt !== undefined && meta[i].expiresAt! <= now) {
+        q.splice(i, 1);
+        meta.splice(i, 1);
+      }
+    }
   }
 }
 
@@ -0,0 +1,85 @@
+// src/scheduler/priority-scheduler.ts
+// Agents with work go most urgent first, re-scored every tick:
+//
+//   score = roleWeight × (1 + secondsWaited / AGE_STEP_S)
+//         + USER_DM_BONUS  if the user DMed the agent
+//         + AGENT_DM_BONUS if another agent DMed it
+//
+// secondsWaited counts from the oldest queued message, so nobody starves:
+// a weight-1 agent that has waited a minute outranks a fresh lead. Role
+// weights come from `role:` in org.yaml; ORG_ROLE_WEIGHTS="lead=3,reviewer=0.5"
+// overrides the defaults below, and unknown roles weigh 1.
+
+import { C, Logger } from "../logger";
+import { R } from "../runtime/runtime";
+import type { Agent } from "../agents/agent";
+import type { Pending } from "./inbox";
+import { RandomScheduler } from "./random-scheduler";
+
+export const DEFAULT_ROLE_WEIGHTS: Readonly<Record<string, number>> = {
+  lead: 3,
+  manager: 3,
+  architect: 2,
+  planner: 2,
+};
+
+/** Seconds of waiting that add one more role weight to the score. */
+export const AGE_STEP_S = 30;
+export const USER_DM_BONUS = 5;
+export const AGENT_DM_BONUS = 2;
+
+/** DEFAULT_ROLE_WEIGHTS with ORG_ROLE_WEIGHTS ("role=weight,...") on top; bad entries are skipped with a warning. */
+export function roleWeights(env: Record<string, string | undefined> = R.env): Record<string, number> {
+  const out: Record<string, number> = { ...DEFAULT_ROLE_WEIGHTS };
+  for (const entry of String(env.ORG_ROLE_WEIGHTS ?? "").split(",").map((s) => s.trim()).filter(Boolean)) {
+    const m = entry.match(/^([A-Za-z][\w-]*)\s*=\s*(\d+(?:\.\d+)?)$/);
+    if (!m || !(Number(m[2]) > 0)) {
+      Logger.warn(C.yellow(`[scheduler] ORG_ROLE_WEIGHTS: ignoring "${entry}" (expected role=weight, weight > 0)`));
+      continue;
+    }
+    out[m[1].toLowerCase()] = Number(m[2]);
+  }
+  return out;
+}
+
+/** How urgent an agent's queue is; 0 when nothing waits. */
+export function priorityScore(weight: number, p: Pending, now: number): number {
+  if (p.count === 0) return 0;
+  const waitedS = Math.max(0, now - (p.oldestAt ?? now)) / 1000;
+  return weight * (1 + waitedS / AGE_STEP_S)
+    + (p.userDMs > 0 ? USER_DM_BONUS : 0)
+    + (p.agentDMs > 0 ? AGENT_DM_BONUS : 0);
+}
+
+/**
+ * PriorityScheduler
+ * -----------------
+ * RandomScheduler with a scored turn order instead of a shuffle. DM urgency is
+ * part of the score, so a DM does not otherwise let its addressee jump the queue.
+ * Ties keep team order.
+ */
+export class PriorityScheduler extends RandomScheduler {
+  private readonly weights: Record<string, number>;
+
+  constructor(opts: ConstructorParameters<typeof RandomScheduler>[0], weights: Record<string, number> = roleWeights()) {
+    super(opts);
+    this.weights = weights;
+  }
+
+  /** The weight of `a`'s role (1 without one). */
+  weightOf(a: Agent): number {
+    return this.weights[a.role?.toLowerCase() ?? ""] ?? 1;
+  }
+
+  protected order(ready: Agent[]): Agent[] {
+    const now = Date.now();
+    const scored = ready.map((a) => ({ a, score: priorityScore(this.weightOf(a), this.inbox.pending(a.id), now) }));
+    scored.sort((x, y) => y.score - x.score || this.agents.indexOf(x.a) - this.agents.indexOf(y.a));
+    Logger.debug("[scheduler] priority:", scored.map((s) => `${s.a.id}=${s.score.toFixed(2)}`).join(" "));
+    return scored.map((s) => s.a);
+  }
+
+  protected pickNext(scheduled: Agent): Agent {
+    return scheduled;
+  }
+}
@@ -2,22 +2,35 @@
 import { shuffle as fisherYatesShuffle } from "../utils/shuffle-array";
 import { C, Logger } from "../logger";
 import { NoiseFilters } from "./filters";
-import { Inbox } from "./inbox";
-import { routeWithSideEffects } from "./router";
+import { Inbox, dmThread, type MessageMeta } from "./inbox";
+import { ccNote, routeWithSideEffects } from "./router";
+import { roleTags } from "../routing/route-with-tags";
+import { WorkerPool, extractSpawns, parseWorkerCommand } from "./workers";
+import { BudgetTracker, describeExhausted, formatAmount, parseExtension, type Exhausted } from "./budgets";
 import { TagSplitter, TagPart } from "../utils/tag-splitter";
+import { attachImages } from "../io/image-attachments";
+import { Workspaces } from "../sandbox/workspaces";
+import { OutputGate } from "../ui/output-gate";
+import { SchedulerEvents } from "./events";
 import type { GuardDecision } from "../guardrails/guardrail";
 import type { ChatMessage } from "../types";
 import type {
   SchedulerOptions,
+  SchedulerSnapshot,
   AskUserFn,
 } from "./types";
 import { sleep } from "../utils/sleep";
 import { Agent, AgentCallbacks } from "../agents/agent";
 import { IScheduler } from "./scheduler";
 
+/** Inbox priority of a DM from the user: an agent with other threads waiting answers the user first. */
+const USER_DM_PRIORITY = 1;
+/** A guard's nudge is about the turn just taken; dropped if the agent hasn't had another turn by then. */
+const NUDGE_TTL_MS = 5 * 60_000;
+
 type Hooks = {
-  onStreamStart: () => void;
-  onStreamEnd: () => void | Promise<void>;
+  onStreamStart: () => AbortSignal | void | Promise<AbortSignal | void>;
+  onStreamEnd: (signal?: AbortSignal) => void | Promise<void>;
 };
 
 /** Extend options locally to accept the external prompt bridge without changing the global type. */
@@ -43,6 +56,10 @@ type SchedulerOptionsWithBridge = Hooks &
  *  - Idle prompting via askUser every few idle ticks when enabled
  *  - Draining/pause/resume/stop and review integration
  *
+ * Turn order is a policy: each tick the agents with work go in `order()`
+ * (shuffled here), and `pickNext()` lets the agent a DM just addressed jump
+ * the queue. RoundRobinScheduler and PriorityScheduler override both.
+ *
  * Notes:
  *    so newer call sites remain compatible without changing the old surface.
  *  - Added *external prompt bridge* (`readUserLine`) so the UI (TTY controller) can
@@ -55,43 +72,73 @@ export class RandomScheduler implements IScheduler {
    */
   readUserLine?: () => Promise<string | undefined>;
 
-  private readonly agents: Agent[];
+  /** Messages, turns, tool calls, guard decisions, files and patches, as they happen (see ./events). */
+  readonly events = new SchedulerEvents();
+
+  protected readonly agents: Agent[];
   private readonly maxTools: number;
-  private readonly shuffle: <T>(arr: T[]) => T[];
+  protected readonly shuffle: <T>(arr: T[]) => T[];
   private readonly filters = new NoiseFilters();
-  private readonly inbox = new Inbox();
-
-  private running = false;
-  private paused = false;
-  private draining = false;
-
-  private activeAgent: Agent | undefined;
-  private respondingAgent: Agent | undefined;
+  protected readonly inbox = new Inbox((to, message, meta, guard) =>
+    this.events.emit({ type: "message-enqueued", to, message, meta, ...(guard ? { guard } : {}) }));
+  protected readonly workers: WorkerPool;
+  protected readonly budgets: BudgetTracker;
+  /** Agents stopped by an exhausted budget; a DM from the user wakes them (and asks again). */
+  protected readonly parked = new Set<string>();
+  /** Turns that may run at once (--concurrency); 1 runs them one after another. */
+  protected readonly concurrency: number;
+  /** Where agents work when turns overlap; set when concurrency > 1. */
+  private readonly workspaces: Workspaces | undefined;
+  /** Holds back the output of overlapping turns so each one prints as a block. */
+  private readonly gate = new OutputGate();
+  /** Turns in flight under concurrency, by agent id. */
+  private readonly inTurn = new Map<string, Promise<boolean>>();
+  /** Overlapping turns ask the user one at a time. */
+  private userTurn: Promise<unknown> = Promise.resolve();
+
+  protected running = false;
+  protected paused = false;
+  protected draining = false;
+
+  /** Agents mid-turn, by id; several at once under concurrency. */
+  protected readonly activeAgents = new Map<string, Agent>();
+  protected respondingAgent: Agent | undefined;
+  /** When each agent's turn last started, as a turn count, by id. */
+  private readonly turnStarts = new Map<string, number>();
+  private turnCount = 0;
 
   private keepAlive: NodeJS.Timeout | null = null;
 
   private mutedUntil = new Map<string, number>();
-  private lastUserDMTarget: string | null = null;
+  protected lastUserDMTarget: string | null = null;
 
   private readonly idlePromptEvery = 3;
 
   private readonly askUser: AskUserFn;
-  private readonly promptEnabled: boolean;
-  private readonly idleSleepMs: number;
+  protected readonly promptEnabled: boolean;
+  protected readonly idleSleepMs: number;
 
   private readonly onStreamStart: Hooks["onStreamStart"];
   private readonly onStreamEnd: Hooks["onStreamEnd"];
+  private readonly onCheckpoint: SchedulerOptions["onCheckpoint"];
+  private readonly onBudgetStop: SchedulerOptions["onBudgetStop"];
 
-  private interjection: string | undefined = undefined;
+  protected interjection: string | undefined = undefined;
 
   // when true, break the current agent loop and start a new tick immediately
-  private rescheduleNow = false;
+  protected rescheduleNow = false;
 
   constructor(opts: SchedulerOptionsWithBridge) {
     this.onStreamStart = opts.onStreamStart;
     this.onStreamEnd = opts.onStreamEnd;
-    this.agents = opts.agents;
+    this.onCheckpoint = opts.onCheckpoint;
+    this.onBudgetStop = opts.onBudgetStop;
+    this.budgets = new BudgetTracker(opts.budgets);
+    this.agents = [...opts.agents]; // grows and shrinks as workers are spawned and retired
+    this.workers = new WorkerPool(this.agents, this.inbox, opts.spawner);
     this.maxTools = opts.maxTools;
+    this.concurrency = Math.max(1, Math.floor(opts.concurrency ?? 1));
+    this.workspaces = this.concurrency > 1 ? opts.workspaces ?? new Workspaces("paths") : undefined;
     this.shuffle = opts.shuffle ?? fisherYatesShuffle;
     this.askUser = opts.onAskUser;
 
@@ -106,19 +153,11 @@ export class RandomScheduler implements IScheduler {
     }
   }
 
-  /** Start the scheduling loop (idempotent). */
-  private startBody = async (): Promise<void> => {
-    if (this.running) return;
-    this.running = true;
-    this.keepAlive = setInterval(() => {
-      /* keep event loop alive during long idle */
-    }, 30_000);
-
+  /** The scheduling loop; runs until stop(). FSMScheduler replaces it with an explicit state machine. */
+  protected async loop(): Promise<void> {
     let idleTicks = 0;
 
     while (this.running) {
-      this.activeAgent = undefined;
-
       if (this.paused || this.draining) {
         await sleep(25);
         continue;
@@ -131,13 +170,14 @@ export class RandomScheduler implements IScheduler {
         await this.handleUserInterjection(this.interjection, { defaultTargetId: this.lastUserDMTarget ?? undefined, });
         this.interjection = undefined;
         this.rescheduleNow = true;
+        await this.checkpoint();
 
         continue;
       }
 
-      // Choose agents that currently have messages waiting.
-      const ready = this.agents.filter((a) => this.inbox.hasWork(a.id));
-      const order = this.shuffle(ready);
+      // Choose agents that currently have messages waiting (and are not mid-turn).
+      const ready = this.agents.filter((a) => this.inbox.hasWork(a.id) && !this.parked.has(a.id) && !this.inTurn.has(a.id));
+      const order = this.order(ready);
 
       for (const agent of order) {
         if (this.rescheduleNow) break;
@@ -147,179 +187,129 @@ export class RandomScheduler implements IScheduler {
           continue;
         }
 
-        const a = this.respondingAgent ?? agent;
+        const a = this.pickNext(agent);
         this.respondingAgent = undefined;
+        if (this.parked.has(a.id) || this.inTurn.has(a.id)) continue;
 
-        const messages = this.inbox.nextPromptFor(a.id);
-        if (messages.length === 0) {
-          Logger.debug(`no work for ${a.id}`);
-          continue;
+        if (this.concurrency > 1) {
+          while (this.inTurn.size >= this.concurrency) await Promise.race(this.inTurn.values());
+          if (!this.canStart(a)) continue; // shares files with a turn in flight; a later tick picks it up
         }
-        Logger.debug(`drained prompt for ${a.id}:`, JSON.stringify(messages));
-
-        let remaining = this.maxTools;
-        let totalToolsUsed = 0;
-        const messagesIn = [...messages];
-        this.activeAgent = a;
-
-        try {
-          const callbacks: AgentCallbacks = {
-            onRouteCompleted: async (message, numToolsUsed, askedUser) => {
-              if (this.respondingAgent) {
-                return true;
-              }
-
-              if (this.interjection) {
-                await this.handleUserInterjection(this.interjection, {
-                  defaultTargetId: a.id,
-                });
-
-                return true;
-              }
-
-              //
-              if (numToolsUsed > 0) {
-                return false; //If the agent just did a tool call, let it continue
-              }
-
-              const shouldUserRespond = askedUser || this.agents.length === 1 || !this.inbox.hasAnyWork();
-
-              if (!shouldUserRespond) {
-                return false;
-              }
-
-              this.lastUserDMTarget = a.id;
-              const userText = (
-                (await this.getUserText(a.id, message)) ?? ""
-              ).trim();
-              if (userText) {
-                await this.handleUserInterjection(userText, {
-                  defaultTargetId: a.id,
-                });
-              }
-              this.rescheduleNow = true;
-
-              return true;
-            },
-            shouldAbort: () => this.draining,
-            onStreamStart: this.onStreamStart,
-            onStreamEnd: this.onStreamEnd,
-            onRoute: async (message: string, filters: NoiseFilters) => {
-              return await routeWithSideEffects(
-                {
-                  agents: this.agents,
-                  enqueue: (toId, msg) => this.inbox.push(toId, msg),
-                  setRespondingAgent: (id) => {
-                    this.respondingAgent = this.agents.find((x) => x.id === id);
-                    this.rescheduleNow = true;
-                  },
-                  applyGuard: async (from, dec) => this.applyGuardDecision(agent, dec),
-                  setLastUserDMTarget: (id) => {
-                    this.lastUserDMTarget = id;
-                  },
-                },
-                this,
-                message,
-                filters
-              );
-            }
-          }
 
-          const result = await this.activeAgent.respond(
-            messagesIn,
-            Math.max(0, remaining),
-            this.filters,
-            this.agents.filter(agent => agent.id !== a.id),
-            callbacks
-          );
-
-          if (result.length > 0) {
-            didWork = true;
-          }
-
-        } finally {
-          if (totalToolsUsed > 0) { /*this.review.markDirty(agent.id);*/ }
+        const over = this.budgets.check(a);
+        if (over && !(await this.extendBudget(a, over))) {
+          if (!this.running) break;
+          continue;
         }
-      }
 
-      if (!didWork) {
-        idleTicks++;
-        const queuesEmpty = !this.inbox.hasAnyWork();
-
-        // ---------- external prompt bridge owns idle input ----------
-        if (
-          queuesEmpty &&
-          this.promptEnabled &&
-          typeof this.readUserLine === "function"
-        ) {
-          // Prefer the last DM target or the first agent if we need a default
-          const preferred =
-            this.lastUserDMTarget ?? this.agents[0]?.id ?? undefined;
-          const line = ((await this.readUserLine()) ?? "").trim();
-          if (line) {
-            await this.handleUserInterjection(line, {
-              defaultTargetId: preferred,
-            });
-            idleTicks = 0;
-            continue; // next tick will process the enqueued user text
-          }
-          await this.sleep(this.idleSleepMs);
+        const { messages, meta, threadId } = this.inbox.nextBatchFor(a.id);
+        if (messages.length === 0) {
+          Logger.debug(`no work for ${a.id}`);
           continue;
         }
-        // -----------------------------------------------------------------
-
-        if (
-          queuesEmpty &&
-          (idleTicks % this.idlePromptEvery) === 0 &&
-          this.promptEnabled
-        ) {
-          const peers = this.agents.map((x) => x.id);
-          const dec =
-            this.agents[0]?.guardOnIdle?.({
-              idleTicks,
-              peers,
-              queuesEmpty: true,
-            }) || null;
-          const prompt =
-            (dec as any)?.askUser ??
-            `(scheduler)
-All agents are idle. Provide the next concrete instruction or question.`;
-          const preferred = this.agents[0]?.id;
-          if (preferred) this.lastUserDMTarget = preferred;
-          const userText = (
-            (await this.askUser("scheduler", prompt)) ?? ""
-          ).trim();
-          if (userText) {
-            await this.handleUserInterjection(userText, {
-              defaultTargetId: preferred || undefined,
-            });
-          }
-          idleTicks = 0;
-        } else {
-          await this.sleep(this.idleSleepMs); // cooperative idle
+        Logger.debug(`drained prompt for ${a.id} (${threadId ?? "system"}):`, JSON.stringify(messages));
+
+        if (this.concurrency > 1) {
+          const turn = this.gate.run(a.id, () => this.runTurn(a, messages, meta))
+            .catch((e) => { Logger.error(`[${a.id}] turn failed:`, e); return false; })
+            .finally(() => { this.inTurn.delete(a.id); });
+          this.inTurn.set(a.id, turn);
+          didWork = true;
+          continue;
         }
-      } else {
+
+        if (await this.runTurn(a, messages, meta)) didWork = true;
+      }
+
+      if (this.inTurn.size > 0) {
+        // Turns in flight: come back when one ends, or soon, to start turns for new work.
+        await Promise.race([...this.inTurn.values(), this.sleep(this.idleSleepMs)]);
         idleTicks = 0;
+        continue;
       }
+
+      if (!didWork) idleTicks = await this.idleTick(idleTicks + 1);
+      else idleTicks = 0;
     }
+  }
 
-    if (this.keepAlive) {
-      clearInterval(this.keepAlive);
-      this.keepAlive = null;
+  /**
+   * Nothing ran this tick: maybe ask the user for the next instruction, else nap.
+   * Returns the idle tick count to carry on with (0 once the user said something).
+   */
+  protected async idleTick(idleTicks: number): Promise<number> {
+    const queuesEmpty = !this.inbox.hasAnyWork();
+
+    // ---------- external prompt bridge owns idle input ----------
+    if (
+      queuesEmpty &&
+      this.promptEnabled &&
+      typeof this.readUserLine === "function"
+    ) {
+      // Prefer the last DM target or the first agent if we need a default
+      const preferred =
+        this.lastUserDMTarget ?? this.agents[0]?.id ?? undefined;
+      const line = await this.getUserText("scheduler", "");
+      if (line) {
+        await this.handleUserInterjection(line, {
+          defaultTargetId: preferred,
+        });
+        await this.checkpoint();
+        return 0; // next tick will process the enqueued user text
+      }
+      await this.sleep(this.idleSleepMs);
+      return idleTicks;
     }
-  };
+    // -----------------------------------------------------------------
+
+    if (
+      queuesEmpty &&
+      (idleTicks % this.idlePromptEvery) === 0 &&
+      this.promptEnabled
+    ) {
+      const peers = this.agents.map((x) => x.id);
+      const dec =
+        this.agents[0]?.guardOnIdle?.({
+          idleTicks,
+          peers,
+          queuesEmpty: true,
+        }) || null;
+      const prompt =
+        (dec as any)?.askUser ??
+        `(scheduler)
+All agents are idle. Provide the next concrete instruction or question.`;
+      const preferred = this.agents[0]?.id;
+      if (preferred) this.lastUserDMTarget = preferred;
+      const userText = await this.getUserText("scheduler", prompt);
+      if (userText) {
+        await this.handleUserInterjection(userText, {
+          defaultTargetId: preferred || undefined,
+        });
+      }
+      return 0;
+    }
+    await this.sleep(this.idleSleepMs); // cooperative idle
+    return idleTicks;
+  }
 
   // ------------------------------ Public API ------------------------------
   async start() {
+    if (this.running) return;
+    this.running = true;
+    const uninstallGate = this.concurrency > 1 ? this.gate.install() : undefined;
+    this.keepAlive = setInterval(() => {
+      /* keep event loop alive during long idle */
+    }, 30_000);
     try {
-      await this.startBody();
+      await this.loop();
+      await Promise.allSettled(this.inTurn.values());
     } catch (e) {
       Logger.error("Scheduler start failed.", e);
     } finally {
+      uninstallGate?.();
       for (const agent of this.agents) {
         agent.save();
       }
-
       if (this.keepAlive) {
         clearInterval(this.keepAlive);
         this.keepAlive = null;
@@ -360,61 +350,112 @@ All agents are idle. Provide the next concrete instruction or question.`;
     return this.draining;
   }
   hasActiveAgent(): boolean {
-    return !!this.activeAgent;
+    return this.activeAgents.size > 0 || this.inTurn.size > 0;
   }
 
   async interject(text: string) {
     this.interjection = text;
   }
 
+  /** State for `org --resume`: queues, mutes, DM target and live workers. */
+  snapshot(): SchedulerSnapshot {
+    const now = Date.now();
+    const mutedMs: Record<string, number> = {};
+    for (const [id, until] of this.mutedUntil) if (until > now) mutedMs[id] = until - now;
+    return {
+      inbox: this.inbox.snapshot(),
+      inboxMeta: this.inbox.snapshotMeta(),
+      mutedMs,
+      lastUserDMTarget: this.lastUserDMTarget,
+      respondingAgent: this.respondingAgent?.id ?? null,
+      interjection: this.interjection ?? null,
+      workers: this.workers.snapshot(),
+      budgets: this.budgets.snapshot(),
+    };
+  }
+
+  /** Put back a snapshot taken by an earlier run; call before start(). Workers are recreated from their templates. */
+  async restore(s: SchedulerSnapshot): Promise<void> {
+    await this.workers.restore(s.workers);
+    this.inbox.restore(s.inbox, s.inboxMeta);
+    this.mutedUntil.clear();
+    for (const [id, ms] of Object.entries(s.mutedMs)) this.mute(id, ms);
+    this.lastUserDMTarget = s.lastUserDMTarget;
+    this.respondingAgent = s.respondingAgent ? this.findAgentByIdExact(s.respondingAgent) : undefined;
+    this.interjection = s.interjection ?? undefined;
+    if (s.budgets) this.budgets.restore(s.budgets);
+  }
+
   /**
    * Enqueue user text.
    * - Explicit agent tags override everything (we reschedule immediately).
    * - Otherwise DM the default target, else broadcast to group.
    */
-  private async handleUserInterjection(
+  protected async handleUserInterjection(
     text: string,
     opts?: { defaultTargetId?: string }
   ) {
-    const raw = String(text ?? "");
+    const cmd = parseWorkerCommand(String(text ?? ""));
+    if (cmd) {
+      await this.handleWorkerCommand(cmd);
+      return;
+    }
+
+    // ##image:path tags become image parts on whatever message this text turns into.
+    const { text: raw, images, errors } = attachImages(String(text ?? ""));
+    for (const e of errors) Logger.warn(C.yellow(e));
+    const withImages = images.length ? { images } : {};
     const parts = TagSplitter.split(raw, {
       userTokens: ["user"],
       groupTokens: ["group"],
       agentTokens: this.agents.map((a) => a.id), // allowlist
+      roleTokens: roleTags(this.agents),
       fileTokens: ["file"],
       allowFileShorthand: false,
     });
 
-    // Explicit @@agent tag
+    // Explicit @@agent tags, recipient lists and role tags
     const agentParts = parts.filter(
-      (p) => p.kind === "agent"
-    ) as Array<TagPart & { kind: "agent" }>;
+      (p) => p.kind === "agent" || p.kind === "agents"
+    ) as Array<TagPart & { kind: "agent" | "agents" }>;
 
     Logger.debug("agentParts", agentParts);
 
-    if (agentParts.length > 0) {
+    const resolve = (ids: string[]) => ids.map((id) => this.findAgentByIdExact(id)).filter((ag): ag is Agent => !!ag);
+    const addressed = agentParts.map((ap) => ({
+      content: ap.content,
+      to: resolve(ap.kind === "agent" ? [ap.tag] : ap.to),
+      cc: resolve(ap.kind === "agent" ? [] : ap.cc),
+    })).filter((p) => p.to.length + p.cc.length > 0);
+
+    if (addressed.length > 0) {
       const targets: Agent[] = [];
-      for (const ap of agentParts) {
-        const ag = this.findAgentByIdExact(ap.tag);
-        if (ag && !targets.some((t) => t.id === ag.id)) targets.push(ag);
-      }
-      if (targets.length > 0) {
-        this.respondingAgent = targets[0];
-        this.lastUserDMTarget = targets[0].id;
-        for (const ap of agentParts) {
-          const ag = this.findAgentByIdExact(ap.tag);
-          if (!ag) continue;
+      for (const p of addressed) for (const ag of p.to) if (!targets.includes(ag)) targets.push(ag);
+      // As for agents' messages: only a single expected replier jumps the queue.
+      if (targets.length === 1) this.respondingAgent = targets[0];
+      if (targets.length > 0) this.lastUserDMTarget = targets[0].id;
+      for (const p of addressed) {
+        const threadId = p.to.length + p.cc.length > 1 ? dmThread("user", ...p.to.map((a) => a.id), ...p.cc.map((a) => a.id)) : undefined;
+        for (const ag of p.to) {
+          this.parked.delete(ag.id);
           const msg: ChatMessage = {
-            content: ap.content,
+            content: p.content,
             role: "user",
             from: "User",
+            ...withImages,
           };
-          this.inbox.push(ag.id, msg);
+          this.inbox.push(ag.id, msg, { direct: true, threadId, priority: USER_DM_PRIORITY });
           Logger.info(`[user → @@${ag.id}] ${raw}`);
         }
-        this.rescheduleNow = true;
-        return;
+        for (const ag of p.cc) {
+          if (p.to.includes(ag)) continue;
+          const msg: ChatMessage = { content: ccNote(p.to.map((a) => a.id)) + p.content, role: "user", from: "User", ...withImages };
+          this.inbox.push(ag.id, msg, { direct: true, threadId, cc: true });
+          Logger.info(`[user → cc:@@${ag.id}] ${raw}`);
+        }
       }
+      this.rescheduleNow = true;
+      return;
     }
 
     // No explicit tags -> DM default if provided
@@ -425,10 +466,12 @@ All agents are idle. Provide the next concrete instruction or question.`;
           content: raw.trim(),
           role: "user",
           from: "User",
+          ...withImages,
         };
         this.respondingAgent = ag;
         this.lastUserDMTarget = ag.id;
-        this.inbox.push(ag.id, msg);
+        this.parked.delete(ag.id);
+        this.inbox.push(ag.id, msg, { direct: true, priority: USER_DM_PRIORITY });
         Logger.info(`[user → @@${ag.id} (def)] ${raw}`);
         this.rescheduleNow = true;
         return;
@@ -437,17 +480,274 @@ All agents are idle. Provide the next concrete instruction or question.`;
 
     // Fallback: broadcast to group
     for (const a of this.agents) {
-      this.inbox.push(a.id, { content: raw.trim(), role: "user", from: "User" });
+      this.inbox.push(a.id, { content: raw.trim(), role: "user", from: "User", ...withImages });
     }
 
     Logger.debug("End of interjection");
     Logger.info(`[user → @@group] ${raw}`);
   }
 
+  /** `/spawn <template> <task>` and `/retire <worker-id>` from the user. */
+  private async handleWorkerCommand(cmd: NonNullable<ReturnType<typeof parseWorkerCommand>>) {
+    if (cmd.kind === "usage") {
+      Logger.warn(C.yellow(cmd.message));
+      return;
+    }
+    if (cmd.kind === "retire") {
+      const ag = this.findAgentByIdExact(cmd.id);
+      if (!ag || !this.workers.isWorker(ag.id)) {
+        Logger.warn(C.yellow(`[retire] ${cmd.id} is not a spawned worker`));
+        return;
+      }
+      this.workers.retire(ag.id);
+      this.forget(ag.id);
+      return;
+    }
+    try {
+      const ag = await this.workers.spawn(cmd.request, "user");
+      this.lastUserDMTarget = ag.id;
+      this.rescheduleNow = true;
+    } catch (e: any) {
+      Logger.warn(C.yellow(`[spawn] ${e?.message ?? e}`));
+    }
+  }
+
+  /**
+   * One turn of `a` on the drained `messages` (with their inbox metadata, if any);
+   * true when it produced a reply. What the agent sends is linked back to the
+   * message it answers.
+   */
+  protected async runTurn(a: Agent, messages: ChatMessage[], meta: MessageMeta[] = []): Promise<boolean> {
+    let remaining = a.budgets?.maxTools ?? this.maxTools;
+    let totalToolsUsed = 0;
+    const messagesIn = [...messages];
+    this.activeAgents.set(a.id, a);
+    this.turnStarts.set(a.id, ++this.turnCount);
+    let didWork = false;
+    if (this.workspaces) a.workDir = this.workspaces.dirFor(a);
+    const started = Date.now();
+    this.events.emit({ type: "turn-started", agentId: a.id, messages: messages.length });
+
+    try {
+      const callbacks: AgentCallbacks = {
+        onRouteCompleted: async (message, numToolsUsed, askedUser) => {
+          if (this.respondingAgent) {
+            return true;
+          }
+
+          if (this.interjection) {
+            await this.handleUserInterjection(this.interjection, {
+              defaultTargetId: a.id,
+            });
+
+            return true;
+          }
+
+          //
+          if (numToolsUsed > 0) {
+            return false; //If the agent just did a tool call, let it continue
+          }
+
+          // With concurrent turns, the team is only idle once nobody else is mid-turn either.
+          const othersBusy = [...this.inTurn.keys()].some((id) => id !== a.id);
+          const shouldUserRespond = askedUser || this.agents.length === 1 || (!this.inbox.hasAnyWork() && !othersBusy);
+
+          if (!shouldUserRespond) {
+            return false;
+          }
+
+          // A worker that just reported is about to be retired; don't queue the reply for it.
+          const replyTarget = this.workers.hasReported(a.id) ? undefined : a.id;
+          if (replyTarget) this.lastUserDMTarget = replyTarget;
+          const userText = (
+            (await this.getUserText(a.id, message)) ?? ""
+          ).trim();
+          if (userText) {
+            await this.handleUserInterjection(userText, {
+              defaultTargetId: replyTarget,
+            });
+          }
+          this.rescheduleNow = true;
+
+          return true;
+        },
+        shouldAbort: () => this.draining,
+        onTool: {
+          started: (call) => this.events.emit({ type: "tool-started", agentId: a.id, ...call }),
+          finished: (call) => this.events.emit({ type: "tool-finished", agentId: a.id, ...call }),
+        },
+        onStreamStart: this.onStreamStart,
+        onStreamEnd: this.onStreamEnd,
+        onRoute: async (message: string, filters: NoiseFilters) => {
+          const { text, requests } = extractSpawns(message);
+          for (const req of requests) {
+            try {
+              await this.workers.spawn(req, a.id);
+            } catch (e: any) {
+              this.inbox.push(a.id, { role: "system", from: "System", content: `[spawn] ${e?.message ?? e}` });
+            }
+          }
+          if (requests.length > 0 && !text) return false;
+
+          return await routeWithSideEffects(
+            {
+              agents: this.agents,
+              enqueue: (toId, msg, kind, opts) => {
+                this.inbox.push(toId, msg, { direct: kind === "dm", replyTo: this.answering(messages, meta, toId, kind), ...opts });
+                this.workers.noteDelivery(a.id, toId);
+              },
+              setRespondingAgent: (id) => {
+                this.respondingAgent = this.agents.find((x) => x.id === id);
+                this.rescheduleNow = true;
+              },
+              applyGuard: async (from, dec) => this.applyGuardDecision(a, dec),
+              setLastUserDMTarget: (id) => {
+                this.lastUserDMTarget = id;
+                this.workers.noteDelivery(a.id, "user");
+              },
+              userAddressed: (content) => this.events.emit({ type: "user-addressed", agentId: a.id, content }),
+              fileRoot: a.workDir,
+              refuseWrite: this.workspaces ? (rel) => this.workspaces!.refuseWrite(a, rel) : undefined,
+              fileWritten: (file, bytes) => this.events.emit({ type: "file-written", agentId: a.id, path: file, bytes }),
+            },
+            a, // the speaker: DMs carry its id, and @@group skips it
+            text,
+            filters
+          );
+        }
+      }
+
+      const result = await this.budgets.charge(a, () => a.respond(
+        messagesIn,
+        Math.max(0, remaining),
+        this.filters,
+        this.agents.filter(agent => agent.id !== a.id),
+        callbacks
+      ));
+
+      if (result.length > 0) {
+        didWork = true;
+      }
+
+      return didWork;
+    } finally {
+      this.activeAgents.delete(a.id);
+      this.events.emit({ type: "turn-ended", agentId: a.id, replied: didWork, ms: Date.now() - started });
+      if (totalToolsUsed > 0) { /*this.review.markDirty(agent.id);*/ }
+      if (this.workers.retireIfDone(a.id)) this.forget(a.id);
+      else if (this.onCheckpoint) await a.save(); // respond() saves without waiting; the checkpoint copies memory
+      await this.checkpoint();
+    }
+  }
+
+  // ------------------------------ Policy ------------------------------
+
+  /** Id of the agent whose turn started last, among overlapping turns too. */
+  protected get lastRan(): string | undefined {
+    let last: string | undefined;
+    let at = 0;
+    for (const [id, n] of this.turnStarts) if (n > at) [last, at] = [id, n];
+    return last;
+  }
+
+  /** The order agents with work take their turns in this tick. */
+  protected order(ready: Agent[]): Agent[] {
+    return this.shuffle(ready);
+  }
+
+  /** Who actually goes when `scheduled` is up: the agent a DM just addressed, if any. */
+  protected pickNext(scheduled: Agent): Agent {
+    return this.respondingAgent ?? scheduled;
+  }
+
   // ------------------------------ Internals ------------------------------
 
-  // Inside RandomScheduler
-  private async getUserText(label: string, prompt: string): Promise<string> {
+  /** Id of the latest message in the turn's batch that a reply to `toId` answers: a DM from it, or the group's last word. */
+  private answering(messages: ChatMessage[], meta: MessageMeta[], toId: string, kind: "dm" | "group"): string | undefined {
+    for (let i = Math.min(messages.length, meta.length) - 1; i >= 0; i--) {
+      const from = messages[i].from.toLowerCase();
+      if (kind === "dm" ? meta[i].direct && from === toId.toLowerCase() : meta[i].threadId === "group") return meta[i].id;
+    }
+    return undefined;
+  }
+
+  /**
+   * A budget ran out before `a`'s turn: tell the agent and ask the user to extend it.
+   * Returns false when the agent is parked, or the whole session stopped.
+   */
+  protected async extendBudget(a: Agent, ex: Exhausted): Promise<boolean> {
+    const what = describeExhausted(ex);
+    Logger.warn(C.yellow(`[budget] ${what}.`));
+
+    let add: number | null = null;
+    if (this.promptEnabled) {
+      const example = ex.kind === "time" ? "5m" : ex.kind === "cost" ? "$1" : String(ex.limit);
+      const prompt = `[budget] Extend it? y adds another ${formatAmount(ex.kind, ex.limit)}, or give an amount (e.g. ${example}); n stops ${ex.scope === "agent" ? a.id : "the session"}.`;
+      for (;;) {
+        Logger.info(C.yellow(prompt)); // readUserLine does not render the question itself
+        add = parseExtension(await this.getUserText("budget", prompt), ex);
+        if (add === null || !Number.isNaN(add)) break;
+        Logger.warn(C.yellow(`[budget] answer y, n or an amount`));
+      }
+    }
+
+    const yours = describeExhausted(ex, true);
+    if (add !== null) {
+      this.budgets.extend(ex, add);
+      this.inbox.push(a.id, { role: "system", from: "System", content: `[budget] ${yours}. The user added ${formatAmount(ex.kind, add)}; finish the task without wasted steps.` });
+      Logger.info(C.magenta(`[budget] +${formatAmount(ex.kind, add)} (${ex.scope === "agent" ? a.id : "session"})`));
+      return true;
+    }
+
+    const stopped = this.promptEnabled ? "The user stopped you here." : "The session ends here.";
+    this.inbox.push(a.id, { role: "system", from: "System", content: `[budget] ${yours}. ${stopped}` });
+    if (ex.scope === "agent" && this.promptEnabled) {
+      this.parked.add(a.id);
+      Logger.info(C.magenta(`[budget] ${a.id} is stopped; DM it to ask for more.`));
+      await this.checkpoint();
+      return false;
+    }
+    // Session budget, or nobody to ask: end the run.
+    Logger.info(C.magenta(`[budget] stopping the session.`));
+    await this.checkpoint();
+    this.stop();
+    await this.onBudgetStop?.();
+    return false;
+  }
+
+  protected async checkpoint() {
+    if (!this.onCheckpoint) return;
+    try {
+      await this.onCheckpoint(this.snapshot());
+    } catch (e) {
+      Logger.warn(C.yellow(`[session] checkpoint failed: ${e instanceof Error ? e.message : String(e)}`));
+    }
+  }
+
+  /** Drop scheduler state that still points at a retired worker. */
+  private forget(id: string) {
+    this.mutedUntil.delete(id);
+    this.turnStarts.delete(id);
+    if (this.lastUserDMTarget === id) this.lastUserDMTarget = null;
+    if (this.respondingAgent?.id === id) this.respondingAgent = undefined;
+  }
+
+  /** Whether `a` may start a turn next to the turns in flight (see sandbox/workspaces). */
+  private canStart(a: Agent): boolean {
+    const running = this.agents.filter((x) => this.inTurn.has(x.id));
+    return this.workspaces?.canRunBeside(a, running) ?? running.length === 0;
+  }
+
+  /** Ask the user; an overlapping turn first waits until it owns the terminal, so its question is on screen. */
+  protected async getUserText(label: string, prompt: string): Promise<string> {
+    if (this.concurrency <= 1) return this.readUserText(label, prompt);
+    await this.gate.hold(label);
+    const answer = this.userTurn.then(() => this.readUserText(label, prompt));
+    this.userTurn = answer.catch(() => undefined);
+    return answer;
+  }
+
+  private async readUserText(label: string, prompt: string): Promise<string> {
     // If an external bridge exists, let the UI own the prompt & echo.
     if (typeof this.readUserLine === "function") {
       // Optional: emit the label/prompt to logs/UI; do NOT read here.
@@ -459,7 +759,7 @@ All agents are idle. Provide the next concrete instruction or question.`;
   }
 
 
-  private isMuted(id: string): boolean {
+  protected isMuted(id: string): boolean {
     const until = this.mutedUntil.get(id) ?? 0;
     return Date.now() < until;
   }
@@ -469,6 +769,7 @@ All agents are idle. Provide the next concrete instruction or question.`;
   }
 
   private async applyGuardDecision(agent: Agent, dec: GuardDecision) {
+    this.events.emit({ type: "guard-decision", agentId: agent.id, decision: dec });
     if (dec.warnings && dec.warnings.length) {
       Logger.debug(`[guard][${agent.id}] ` + (dec as any).warnings.join("; "));
     }
@@ -477,7 +778,7 @@ All agents are idle. Provide the next concrete instruction or question.`;
         content: dec.nudge,
         from: "System",
         role: "system",
-      });
+      }, { ttlMs: NUDGE_TTL_MS });
     }
     if (dec.muteMs && dec.muteMs > 0) {
       this.mute(agent.id, dec.muteMs);
@@ -497,7 +798,7 @@ All agents are idle. Provide the next concrete instruction or question.`;
     return this.agents.find((a) => a.id.toLowerCase() === t);
   }
 
-  private sleep(ms: number) {
+  protected sleep(ms: number) {
     return new Promise<void>((r) => setTimeout(r, ms));
   }
 }
\ No newline at end of file
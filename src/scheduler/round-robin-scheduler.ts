@@ -0,0 +1,23 @@
+// src/scheduler/round-robin-scheduler.ts
+import type { Agent } from "../agents/agent";
+import { RandomScheduler } from "./random-scheduler";
+
+/**
+ * RoundRobinScheduler
+ * -------------------
+ * Agents with work take turns in team order, starting after whoever went last.
+ * A DM does not let its addressee jump the queue; it waits for its turn like
+ * everything else, so no pair of agents can keep the floor to themselves.
+ */
+export class RoundRobinScheduler extends RandomScheduler {
+  protected order(ready: Agent[]): Agent[] {
+    const n = this.agents.length;
+    const last = this.agents.findIndex((a) => a.id === this.lastRan);
+    const distance = (a: Agent) => (this.agents.indexOf(a) - last - 1 + n) % n;
+    return [...ready].sort((x, y) => distance(x) - distance(y));
+  }
+
+  protected pickNext(scheduled: Agent): Agent {
+    return scheduled;
+  }
+}
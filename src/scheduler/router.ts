@@ -7,28 +7,46 @@ import type { GuardDecision } from "../guardrails/guardrail";
 import type { Responder } from "./types";
 import type { ChatMessage } from "../types";
 import { NoiseFilters } from "./filters";
+import { dmThread } from "./inbox";
 import { ISandboxSession } from "../sandbox/types";
 import { R } from "../runtime/runtime";
+import * as path from "path";
 
 /** Side-effects required by routing (DMs, group fanout, files, user prompts). */
 interface RouteDeps {
     /** Known agents (for lookup and fan-out). */
     agents: Responder[];
-    /** Enqueue a message for an agent. */
-    enqueue: (toId: string, msg: ChatMessage) => void;
+    /**
+     * Enqueue a message for an agent; `kind` says whether it was a DM or a group broadcast.
+     * A DM to several carries their shared `threadId`; `cc` marks a copy that needs no reply.
+     */
+    enqueue: (toId: string, msg: ChatMessage, kind: "dm" | "group", opts?: { threadId?: string; cc?: boolean; guard?: GuardDecision }) => void;
     /** Provide the scheduler a hint who is likely to reply next. */
     setRespondingAgent: (id?: string) => void;
     /** Called when guardrails return a decision. */
     applyGuard: (from: Responder, dec: GuardDecision) => Promise<void>;
     /** Remember last agent that addressed @@user. */
     setLastUserDMTarget: (id: string) => void;
+    /** Called with what the speaker said to @@user. */
+    userAddressed?: (content: string) => void;
+    /** Where ##file blocks land (the speaker's workspace); default the current directory. */
+    fileRoot?: string;
+    /** Why the speaker may not write `rel`, or undefined when it may (see sandbox/workspaces). */
+    refuseWrite?: (rel: string) => string | undefined;
+    /** Called after a ##file block is written to `file`. */
+    fileWritten?: (file: string, bytes: number) => void;
 }
 
 
+/** Prefix for a cc copy, so its reader knows who was asked and that no reply is expected. */
+export function ccNote(to: string[]): string {
+    return to.length ? `[cc, to ${to.join(", ")}; no reply needed] ` : "[cc; no reply needed] ";
+}
+
 const finalizeRepository = async (): Promise<void> => { }
 
 /**
- * Route a model message that may contain @@agent, @@group, @@user, ##file.
+ * Route a model message that may contain @@agent, @@a,b,cc:c, @@<role>s, @@group, @@user, ##file.
  * Returns true if the message contained a request to talk to the user.
  */
 export async function routeWithSideEffects(
@@ -41,7 +59,17 @@ export async function routeWithSideEffects(
         onAgent: async (_from, to, cleaned) => {
             //Logger.info(C.blue(`[@@${from} → @@${to}]`));
             deps.setRespondingAgent(to);
-            if (cleaned) deps.enqueue(to, { role: "user", from: fromAgent.id, content: cleaned });
+            if (cleaned) deps.enqueue(to, { role: "user", from: fromAgent.id, content: cleaned }, "dm");
+        },
+        onAgents: async (_from, to, cc, cleaned) => {
+            const others = (ids: string[]) => ids.filter((id) => id.toLowerCase() !== fromAgent.id.toLowerCase());
+            const [replyFrom, copied] = [others(to), others(cc)];
+            // Only a single expected replier jumps the queue; a multicast waits its turn like a broadcast.
+            if (replyFrom.length === 1) deps.setRespondingAgent(replyFrom[0]);
+            if (!cleaned) return;
+            const threadId = dmThread(fromAgent.id, ...replyFrom, ...copied);
+            for (const id of replyFrom) deps.enqueue(id, { role: "user", from: fromAgent.id, content: cleaned }, "dm", { threadId });
+            for (const id of copied) deps.enqueue(id, { role: "user", from: fromAgent.id, content: ccNote(replyFrom) + cleaned }, "dm", { threadId, cc: true });
         },
         onGroup: async (from, cleaned) => {
             const peers = deps.agents.map(a => a.id);
@@ -54,11 +82,12 @@ export async function routeWithSideEffects(
             //Logger.info(C.blue(`[user → @@${from}] @@group`));
             for (const a of deps.agents) {
                 if (a.id === fromAgent.id) continue;
-                if (cleaned) deps.enqueue(a.id, { role: "user", from: fromAgent.id, content: cleaned });
+                if (cleaned) deps.enqueue(a.id, { role: "user", from: fromAgent.id, content: cleaned }, "group", dec ? { guard: dec } : undefined);
             }
         },
-        onUser: async (from, _content) => {
+        onUser: async (from, content) => {
             //Logger.info(C.blue(`[@@${from} → @@user]`));
+            deps.userAddressed?.(content);
             // In non-interactive mode, an @@user tag should terminate cleanly
             if (!R.stdin.isTTY) {
                 Logger.info("Exiting...");
@@ -74,6 +103,13 @@ export async function routeWithSideEffects(
             const rel = String(name || "").replace(/^\.\/+/, "");
             const bytes = Buffer.byteLength(body, "utf8");
 
+            const refused = deps.refuseWrite?.(rel);
+            if (refused) {
+                Logger.warn(C.yellow(`[workspace] ${fromAgent.id}: ${refused}`));
+                deps.enqueue(fromAgent.id, { role: "system", from: "System", content: `[workspace] ${refused}` }, "dm");
+                return;
+            }
+
             // Optional confirmation only when interactive
             if (R.stdin.isTTY) {
                 const confirm = `${body}\n***** Write to file? [y/N] ${rel}\n`;
@@ -88,9 +124,11 @@ export async function routeWithSideEffects(
             }
 
             const writer = new FileWriter();
-            await writer.write(rel, body);
+            const file = deps.fileRoot ? path.join(deps.fileRoot, rel) : rel;
+            await writer.write(file, body);
+            deps.fileWritten?.(file, bytes);
 
-            Logger.info(C.magenta(`Written to /work/${rel} (${bytes} bytes)`));
+            Logger.info(C.magenta(`Written to ${deps.fileRoot ? file : `/work/${rel}`} (${bytes} bytes)`));
         }
         ,
     },
@@ -3,6 +3,7 @@
  */
 
 import { ChatMessage } from "../drivers/types";
+import type { SchedulerEvents } from "./events";
 
 type ReviewMode = "ask" | "auto" | "never";
 type OnAskUser = (fromAgent: string, content: string) => Promise<void>;
@@ -21,6 +22,9 @@ export interface IScheduler {
   drain(): Promise<boolean>;
 
   interject(s: string): Promise<void>;
+
+  /** What happens during the run, for UIs, metrics and plugins to subscribe to. */
+  readonly events: SchedulerEvents;
 }
 
 /** Agent shape schedulers coordinate. Only what we need. */
@@ -1,5 +1,10 @@
 import type { ISandboxSession } from "../sandbox/types";
+import type { ChatMessage } from "../types";
 import { Agent } from "../agents/agent";
+import type { AgentSpawner } from "../agents/agent-spawner";
+import type { BudgetSnapshot, Limits } from "./budgets";
+import type { InboxMeta } from "./inbox";
+import type { Workspaces } from "../sandbox/workspaces";
 
 
 export type ChatResponse = { message: string; toolsUsed: number };
@@ -18,4 +23,35 @@ export type SchedulerOptions = {
   idleSleepMs?: number;
   shuffle?: <T>(arr: T[]) => T[];
   sandbox: ISandboxSession;            // <-- NEW (required)
+  /** Templates for `##spawn:` and `/spawn`; without it spawning is refused. */
+  spawner?: AgentSpawner;
+  /** Called after every agent turn and user input, with the state worth keeping (see runtime/session-state). */
+  onCheckpoint?: (snapshot: SchedulerSnapshot) => void | Promise<void>;
+  /** Session-wide hop/token/time/cost limits (see scheduler/budgets); agents bring their own. */
+  budgets?: Limits;
+  /** Called when a budget runs out and the session stops (the user said no, or nobody can be asked). */
+  onBudgetStop?: () => void | Promise<void>;
+  /** Agent turns that may run at once (--concurrency); default 1. */
+  concurrency?: number;
+  /** Isolation for concurrent turns; default `paths` mode (see sandbox/workspaces). */
+  workspaces?: Workspaces;
+};
+
+export type WorkerRecord = { id: string; template: string; parent: string };
+
+/** Scheduler state that `org --resume` restores. */
+export type SchedulerSnapshot = {
+  /** Queued messages per agent id. */
+  inbox: Record<string, ChatMessage[]>;
+  /** Ids, threads and links for those messages. Absent in sessions saved before messages had metadata. */
+  inboxMeta?: InboxMeta;
+  /** Remaining mute per agent, in ms. */
+  mutedMs: Record<string, number>;
+  lastUserDMTarget: string | null;
+  respondingAgent: string | null;
+  /** User input received but not yet routed. */
+  interjection: string | null;
+  workers: WorkerRecord[];
+  /** Absent in sessions saved before budgets were tracked. */
+  budgets?: BudgetSnapshot;
 };
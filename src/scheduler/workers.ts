@@ -0,0 +1,151 @@
+// src/scheduler/workers.ts
+// Short-lived agents spawned at runtime (manager/worker patterns).
+//
+//   Agents:  ##spawn:<template> <task>      (one per line; the task is the rest of the line)
+//   User:    /spawn <template> <task>       /retire <worker-id>
+//
+// A worker gets the task from whoever spawned it (its parent) and is retired
+// once it reports back to that parent with @@<parent>, or @@user for the user.
+
+import { Logger, C } from "../logger";
+import { R } from "../runtime/runtime";
+import type { Agent } from "../agents/agent";
+import type { AgentSpawner } from "../agents/agent-spawner";
+import type { Inbox } from "./inbox";
+import type { WorkerRecord } from "./types";
+
+export type SpawnRequest = { template: string; task: string };
+
+const SPAWN_TAG_RE = /^[ \t]*##spawn:([A-Za-z0-9][\w.-]*)[ \t]*(.*)\n?/gm;
+const SPAWN_CMD_RE = /^\/spawn\s+([A-Za-z0-9][\w.-]*)\s*([\s\S]*)$/;
+const RETIRE_CMD_RE = /^\/retire\s+(\S+)\s*$/;
+
+const DEFAULT_MAX_WORKERS = 4;
+
+function maxWorkers(): number {
+    const raw = R.env.ORG_MAX_WORKERS;
+    const n = Number(raw);
+    return raw && Number.isInteger(n) && n >= 0 ? n : DEFAULT_MAX_WORKERS;
+}
+
+/** Pull every `##spawn:` line out of an agent's message. */
+export function extractSpawns(text: string): { text: string; requests: SpawnRequest[] } {
+    const requests: SpawnRequest[] = [];
+    const out = text.replace(SPAWN_TAG_RE, (_line, template: string, task: string) => {
+        requests.push({ template, task: task.trim() });
+        return "";
+    });
+    return { text: requests.length ? out.trim() : text, requests };
+}
+
+/** Parse a `/spawn` or `/retire` line typed by the user; null for anything else. */
+export function parseWorkerCommand(line: string):
+    | { kind: "spawn"; request: SpawnRequest }
+    | { kind: "retire"; id: string }
+    | { kind: "usage"; message: string }
+    | null {
+    const s = line.trim();
+    if (!/^\/(spawn|retire)\b/.test(s)) return null;
+    const spawn = s.match(SPAWN_CMD_RE);
+    if (spawn) {
+        if (!spawn[2].trim()) return { kind: "usage", message: "usage: /spawn <template> <task>" };
+        return { kind: "spawn", request: { template: spawn[1], task: spawn[2].trim() } };
+    }
+    const retire = s.match(RETIRE_CMD_RE);
+    if (retire) return { kind: "retire", id: retire[1].replace(/^@@/, "") };
+    return { kind: "usage", message: s.startsWith("/spawn") ? "usage: /spawn <template> <task>" : "usage: /retire <worker-id>" };
+}
+
+type Worker = Omit<WorkerRecord, "id">;
+
+/**
+ * Adds spawned agents to the scheduler's agent list and inbox, and takes them
+ * out again. `agents` is the scheduler's own array and is edited in place.
+ */
+export class WorkerPool {
+    private readonly workers = new Map<string, Worker>();
+    private readonly reported = new Set<string>();
+
+    constructor(
+        private readonly agents: Agent[],
+        private readonly inbox: Inbox,
+        private readonly spawner?: AgentSpawner,
+    ) { }
+
+    isWorker(id: string): boolean {
+        return this.workers.has(id);
+    }
+
+    /** Spawn a worker for `parent` ("user" or an agent id) and queue its task. Throws on failure. */
+    async spawn(req: SpawnRequest, parent: string): Promise<Agent> {
+        if (!this.spawner) throw new Error("spawning is not available in this session");
+        if (!req.task) throw new Error(`##spawn:${req.template} needs a task after the template name`);
+        if (this.workers.size >= maxWorkers()) {
+            throw new Error(`too many workers (${this.workers.size}; ORG_MAX_WORKERS=${maxWorkers()}); wait for one to report back`);
+        }
+
+        const agent = await this.spawner.spawn(req.template, this.agents.map(a => a.id));
+        this.agents.push(agent);
+        this.workers.set(agent.id, { parent, template: req.template });
+        this.inbox.ensure(agent.id);
+
+        const replyTo = parent === "user" ? "@@user" : `@@${parent}`;
+        this.inbox.push(agent.id, {
+            role: "system",
+            from: "System",
+            content: `You are ${agent.id}, spawned by ${parent} for a single task. ` +
+                `When it is done, report the result with ${replyTo}; you are retired after that.`,
+        });
+        this.inbox.push(agent.id, { role: "user", from: parent === "user" ? "User" : parent, content: req.task }, { direct: true });
+        Logger.info(C.magenta(`[spawn] ${parent} → @@${agent.id} (${req.template})`));
+        return agent;
+    }
+
+    /** Live workers, for session checkpoints. */
+    snapshot(): WorkerRecord[] {
+        return [...this.workers].map(([id, w]) => ({ id, ...w }));
+    }
+
+    /** Recreate workers from a checkpoint under their old ids; their queues come from the inbox snapshot. */
+    async restore(recs: WorkerRecord[]): Promise<void> {
+        for (const rec of recs) {
+            if (!this.spawner) throw new Error("spawning is not available in this session");
+            const agent = await this.spawner.respawn(rec.template, rec.id);
+            this.agents.push(agent);
+            this.workers.set(agent.id, { template: rec.template, parent: rec.parent });
+            this.inbox.ensure(agent.id);
+        }
+    }
+
+    /** Note a message from `from` to `to` ("user" for @@user); a worker answering its parent is done. */
+    noteDelivery(from: string, to: string): void {
+        const w = this.workers.get(from);
+        if (w && w.parent.toLowerCase() === to.toLowerCase()) this.reported.add(from);
+    }
+
+    /** Whether worker `id` has reported back and will be retired at the end of its turn. */
+    hasReported(id: string): boolean {
+        return this.reported.has(id);
+    }
+
+    /** Retire `id` if it has reported back during its turn. */
+    retireIfDone(id: string): boolean {
+        if (!this.reported.has(id)) return false;
+        this.retire(id);
+        return true;
+    }
+
+    /** Remove a worker: it leaves the agent list and its queue is dropped. */
+    retire(id: string): Agent | undefined {
+        const w = this.workers.get(id);
+        if (!w) return undefined;
+        this.workers.delete(id);
+        this.reported.delete(id);
+        this.inbox.clear(id);
+        const i = this.agents.findIndex(a => a.id === id);
+        const [agent] = i >= 0 ? this.agents.splice(i, 1) : [];
+        agent?.save();
+        Logger.info(C.magenta(`[retire] @@${id} (${w.template}, spawned by ${w.parent})`));
+        return agent;
+    }
+}
@@ -91,7 +91,7 @@ export async function runSh(
         const idleFor = Date.now() - lastChildOutputAt;
         if (idleFor >= idleHeartbeatMs && exitCode === null) {
           // print a dot on stderr to indicate we're alive
-          R.stderr.write(C.bold("."));
+          Logger.emit(() => R.stderr.write(C.bold(".")));
           printedHeartbeat = true;
           // Note: DO NOT update lastChildOutputAt here — only child output resets idleness
         }
@@ -115,10 +115,10 @@ export async function runSh(
 
       // If we were printing dots, break the line once before showing real output.
       if (printedHeartbeat && !brokeLineAfterHeartbeat) {
-        R.stderr.write("\n");
+        Logger.emit(() => R.stderr.write("\n"));
         brokeLineAfterHeartbeat = true;
       }
-      R.stdout.write(s);
+      Logger.emit(() => R.stdout.write(s));
     });
 
     child.stderr.on("data", (buf: Buffer) => {
@@ -127,17 +127,17 @@ export async function runSh(
       err += s;
 
       if (printedHeartbeat && !brokeLineAfterHeartbeat) {
-        R.stderr.write("\n");
+        Logger.emit(() => R.stderr.write("\n"));
         brokeLineAfterHeartbeat = true;
       }
-      R.stderr.write(s);
+      Logger.emit(() => R.stderr.write(s));
     });
 
     child.on("error", (e) => {
       clearHeartbeat();
       // Ensure we end the heartbeat line cleanly
-      if (printedHeartbeat && !brokeLineAfterHeartbeat) R.stderr.write("\n");
-      if (!printedHeartbeat) R.stderr.write("\n");
+      if (printedHeartbeat && !brokeLineAfterHeartbeat) Logger.emit(() => R.stderr.write("\n"));
+      if (!printedHeartbeat) Logger.emit(() => R.stderr.write("\n"));
 
       resolve({
         ok: false,
@@ -153,10 +153,10 @@ export async function runSh(
       clearHeartbeat();
 
       // Finish the line cleanly if we showed a heartbeat but no output followed.
-      if (printedHeartbeat && !brokeLineAfterHeartbeat) R.stderr.write("\n");
+      if (printedHeartbeat && !brokeLineAfterHeartbeat) Logger.emit(() => R.stderr.write("\n"));
       if (!printedHeartbeat && out.length === 0 && err.length === 0) {
         // no output at all — still end the line
-        R.stderr.write("\n");
+        Logger.emit(() => R.stderr.write("\n"));
       }
 
       resolve({
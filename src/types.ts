@@ -1,9 +1,12 @@
+import type { ChatImage } from "./drivers/types";
+
 type Role = 'user' | 'assistant' | 'system' | 'tool';
 
 export interface ChatMessage {
   role: string;
   from: string;
   content: string;
+  images?: ChatImage[];
 }
 
 interface ToolCall {
@@ -0,0 +1,64 @@
+// src/ui/output-gate.ts
+// With --concurrency above 1 several agent turns run at once. The gate keeps the
+// terminal readable: one turn owns it and prints live, the others' output is held
+// and played back when the owner's turn ends, so every turn reads as one block.
+// Writes made outside any turn (scheduler notices) go straight through.
+
+import { AsyncLocalStorage } from "node:async_hooks";
+import { Logger } from "../logger";
+
+type Lane = { backlog: Array<() => void>; finished: boolean; waiters: Array<() => void> };
+
+export class OutputGate {
+  private readonly current = new AsyncLocalStorage<string>();
+  /** Turns in the order they started; the first one owns the terminal. */
+  private readonly lanes = new Map<string, Lane>();
+
+  /** Route every Logger write through the gate; returns the uninstaller. */
+  install(): () => void {
+    const prev = Logger.gate;
+    Logger.gate = (write) => this.write(write);
+    return () => { Logger.gate = prev; };
+  }
+
+  /** Run `fn` as `id`'s turn. Its output is held while an earlier turn owns the terminal. */
+  async run<T>(id: string, fn: () => Promise<T>): Promise<T> {
+    this.lanes.set(id, { backlog: [], finished: false, waiters: [] });
+    try {
+      return await this.current.run(id, fn);
+    } finally {
+      this.lanes.get(id)!.finished = true;
+      this.advance();
+    }
+  }
+
+  /** Resolves once `id`'s turn owns the terminal, e.g. before it prompts the user. */
+  hold(id: string): Promise<void> {
+    const lane = this.lanes.get(id);
+    if (!lane || this.owner() === id) return Promise.resolve();
+    return new Promise((resolve) => lane.waiters.push(resolve));
+  }
+
+  private owner(): string | undefined {
+    return this.lanes.keys().next().value;
+  }
+
+  private write(write: () => void): void {
+    const id = this.current.getStore();
+    const lane = id === undefined ? undefined : this.lanes.get(id);
+    if (!lane || this.owner() === id) write();
+    else lane.backlog.push(write);
+  }
+
+  /** Drop finished turns from the front, playing back what they held; the next live turn takes over. */
+  private advance(): void {
+    for (const [id, lane] of this.lanes) {
+      for (const write of lane.backlog.splice(0)) write();
+      if (!lane.finished) {
+        for (const resolve of lane.waiters.splice(0)) resolve();
+        return;
+      }
+      this.lanes.delete(id);
+    }
+  }
+}
@@ -5,6 +5,7 @@
  *
  * Supports:
  *   - Agent DM:  @@<agent> ...   and (optionally)  @<agent> ...
+ *   - Several:   @@<agent>,<agent>,cc:<agent> ...  and role tags  @@<role> ...
  *   - User:      @@<userToken> ...      /  @<userToken> ...
  *   - Group:     @@<groupToken> ...     /  @<groupToken> ...
  *   - File:      ##fileToken:NAME ...   /  #fileToken:NAME ...
@@ -15,10 +16,14 @@
  * - “File shorthand” (##path/to.txt) is enabled by default; disable via `allowFileShorthand:false`.
  * - Content for a tag spans until the next recognized tag.
  * - Plain text before the first tag becomes a single group part.
+ * - A recipient list (commas, no spaces) or a role tag becomes an "agents" part with
+ *   the agent ids expanded: `to` are expected to reply, `cc` only to read along.
+ *   A single plain @@agent stays an "agent" part.
  */
 
 export type TagPart =
   | { kind: "agent"; tag: string; content: string; index: number }
+  | { kind: "agents"; tag: string; to: string[]; cc: string[]; content: string; index: number }
   | { kind: "group"; tag: "group"; content: string; index: number }
   | { kind: "user";  tag: "user";  content: string; index: number }
   | { kind: "file";  tag: string;  content: string; index: number };
@@ -32,6 +37,8 @@ type TagSplitterOptions = {
   groupTokens?: string[];
   /** File keyword tokens allowed for the “file:NAME” form. Default: ['file'] */
   fileTokens?: string[];
+  /** Role tags and the agents they stand for (e.g., { reviewers: ['bob','carol'] }). Default: {} */
+  roleTokens?: Record<string, string[]>;
 
   /** Accept single '@' in addition to '@@'. Default: true */
   allowSingleAt?: boolean;
@@ -48,13 +55,15 @@ type TagSplitterOptions = {
 };
 
 const DEFAULTS: Required<Omit<TagSplitterOptions,
-  "agentTokens" | "userTokens" | "groupTokens" | "fileTokens">> & {
+  "agentTokens" | "userTokens" | "groupTokens" | "fileTokens" | "roleTokens">> & {
     agentTokens: string[]; userTokens: string[]; groupTokens: string[]; fileTokens: string[];
+    roleTokens: Record<string, string[]>;
   } = {
   agentTokens: [],
   userTokens: ["user"],
   groupTokens: ["group"],
   fileTokens: ["file"],
+  roleTokens: {},
   allowSingleAt: false,
   allowSingleHash: false,
   allowFileShorthand: false,
@@ -65,7 +74,13 @@ const isWordChar = (ch: string) => /[A-Za-z0-9._-]/.test(ch);
 // Allow slashes in filenames for shorthand (#/##)
 const isFileNameChar = (ch: string) => /[A-Za-z0-9._\-\/]/.test(ch);
 
-type Tok = { kind: "agent" | "group" | "user" | "file"; tag: string; start: number; end: number };
+type Tok = { kind: "agent" | "agents" | "group" | "user" | "file"; tag: string; start: number; end: number; to?: string[]; cc?: string[] };
+
+const readWord = (text: string, j: number) => {
+  let token = "";
+  while (j < text.length && isWordChar(text[j])) { token += text[j]; j++; }
+  return token;
+};
 
 export class TagSplitter {
   static split(input: string, opts?: TagSplitterOptions): TagPart[] {
@@ -79,7 +94,36 @@ export class TagSplitter {
       userTokens:  (opts?.userTokens  ?? DEFAULTS.userTokens).map(s => s.toLowerCase()),
       groupTokens: (opts?.groupTokens ?? DEFAULTS.groupTokens).map(s => s.toLowerCase()),
       fileTokens:  (opts?.fileTokens  ?? DEFAULTS.fileTokens).map(s => s.toLowerCase()),
+      roleTokens:  Object.fromEntries(Object.entries(opts?.roleTokens ?? {}).map(([k, v]) => [k.toLowerCase(), v])),
+    };
+
+    /** Agent ids a list item stands for (`cc:` stripped), or undefined when it is neither an agent nor a role. */
+    const recipients = (item: string): string[] | undefined => {
+      const low = item.toLowerCase();
+      if (cfg.agentTokens.includes(low)) return [item];
+      return cfg.roleTokens[low];
     };
+    /**
+     * Read `,item` / `,cc:item` entries after the first recipient; stops before a
+     * comma that isn't followed by one (so "@@alice, hi" keeps its comma as text).
+     */
+    const readList = (j: number, first: string[]) => {
+      const to = [...first], cc: string[] = [];
+      while (text[j] === ",") {
+        let k = j + 1;
+        const isCc = text.slice(k, k + 3).toLowerCase() === "cc:";
+        if (isCc) k += 3;
+        const item = readWord(text, k);
+        const ids = item ? recipients(item) : undefined;
+        if (!ids) break;
+        (isCc ? cc : to).push(...ids);
+        j = k + item.length;
+      }
+      return { to, cc, end: j };
+    };
+    const uniq = (ids: string[], skip: string[] = []) =>
+      ids.filter((id, n) => !skip.some((s) => s.toLowerCase() === id.toLowerCase())
+        && ids.findIndex((x) => x.toLowerCase() === id.toLowerCase()) === n);
 
     const isBoundary = (i: number) => (i <= 0) || cfg.boundaryChars.includes(text[i - 1] ?? "");
 
@@ -93,24 +137,33 @@ export class TagSplitter {
         const s = dbl ? 2 : 1;
         if (dbl || cfg.allowSingleAt) {
           let j = i + s;
-          // Read token word
-          let token = "";
-          while (j < text.length && isWordChar(text[j])) { token += text[j]; j++; }
+          // Read token word (or "cc:" and a word, for a list that only copies people in)
+          const leadCc = text.slice(j, j + 3).toLowerCase() === "cc:";
+          const token = readWord(text, leadCc ? j + 3 : j);
+          j += (leadCc ? 3 : 0) + token.length;
 
           if (token.length > 0) {
             const low = token.toLowerCase();
             // Only recognize tokens that are explicitly allowed
-            if (cfg.groupTokens.includes(low)) {
+            if (!leadCc && cfg.groupTokens.includes(low)) {
               toks.push({ kind: "group", tag: token, start: i, end: j });
               i = j; continue;
             }
-            if (cfg.userTokens.includes(low)) {
+            if (!leadCc && cfg.userTokens.includes(low)) {
               toks.push({ kind: "user", tag: token, start: i, end: j });
               i = j; continue;
             }
-            if (cfg.agentTokens.includes(low)) {
-              toks.push({ kind: "agent", tag: token, start: i, end: j });
-              i = j; continue;
+            const first = recipients(token);
+            if (first) {
+              const list = readList(j, leadCc ? [] : first);
+              if (leadCc) list.cc.unshift(...first);
+              if (list.end === j && !leadCc && cfg.agentTokens.includes(low)) {
+                toks.push({ kind: "agent", tag: token, start: i, end: j });
+              } else {
+                const to = uniq(list.to);
+                toks.push({ kind: "agents", tag: text.slice(i + s, list.end), start: i, end: list.end, to, cc: uniq(list.cc, to) });
+              }
+              i = list.end; continue;
             }
           }
         }
@@ -172,6 +225,8 @@ export class TagSplitter {
         parts.push({ kind: "group", tag: "group", content, index });
       } else if (cur.kind === "user") {
         parts.push({ kind: "user", tag: "user", content, index });
+      } else if (cur.kind === "agents") {
+        parts.push({ kind: "agents", tag: cur.tag, to: cur.to!, cc: cur.cc!, content, index });
       } else {
         parts.push({ kind: "agent", tag: cur.tag, content, index });
       }
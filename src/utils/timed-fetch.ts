@@ -4,25 +4,38 @@ export async function timedFetch(
   init: RequestInit & { timeoutMs?: number; where?: string } = {}
 ) {
   const { timeoutMs, where, ...rest } = init;
+  const userSignal = rest.signal ?? undefined;
   let controller: AbortController | null = null;
   let timer: NodeJS.Timeout | null = null;
+  let timedOut = false;
+  const linkAbort = () => controller?.abort(userSignal?.reason);
 
   try {
     if (timeoutMs && timeoutMs > 0) {
+      // Combine the caller's signal with our timeout; the caller's abort must still
+      // cancel the request (and the body stream that follows it).
       controller = new AbortController();
-      (rest as any).signal = controller.signal;
-      timer = setTimeout(() => controller!.abort(), timeoutMs);
+      if (userSignal?.aborted) controller.abort(userSignal.reason);
+      else userSignal?.addEventListener("abort", linkAbort, { once: true });
+      rest.signal = controller.signal;
+      timer = setTimeout(() => { timedOut = true; controller!.abort(); }, timeoutMs);
     }
     const res = await fetch(url, rest);
     return res;
   } catch (e: any) {
     // Normalize DOMException TimeoutError/AbortError to a regular Error with context.
+    const kind = timedOut ? "fetch timeout" : userSignal?.aborted ? "fetch aborted" : "fetch error";
     const err = new Error(
-      `[fetch timeout] ${where ?? ""} ${url} -> ${e?.name || "Error"}: ${e?.message || e}`
+      `[${kind}] ${where ?? ""} ${url} -> ${e?.name || "Error"}: ${e?.message || e}`
     );
     (err as any).cause = e;
+    userSignal?.removeEventListener("abort", linkAbort);
+    // Keep cancellations recognisable to callers that test `err.name === "AbortError"`.
+    if (userSignal?.aborted && !timedOut) err.name = "AbortError";
     throw err;
   } finally {
+    // The link to the caller's signal stays in place on success: aborting it must
+    // also cancel the response body, which is read after we return.
     if (timer) clearTimeout(timer);
   }
 }
@@ -0,0 +1,67 @@
+// test/_helpers/scripted-agent.ts
+import { Agent, type AgentCallbacks, type AgentReply } from "../../src/agents/agent";
+import { AgentSpawner } from "../../src/agents/agent-spawner";
+import type { AgentDefinition } from "../../src/agents/agent-definition";
+import type { RandomScheduler } from "../../src/scheduler/random-scheduler";
+import { createScheduler, type SchedulerKind } from "../../src/scheduler/create-scheduler";
+import type { SchedulerOptions } from "../../src/scheduler/types";
+import type { NoiseFilters } from "../../src/scheduler/filters";
+import type { ChatMessage } from "../../src/types";
+
+export type Script = (batch: ChatMessage[]) => string | undefined;
+
+/** A real Agent subclass that replies with whatever `script` returns for the drained batch, routed like an LlmAgent. */
+export class ScriptedAgent extends Agent {
+  readonly seen: ChatMessage[] = [];
+  constructor(id: string, private readonly script: Script) {
+    super(id);
+  }
+  async respond(messages: ChatMessage[], _maxTools: number, filters: NoiseFilters, _peers: Agent[], cb: AgentCallbacks): Promise<AgentReply[]> {
+    this.seen.push(...messages);
+    const text = this.script(messages);
+    if (!text) return [];
+    await cb.onRoute(text, filters);
+    return [{ message: text, toolsUsed: 0 }];
+  }
+  async load() {}
+  async save() {}
+}
+
+/** An AgentSpawner over a fake manager that builds ScriptedAgents; `created` lists the ids per create() call. */
+export function scriptedSpawner(templates: AgentDefinition[], script: Script) {
+  const created: string[][] = [];
+  const agents: ScriptedAgent[] = [];
+  const manager: any = {
+    create: async (defs: AgentDefinition[]) => {
+      created.push(defs.map((d) => d.id));
+      return defs.map((d) => {
+        const model = new ScriptedAgent(d.id, script);
+        agents.push(model);
+        return { id: d.id, kind: "mock", model };
+      });
+    },
+  };
+  const spawner = new AgentSpawner(manager, templates, { model: "m", baseUrl: "http://127.0.0.1:9", protocol: "openai" });
+  return { spawner, created, agents };
+}
+
+/** A deterministic, non-interactive RandomScheduler (or one of its subclasses, by `kind`) for tests. */
+export function scriptedScheduler(
+  agents: Agent[],
+  opts: Partial<SchedulerOptions> & { readUserLine?: () => Promise<string | undefined>; kind?: SchedulerKind } = {},
+) {
+  const { kind = "random", ...rest } = opts;
+  return createScheduler(kind, {
+    agents,
+    maxTools: 1,
+    onAskUser: async () => undefined,
+    projectDir: "/tmp",
+    reviewMode: "never",
+    promptEnabled: false,
+    idleSleepMs: 5,
+    shuffle: <T>(a: T[]) => a,
+    onStreamStart: () => {},
+    onStreamEnd: async () => {},
+    ...rest,
+  } as any) as RandomScheduler;
+}
@@ -0,0 +1,56 @@
+// test/helpers/stub-llm-server.ts
+import { createServer, type IncomingMessage, type ServerResponse } from "node:http";
+import type { AddressInfo } from "node:net";
+import { readFileSync } from "node:fs";
+
+type Recorded = {
+  status?: number;
+  contentType?: string;
+  /** Raw response body (e.g. a recorded SSE or NDJSON stream). */
+  body: string;
+  /** Write `body` but keep the stream open, like a model that is still generating. */
+  hold?: boolean;
+};
+
+export type StubRequest = { method: string; url: string; headers: IncomingMessage["headers"]; body: any };
+
+/**
+ * Minimal HTTP stub that replays recorded model responses in order.
+ * Every request is captured (with its parsed JSON body) for assertions.
+ */
+export async function startStubLlmServer(responses: Recorded[]) {
+  const queue = [...responses];
+  const requests: StubRequest[] = [];
+
+  const server = createServer((req: IncomingMessage, res: ServerResponse) => {
+    let raw = "";
+    req.on("data", (c) => { raw += c; });
+    req.on("end", () => {
+      let body: any = raw;
+      try { body = JSON.parse(raw); } catch { /* keep raw */ }
+      requests.push({ method: req.method ?? "", url: req.url ?? "", headers: req.headers, body });
+
+      const next = queue.shift() ?? { status: 500, body: "no more recorded responses" };
+      res.writeHead(next.status ?? 200, { "content-type": next.contentType ?? "text/event-stream" });
+      if (next.hold) res.write(next.body);
+      else res.end(next.body);
+    });
+  });
+
+  await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", () => resolve()));
+  const { port } = server.address() as AddressInfo;
+
+  return {
+    baseUrl: `http://127.0.0.1:${port}`,
+    requests,
+    close: () => new Promise<void>((resolve) => {
+      server.closeAllConnections?.();
+      server.close(() => resolve());
+    }),
+  };
+}
+
+/** Load a recorded stream from test/fixtures. */
+export function fixture(rel: string): string {
+  return readFileSync(new URL(`../fixtures/${rel}`, import.meta.url), "utf8");
+}
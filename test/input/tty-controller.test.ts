@@ -164,6 +164,42 @@ describe("'i' during streaming → defer prompt", () => {
   });
 });
 
+describe("concurrent streams (--concurrency > 1)", () => {
+  test("ESC aborts every open stream; finalize waits for the last one", async () => {
+    const a = ctl.onStreamStart();
+    const b = ctl.onStreamStart();
+
+    stdin.emitData(Buffer.from([0x1b]));
+    await delay(10);
+    expect([a.aborted, b.aborted]).toEqual([true, true]);
+
+    await ctl.onStreamEnd(b);
+    await delay(10);
+    expect(lastExitCode).toBe(null); // a is still winding down
+
+    await ctl.onStreamEnd(a);
+    await delay(10);
+    expect(lastExitCode).toBe(0);
+  });
+
+  test("a stream that starts while another runs is still cancellable after the first ends", async () => {
+    const a = ctl.onStreamStart();
+    const b = ctl.onStreamStart();
+    await ctl.onStreamEnd(a);
+
+    stdin.emitData("i");
+    await delay(10);
+    expect(b.aborted).toBe(true);
+    expect(stderr.data).toMatch(/waiting for model to finish/i);
+
+    const ended = ctl.onStreamEnd(b);
+    await delay(15);
+    stdin.pushString("hold on\n");
+    await ended;
+    expect(scheduler.enqueued).toEqual(["hold on"]);
+  });
+});
+
 describe("ESC when idle → finalize now", () => {
   test("finalize called and exit(0)", async () => {
     stdin.emitData(Buffer.from([0x1b])); // ESC when idle
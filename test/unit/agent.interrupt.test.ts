@@ -0,0 +1,108 @@
+// test/unit/agent.interrupt.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdtempSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import { LlmAgent, INTERRUPTED_MARK } from "../../src/agents/llm-agent";
+import type { AgentCallbacks } from "../../src/agents/agent";
+import type { ChatDriver, ChatMessage, ChatOutput } from "../../src/drivers/types";
+import { NoiseFilters } from "../../src/scheduler/filters";
+import { TtyController } from "../../src/input/tty-controller";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+const ask = [{ role: "user", from: "User", content: "hi" }];
+
+/** Streams two tokens, then blocks until aborted (like a long reasoning turn). */
+class HangingDriver implements ChatDriver {
+  seen: ChatMessage[][] = [];
+  async chat(messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+    this.seen.push(messages);
+    if (this.seen.length > 1) return { text: "ok", toolCalls: [] };
+    opts.onToken?.("Partial ");
+    opts.onToken?.("answer");
+    return new Promise((_, reject) => {
+      opts.signal.addEventListener("abort", () => {
+        const e = new Error("aborted");
+        e.name = "AbortError";
+        reject(e);
+      });
+    });
+  }
+}
+
+function callbacks(signal: AbortSignal | undefined, routed: string[]): AgentCallbacks {
+  return {
+    shouldAbort: () => false,
+    onStreamStart: () => signal,
+    onStreamEnd: () => {},
+    onRoute: async (message) => { routed.push(message); return false; },
+    onRouteCompleted: async () => false,
+  };
+}
+
+describe("cancelling in-flight generation", () => {
+  let cwd: string;
+  let tmp: string;
+  beforeEach(() => {
+    cwd = process.cwd();
+    tmp = mkdtempSync(path.join(tmpdir(), "org-interrupt-"));
+    process.chdir(tmp); // agent memory persists under cwd
+  });
+  afterEach(async () => {
+    await new Promise((r) => setTimeout(r, 50)); // respond() saves memory without awaiting
+    process.chdir(cwd);
+    rmSync(tmp, { recursive: true, force: true });
+  });
+
+  it("keeps the partial reply marked as interrupted and does not route it", async () => {
+    const driver = new HangingDriver();
+    const agent = new LlmAgent("alice", driver, "m");
+    const ctl = new AbortController();
+    const routed: string[] = [];
+
+    setTimeout(() => ctl.abort(), 20);
+    const replies = await agent.respond(ask, 2, new NoiseFilters(), [], callbacks(ctl.signal, routed));
+
+    expect(replies).toHaveLength(1);
+    expect(replies[0]).toMatchObject({ message: "Partial answer", toolsUsed: 0, interrupted: true });
+    expect(routed).toEqual([]);
+
+    // The next turn shows the model its cut-off reply.
+    await agent.respond([{ role: "user", from: "User", content: "go on" }], 2, new NoiseFilters(), [], callbacks(undefined, routed));
+    const context = driver.seen[1].map((m) => m.content).join("\n");
+    expect(context).toContain(`Partial answer ${INTERRUPTED_MARK}`);
+  });
+
+  it("aborts the HTTP stream mid-body even with a fetch timeout configured", async () => {
+    const stub = await startStubLlmServer([{
+      hold: true,
+      body: 'data: {"choices":[{"delta":{"content":"Hel"}}]}\n\n',
+    }]);
+    try {
+      const driver = makeStreamingOpenAiLmStudio({ baseUrl: stub.baseUrl, model: "m" });
+      const ctl = new AbortController();
+      const t0 = Date.now();
+      const p = driver.chat(ask, { signal: ctl.signal, onToken: () => ctl.abort() });
+      await expect(p).rejects.toThrow();
+      expect(Date.now() - t0).toBeLessThan(2_000);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("ESC and the interject key abort the stream signal", () => {
+    const tty = { isTTY: true, on: () => {}, off: () => {} };
+    const sink = { write: () => true };
+    const ctl = new TtyController({ stdin: tty, stdout: sink, stderr: sink, autostart: true });
+
+    const first = ctl.onStreamStart();
+    (ctl as any).onData("i");
+    expect(first.aborted).toBe(true);
+
+    const second = ctl.onStreamStart();
+    expect(second.aborted).toBe(false);
+    (ctl as any).onData("\x1b");
+    expect(second.aborted).toBe(true);
+  });
+});
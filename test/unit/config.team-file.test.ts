@@ -0,0 +1,163 @@
+// test/unit/config.team-file.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { parseYaml } from "../../src/config/yaml";
+import { parseTeamFile, resolveAgentDefinitions } from "../../src/config/team-file";
+import { AgentManger } from "../../src/agents/agent-manager";
+import type { AgentCallbacks } from "../../src/agents/agent";
+import { NoiseFilters } from "../../src/scheduler/filters";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+const TEAM = `
+version: 1
+defaults:
+  driver: ollama.openai
+  baseUrl: http://10.0.0.5:11434   # shared server
+  memory: { contextTokens: 32768 }
+agents:
+  - id: planner
+    model: gpt-oss:120b
+    temperature: 0.2
+    systemPromptFile: prompts/planner.md
+    budgets:
+      maxTools: 40
+  - id: reviewer
+    model: qwen3:8b
+    driver: ollama.native+hermes
+    tools: []
+    memory:
+      keepRecentTools: 1
+    guardrails: { repeatToolSigEndTurnLimit: 3 }
+    systemPrompt: |
+      Review diffs.
+      Never run commands.
+`;
+
+describe("org.yaml team file", () => {
+  let cwd: string;
+  let tmp: string;
+  beforeEach(() => {
+    cwd = process.cwd();
+    tmp = mkdtempSync(path.join(tmpdir(), "org-team-"));
+    process.chdir(tmp); // agent memory persists under cwd
+  });
+  afterEach(async () => {
+    await new Promise((r) => setTimeout(r, 50)); // respond() saves memory without awaiting
+    process.chdir(cwd);
+    rmSync(tmp, { recursive: true, force: true });
+  });
+
+  it("parses the YAML subset org.yaml uses", () => {
+    expect(parseYaml([
+      "a: 'it''s'",
+      "b: [1, \"x y\", {k: true}]",
+      "c:",
+      "- one",
+      "- two: 2",
+      "d: >-",
+      "  folded",
+      "  text",
+    ].join("\n"))).toEqual({ a: "it's", b: [1, "x y", { k: true }], c: ["one", { two: 2 }], d: "folded text" });
+
+    expect(() => parseYaml("a: 1\na: 2", "org.yaml")).toThrow('org.yaml:2: duplicate key "a"');
+    expect(() => parseYaml("a: 1\n   b: 2", "org.yaml")).toThrow("org.yaml:2: unexpected indentation");
+    expect(() => parseYaml("a: *ref", "org.yaml")).toThrow("org.yaml:1: anchors, aliases and tags are not supported");
+  });
+
+  it("merges defaults into each agent and reads prompt files next to the team file", () => {
+    const dir = path.join(tmp, "team");
+    mkdirSync(path.join(dir, "prompts"), { recursive: true });
+    writeFileSync(path.join(dir, "prompts/planner.md"), "Plan the work.\n");
+
+    const [planner, reviewer] = parseTeamFile(TEAM, "org.yaml", dir);
+    expect(planner).toEqual({
+      id: "planner",
+      model: "gpt-oss:120b",
+      kind: "ollama",
+      protocol: "openai",
+      baseUrl: "http://10.0.0.5:11434",
+      sampling: { temperature: 0.2 },
+      systemPrompt: "Plan the work.\n",
+      memory: { contextTokens: 32768 },
+      budgets: { maxTools: 40 },
+    });
+    expect(reviewer).toMatchObject({
+      kind: "ollama",
+      protocol: "native",
+      toolParser: "hermes",
+      tools: [],
+      memory: { contextTokens: 32768, keepRecentTools: 1 },
+      guardrails: { repeatToolSigEndTurnLimit: 3 },
+      systemPrompt: "Review diffs.\nNever run commands.",
+    });
+  });
+
+  it("reports the file and the offending field", () => {
+    const bad = (body: string) => () => parseTeamFile(`version: 1\nagents:\n${body}`, "org.yaml", tmp);
+
+    expect(() => parseTeamFile("agents: []", "org.yaml", tmp)).toThrow('org.yaml: version: missing (add "version: 1")');
+    expect(bad("  - id: a\n  - id: b\n    temperature: 3")).toThrow("org.yaml: agents[1].temperature: expected a number between 0 and 2");
+    expect(bad("  - id: a\n    tempreature: 1")).toThrow("org.yaml: agents[0].tempreature: unknown field (known: id, model, driver,");
+    expect(bad("  - id: a\n    driver: lmstudio.gpt")).toThrow("org.yaml: agents[0].driver: unknown protocol: gpt");
+    expect(bad("  - id: a\n    tools: [sh, curl]")).toThrow('org.yaml: agents[0].tools[1]: unknown tool "curl" (known: sh)');
+    expect(bad("  - id: a\n    budgets: { maxTools: 2.5 }")).toThrow("org.yaml: agents[0].budgets.maxTools: expected an integer >= 0");
+    expect(bad("  - id: a\n    systemPromptFile: nope.md")).toThrow("org.yaml: agents[0].systemPromptFile: cannot read");
+    expect(bad("  - id: a\n  - id: A")).toThrow('org.yaml: agents[1].id: duplicate agent id "A"');
+  });
+
+  it("picks --team, then --agents, then ./org.yaml", () => {
+    expect(resolveAgentDefinitions({}, tmp).definitions).toEqual([{ id: "alice", model: "lmstudio" }]);
+
+    writeFileSync(path.join(tmp, "org.yaml"), "version: 1\nagents:\n  - id: bob\n");
+    expect(resolveAgentDefinitions({}, tmp).definitions).toEqual([{ id: "bob" }]);
+    expect(resolveAgentDefinitions({ agents: "carol^m^ollama.native" }, tmp).definitions)
+      .toEqual([{ id: "carol", model: "m", kind: "ollama", protocol: "native" }]);
+
+    writeFileSync(path.join(tmp, "team.yaml"), "version: 1\nagents:\n  - id: dave\n");
+    expect(resolveAgentDefinitions({ team: "team.yaml" }, tmp).definitions).toEqual([{ id: "dave" }]);
+    expect(() => resolveAgentDefinitions({ team: "team.yaml", agents: "x" }, tmp)).toThrow("use either --team or --agents");
+  });
+
+  it("creates agents with their own server, model, temperature, tools and budgets", async () => {
+    const prev = process.env.ORG_MODEL_PROBE;
+    process.env.ORG_MODEL_PROBE = "0";
+    const stub = await startStubLlmServer([{ body: 'data: {"choices":[{"delta":{"content":"ok"}}]}\n\ndata: [DONE]\n\n' }]);
+    try {
+      const defs = parseTeamFile([
+        "version: 1",
+        "agents:",
+        "  - id: reviewer",
+        "    model: small-model",
+        "    driver: lmstudio.openai",
+        `    baseUrl: ${stub.baseUrl}`,
+        "    temperature: 0.1",
+        "    tools: []",
+        "    systemPrompt: You only review.",
+        "    budgets: { maxTools: 3 }",
+      ].join("\n"), "org.yaml", tmp);
+      const [spec] = await new AgentManger().create(defs, { model: "shared-model", baseUrl: "http://127.0.0.1:9", protocol: "openai" });
+      expect(spec.model.budgets).toEqual({ maxTools: 3 });
+
+      const callbacks: AgentCallbacks = {
+        shouldAbort: () => false,
+        onStreamStart: () => {},
+        onStreamEnd: () => {},
+        onRoute: async () => false,
+        onRouteCompleted: async () => false,
+      };
+      await spec.model.respond([{ role: "user", from: "User", content: "hi" }], 1, new NoiseFilters(), [], callbacks);
+
+      const body = stub.requests[0].body;
+      expect(body.model).toBe("small-model");
+      expect(body.temperature).toBe(0.1);
+      expect(body.tools).toBeUndefined();
+      expect(JSON.stringify(body.messages)).toContain("You only review.");
+    } finally {
+      await stub.close();
+      if (prev === undefined) delete process.env.ORG_MODEL_PROBE;
+      else process.env.ORG_MODEL_PROBE = prev;
+    }
+  });
+});
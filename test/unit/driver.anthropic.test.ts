@@ -0,0 +1,105 @@
+// test/unit/driver.anthropic.test.ts
+import { describe, it, expect } from "bun:test";
+import { makeStreamingAnthropic, toAnthropicMessages } from "../../src/drivers/streaming-anthropic";
+import { SH_TOOL_DEF } from "../../src/tools/sh";
+import type { ChatToolCall } from "../../src/drivers/types";
+import { startStubLlmServer, fixture } from "../helpers/stub-llm-server";
+
+describe("Anthropic Messages driver", () => {
+  it("streams text, thinking and tool_use blocks from a recorded SSE stream", async () => {
+    const stub = await startStubLlmServer([{ body: fixture("anthropic/tool-use.sse") }]);
+    try {
+      const driver = makeStreamingAnthropic({ baseUrl: stub.baseUrl, model: "claude-test", apiKey: "sk-test" });
+
+      const tokens: string[] = [];
+      const reasoning: string[] = [];
+      const deltas: ChatToolCall[] = [];
+      const out = await driver.chat(
+        [
+          { role: "system", from: "System", content: "You are alice." },
+          { role: "user", from: "User", content: "list files" },
+        ],
+        {
+          tools: [SH_TOOL_DEF],
+          onToken: (t) => tokens.push(t),
+          onReasoningToken: (t) => reasoning.push(t),
+          onToolCallDelta: (d) => deltas.push({ ...d, function: { ...d.function } }),
+        }
+      );
+
+      expect(out.text).toBe("Let me check.");
+      expect(tokens.join("")).toBe("Let me check.");
+      expect(out.reasoning).toBe("List the files first.");
+      expect(reasoning.join("")).toBe("List the files first.");
+      expect(out.thinking).toEqual([{ type: "thinking", thinking: "List the files first.", signature: "sig-abc" }]);
+      expect(out.toolCalls).toEqual([
+        { id: "toolu_01", type: "function", function: { name: "sh", arguments: '{"cmd": "ls -la"}' } },
+      ]);
+      expect(deltas.length).toBeGreaterThan(1);
+      expect(out.usage).toEqual({ promptTokens: 42, completionTokens: 27 });
+      expect(out.timing?.ttftMs).toBeGreaterThanOrEqual(0);
+
+      const req = stub.requests[0];
+      expect(req.url).toBe("/v1/messages");
+      expect(req.headers["x-api-key"]).toBe("sk-test");
+      expect(req.headers["anthropic-version"]).toBe("2023-06-01");
+      expect(req.body.system).toBe("You are alice.");
+      expect(req.body.stream).toBe(true);
+      expect(req.body.tools[0].name).toBe("sh");
+      expect(req.body.tools[0].input_schema.required).toEqual(["cmd"]);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("sends thinking back ahead of the tool calls it led to, with room above the budget", async () => {
+    const stub = await startStubLlmServer([1, 2, 3].map(() => ({ body: fixture("anthropic/tool-use.sse") })));
+    try {
+      const driver = makeStreamingAnthropic({ baseUrl: stub.baseUrl, model: "claude-test", thinkingBudgetTokens: 8000 });
+      const first = await driver.chat([{ role: "user", from: "User", content: "list files" }], { tools: [SH_TOOL_DEF] });
+      await driver.chat([
+        { role: "user", from: "User", content: "list files" },
+        { role: "assistant", from: "Me", content: first.text, thinking: first.thinking, tool_calls: first.toolCalls } as any,
+        { role: "tool", from: "Tool", content: "a.txt", tool_call_id: "toolu_01", name: "sh" },
+      ], { tools: [SH_TOOL_DEF] });
+
+      expect(stub.requests[0].body.thinking).toEqual({ type: "enabled", budget_tokens: 8000 });
+      expect(stub.requests[0].body.max_tokens).toBe(12096); // the default 4096 cap on top of the budget
+      const assistant = stub.requests[1].body.messages[1];
+      expect(assistant.content.map((b: any) => b.type)).toEqual(["thinking", "text", "tool_use"]);
+      expect(assistant.content[0]).toEqual({ type: "thinking", thinking: "List the files first.", signature: "sig-abc" });
+      expect(stub.requests[1].body.messages[2].content[0]).toMatchObject({ type: "tool_result", tool_use_id: "toolu_01" });
+
+      const roomy = makeStreamingAnthropic({ baseUrl: stub.baseUrl, model: "claude-test", thinkingBudgetTokens: 2000, sampling: { maxTokens: 16000 } });
+      await roomy.chat([{ role: "user", from: "User", content: "hi" }]);
+      expect(stub.requests[2].body.max_tokens).toBe(16000);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("surfaces in-stream error events", async () => {
+    const stub = await startStubLlmServer([{ body: fixture("anthropic/error.sse") }]);
+    try {
+      const driver = makeStreamingAnthropic({ baseUrl: stub.baseUrl, model: "claude-test" });
+      await expect(driver.chat([{ role: "user", from: "User", content: "hi" }])).rejects.toThrow(/Overloaded/);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("maps tool results onto tool_result blocks only when the tool_use is known", () => {
+    const call: ChatToolCall = { id: "toolu_9", type: "function", function: { name: "sh", arguments: '{"cmd":"pwd"}' } };
+    const { messages } = toAnthropicMessages([
+      { role: "user", from: "User", content: "where am I?" },
+      { role: "assistant", from: "Me", content: "", tool_calls: [call] } as any,
+      { role: "tool", from: "Tool", content: "/work", tool_call_id: "toolu_9", name: "sh" },
+      { role: "tool", from: "Tool", content: "stale", tool_call_id: "call_x", name: "sh" },
+    ]);
+
+    expect(messages.map((m) => m.role)).toEqual(["user", "assistant", "user"]);
+    expect(messages[1].content[0]).toMatchObject({ type: "tool_use", id: "toolu_9", input: { cmd: "pwd" } });
+    expect(messages[2].content[0]).toMatchObject({ type: "tool_result", tool_use_id: "toolu_9", content: "/work" });
+    expect(messages[2].content[1]).toMatchObject({ type: "text" });
+  });
+});
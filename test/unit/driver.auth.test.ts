@@ -0,0 +1,134 @@
+// test/unit/driver.auth.test.ts
+import { describe, it, expect, afterEach } from "bun:test";
+import { mkdtempSync, rmSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import { redactAuth, resolveDriverAuth, resolveEndpointAuth } from "../../src/drivers/auth";
+import { withRetries } from "../../src/drivers/retry-driver";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+const AUTH_VARS = [
+  "ORG_LLM_API_KEY", "ORG_LLM_API_KEY_FILE", "ORG_LLM_HEADERS",
+  "ORG_LLM_API_KEY_BOB_2", "ORG_LLM_API_KEY_FILE_BOB_2", "ORG_LLM_HEADERS_BOB_2",
+  "ORG_LLM_ENDPOINT_AUTH", "ORG_LLM_FAILOVER_CAROL", "ORG_LLM_RETRIES",
+];
+
+describe("API keys and custom headers", () => {
+  const saved = Object.fromEntries(AUTH_VARS.map((k) => [k, process.env[k]]));
+  afterEach(() => {
+    for (const [k, v] of Object.entries(saved)) {
+      if (v === undefined) delete process.env[k];
+      else process.env[k] = v;
+    }
+  });
+
+  it("sends the bearer key and extra headers, and never logs the key", async () => {
+    const stub = await startStubLlmServer([{
+      body: 'data: {"choices":[{"delta":{"content":"ok"}}]}\n\ndata: [DONE]\n\n',
+    }]);
+    const logged: string[] = [];
+    const log = console.log;
+    const level = process.env.ORG_LOG_LEVEL;
+    process.env.ORG_LOG_LEVEL = "DEBUG";
+    console.log = (...a: any[]) => { logged.push(a.map((x) => (typeof x === "string" ? x : JSON.stringify(x))).join(" ")); };
+    try {
+      const driver = makeStreamingOpenAiLmStudio({
+        baseUrl: stub.baseUrl,
+        model: "m",
+        apiKey: "sk-gateway-secret",
+        headers: { "X-Team": "infra", "X-Api-Key": "other-secret" },
+      });
+      await driver.chat([{ role: "user", from: "User", content: "hi" }]);
+    } finally {
+      console.log = log;
+      if (level === undefined) delete process.env.ORG_LOG_LEVEL;
+      else process.env.ORG_LOG_LEVEL = level;
+      await stub.close();
+    }
+
+    const headers = stub.requests[0].headers;
+    expect(headers.authorization).toBe("Bearer sk-gateway-secret");
+    expect(headers["x-team"]).toBe("infra");
+    expect(headers["x-api-key"]).toBe("other-secret");
+
+    const all = logged.join("\n");
+    expect(all).toContain("infra");
+    expect(all).not.toContain("sk-gateway-secret");
+    expect(all).not.toContain("other-secret");
+  });
+
+  it("prefers the agent's own key, then a key file, then the shared key", () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-auth-"));
+    try {
+      const keyFile = path.join(dir, "key");
+      writeFileSync(keyFile, "from-file\n");
+
+      process.env.ORG_LLM_API_KEY = "shared";
+      expect(resolveDriverAuth("alice").apiKey).toBe("shared");
+
+      process.env.ORG_LLM_API_KEY_FILE_BOB_2 = keyFile;
+      expect(resolveDriverAuth("bob-2").apiKey).toBe("from-file");
+
+      process.env.ORG_LLM_API_KEY_BOB_2 = "bob-only";
+      expect(resolveDriverAuth("bob-2").apiKey).toBe("bob-only");
+
+      delete process.env.ORG_LLM_API_KEY;
+      expect(resolveDriverAuth("alice", "ORG_LLM_API_KEY", "from-config").apiKey).toBe("from-config");
+      expect(resolveDriverAuth("alice").apiKey).toBeUndefined();
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("parses header JSON per agent and rejects anything else", () => {
+    process.env.ORG_LLM_HEADERS = '{"X-Team":"infra"}';
+    process.env.ORG_LLM_HEADERS_BOB_2 = '{"X-Team":"research","X-Priority":1}';
+    expect(resolveDriverAuth("alice").headers).toEqual({ "X-Team": "infra" });
+    expect(resolveDriverAuth("bob-2").headers).toEqual({ "X-Team": "research", "X-Priority": "1" });
+
+    process.env.ORG_LLM_HEADERS = "X-Team: infra";
+    expect(() => resolveDriverAuth("alice")).toThrow("ORG_LLM_HEADERS must be a JSON object");
+  });
+
+  it("keeps the agent's credentials off failover endpoints on other hosts", async () => {
+    const ok = 'data: {"choices":[{"delta":{"content":"ok"}}]}\n\ndata: [DONE]\n\n';
+    const down = { status: 503, contentType: "text/plain", body: "down" };
+    const primary = await startStubLlmServer([down, down]);
+    const backup = await startStubLlmServer([{ body: ok }, { body: ok }]);
+    try {
+      process.env.ORG_LLM_RETRIES = "0";
+      process.env.ORG_LLM_FAILOVER_CAROL = `m2@${backup.baseUrl}`;
+      const chain = () => withRetries("carol", { baseUrl: primary.baseUrl, model: "m1", auth: { apiKey: "sk-primary", headers: { "X-Team": "infra" } } }, (t) =>
+        makeStreamingOpenAiLmStudio({ baseUrl: t.baseUrl, model: t.model, apiKey: t.auth?.apiKey, headers: t.auth?.headers }));
+
+      await chain().chat([{ role: "user", from: "User", content: "hi" }]);
+      process.env.ORG_LLM_ENDPOINT_AUTH = JSON.stringify({ [backup.baseUrl]: { apiKey: "sk-backup" } });
+      await chain().chat([{ role: "user", from: "User", content: "hi" }]);
+
+      expect(primary.requests.map((r) => r.headers.authorization)).toEqual(["Bearer sk-primary", "Bearer sk-primary"]);
+      expect(backup.requests.map((r) => [r.headers.authorization, r.headers["x-team"]])).toEqual([[undefined, undefined], ["Bearer sk-backup", undefined]]);
+    } finally {
+      await primary.close();
+      await backup.close();
+    }
+  }, 20_000);
+
+  it("scopes endpoint credentials by origin", () => {
+    const primary = { baseUrl: "http://10.0.0.1:1234/v1", auth: { apiKey: "sk-primary" } };
+    expect(resolveEndpointAuth("http://10.0.0.1:1234/v1", primary)).toEqual({ apiKey: "sk-primary" });
+    expect(resolveEndpointAuth("http://10.0.0.1:4321/v1", primary)).toBeUndefined();
+
+    process.env.ORG_LLM_ENDPOINT_AUTH = '{"https://backup.example.com":{"apiKey":"sk-b","headers":{"X-Team":"infra"}}}';
+    expect(resolveEndpointAuth("https://backup.example.com/v1", primary)).toEqual({ apiKey: "sk-b", headers: { "X-Team": "infra" } });
+    expect(resolveEndpointAuth("https://evil.example.com/v1", primary)).toBeUndefined();
+
+    process.env.ORG_LLM_ENDPOINT_AUTH = "sk-b";
+    expect(() => resolveEndpointAuth("https://backup.example.com/v1", primary)).toThrow("ORG_LLM_ENDPOINT_AUTH must be a JSON object");
+  });
+
+  it("redacts keys and credential headers for logging", () => {
+    const safe = redactAuth({ baseUrl: "http://x", apiKey: "k", headers: { Authorization: "Bearer k", "X-Team": "infra" } });
+    expect(safe).toEqual({ baseUrl: "http://x", apiKey: "***", headers: { Authorization: "***", "X-Team": "infra" } });
+  });
+});
@@ -0,0 +1,121 @@
+// test/unit/driver.capabilities.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdtempSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import {
+  clearCapabilityCache,
+  DEFAULT_CAPABILITIES,
+  probeModelCapabilities,
+  profileFor,
+} from "../../src/drivers/model-capabilities";
+import { LlmAgent } from "../../src/agents/llm-agent";
+import type { AgentCallbacks } from "../../src/agents/agent";
+import type { ChatDriver, ChatMessage, ChatOutput } from "../../src/drivers/types";
+import { NoiseFilters } from "../../src/scheduler/filters";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+const json = (body: unknown, status = 200) => ({ status, contentType: "application/json", body: JSON.stringify(body) });
+
+describe("model capability probing", () => {
+  beforeEach(() => clearCapabilityCache());
+
+  it("reads context, tools and thinking from Ollama /api/show", async () => {
+    const stub = await startStubLlmServer([json({
+      parameters: "temperature 0.7\nnum_ctx 16384",
+      model_info: { "qwen3.context_length": 40960 },
+      capabilities: ["completion", "thinking"],
+    })]);
+    try {
+      const caps = await probeModelCapabilities({ baseUrl: `${stub.baseUrl}/v1`, model: "qwen3:8b", ollama: true });
+      expect(stub.requests[0]).toMatchObject({ method: "POST", url: "/api/show", body: { model: "qwen3:8b" } });
+      expect(caps).toMatchObject({ contextTokens: 16384, nativeTools: false, reasoning: true, source: "probe" });
+      expect(caps.toolParser).toBe("hermes");
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("reads LM Studio extras from /v1/models and fills gaps from the profile", async () => {
+    const stub = await startStubLlmServer([json({
+      data: [
+        { id: "other", max_context_length: 4096 },
+        { id: "google/gemma-3-12b", loaded_context_length: 8192, max_context_length: 131072 },
+      ],
+    })]);
+    try {
+      const caps = await probeModelCapabilities({ baseUrl: stub.baseUrl, model: "google/gemma-3-12b" });
+      expect(stub.requests[0]).toMatchObject({ method: "GET", url: "/v1/models" });
+      expect(caps).toMatchObject({ contextTokens: 8192, nativeTools: false, toolParser: "gemma", source: "probe" });
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("falls back to the profile table, then the defaults, when the server knows nothing", async () => {
+    const stub = await startStubLlmServer([json({ error: "nope" }, 404), json({ data: [] }), json({ data: [] })]);
+    try {
+      const llama = await probeModelCapabilities({ baseUrl: stub.baseUrl, model: "llama3.1:8b", ollama: true });
+      expect(llama).toMatchObject({ contextTokens: 131_072, nativeTools: true, source: "profile" });
+
+      const unknown = await probeModelCapabilities({ baseUrl: stub.baseUrl, model: "my-finetune" });
+      expect(unknown).toEqual(DEFAULT_CAPABILITIES);
+
+      // Cached per endpoint+model: no further requests.
+      await probeModelCapabilities({ baseUrl: stub.baseUrl, model: "my-finetune" });
+      expect(stub.requests).toHaveLength(3);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("matches profiles most-specific first", () => {
+    expect(profileFor("Qwen2.5-Coder-7B-Instruct")).toMatchObject({ nativeTools: false, toolParser: "qwen" });
+    expect(profileFor("qwen2.5:14b")).toMatchObject({ nativeTools: true });
+    expect(profileFor("qwen3-coder:30b")).toMatchObject({ contextTokens: 262_144 });
+    expect(profileFor("phi-4")).toBeUndefined();
+  });
+});
+
+describe("LlmAgent and capabilities", () => {
+  let cwd: string;
+  let tmp: string;
+  beforeEach(() => {
+    cwd = process.cwd();
+    tmp = mkdtempSync(path.join(tmpdir(), "org-caps-"));
+    process.chdir(tmp); // agent memory persists under cwd
+  });
+  afterEach(async () => {
+    await new Promise((r) => setTimeout(r, 50)); // respond() saves memory without awaiting
+    process.chdir(cwd);
+    rmSync(tmp, { recursive: true, force: true });
+  });
+
+  class RecordingDriver implements ChatDriver {
+    opts: any[] = [];
+    async chat(_messages: ChatMessage[], opts?: any): Promise<ChatOutput> {
+      this.opts.push(opts);
+      return { text: "done", toolCalls: [] };
+    }
+  }
+
+  const callbacks: AgentCallbacks = {
+    shouldAbort: () => false,
+    onStreamStart: () => {},
+    onStreamEnd: () => {},
+    onRoute: async () => false,
+    onRouteCompleted: async () => false,
+  };
+  const ask = [{ role: "user", from: "User", content: "hi" }];
+
+  it("only advertises native tools to models that support them", async () => {
+    const native = new RecordingDriver();
+    await new LlmAgent("a", native, "m").respond(ask, 2, new NoiseFilters(), [], callbacks);
+    expect(native.opts[0].tools.map((t: any) => t.function.name)).toEqual(["sh"]);
+
+    const text = new RecordingDriver();
+    const caps = { ...DEFAULT_CAPABILITIES, nativeTools: false, toolParser: "hermes" };
+    await new LlmAgent("b", text, "m", undefined, { caps }).respond(ask, 2, new NoiseFilters(), [], callbacks);
+    expect(text.opts[0].tools).toBeUndefined();
+  });
+});
@@ -0,0 +1,119 @@
+// test/unit/driver.cassette.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
+import { makeRecordingDriver, makeReplayDriver, readCassette, cassettePath } from "../../src/drivers/cassette-driver";
+import type { ChatDriver, ChatMessage, ChatToolCall } from "../../src/drivers/types";
+import { AgentManger } from "../../src/agents/agent-manager";
+import { clearCapabilityCache } from "../../src/drivers/model-capabilities";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+/** Scripted driver that streams like the real ones (mutating one tool-call object). */
+function scriptedDriver(): ChatDriver & { calls: number } {
+  const d = {
+    calls: 0,
+    async chat(_messages: ChatMessage[], opts?: any) {
+      d.calls++;
+      if (d.calls === 2) throw new Error("upstream 503");
+      opts?.onReasoningToken?.("plan");
+      opts?.onToken?.("Let me ");
+      opts?.onToken?.("look.");
+      const call: ChatToolCall = { id: "call_0", type: "function" as const, function: { name: "sh", arguments: '{"cmd":' } };
+      opts?.onToolCallDelta?.(call);
+      call.function.arguments += '"ls"}';
+      opts?.onToolCallDelta?.(call);
+      return { text: "Let me look.", reasoning: "plan", toolCalls: [call] };
+    },
+  };
+  return d;
+}
+
+const ask: ChatMessage[] = [{ role: "user", from: "User", content: "list files" }];
+
+describe("LLM cassettes", () => {
+  let dir: string;
+  beforeEach(() => { dir = fs.mkdtempSync(path.join(os.tmpdir(), "org-cassette-")); });
+  afterEach(() => { fs.rmSync(dir, { recursive: true, force: true }); });
+
+  it("records deltas and outputs, then replays them without the inner driver", async () => {
+    const file = cassettePath(dir, "alice");
+    const inner = scriptedDriver();
+    const rec = makeRecordingDriver(inner, file);
+
+    const liveTokens: string[] = [];
+    const first = await rec.chat(ask, { model: "m", onToken: (t: string) => liveTokens.push(t) });
+    await expect(rec.chat(ask)).rejects.toThrow("upstream 503");
+
+    expect(liveTokens.join("")).toBe("Let me look.");
+    const entries = readCassette(file);
+    expect(entries.map((e) => e.seq)).toEqual([0, 1]);
+    expect(entries[0].request.model).toBe("m");
+    expect(entries[0].events.filter((e) => e.k === "tool").map((e: any) => e.call.function.arguments))
+      .toEqual(['{"cmd":', '{"cmd":"ls"}']);
+    expect(entries[1].error).toBe("upstream 503");
+
+    const replay = makeReplayDriver(file);
+    const tokens: string[] = [];
+    const reasoning: string[] = [];
+    const deltas: string[] = [];
+    const out = await replay.chat(ask, {
+      onToken: (t: string) => tokens.push(t),
+      onReasoningToken: (t: string) => reasoning.push(t),
+      onToolCallDelta: (c: ChatToolCall) => deltas.push(c.function.arguments),
+    });
+
+    expect(out).toEqual(first);
+    expect(tokens).toEqual(["Let me ", "look."]);
+    expect(reasoning).toEqual(["plan"]);
+    expect(deltas).toEqual(['{"cmd":', '{"cmd":"ls"}']);
+    await expect(replay.chat(ask)).rejects.toThrow("upstream 503");
+    await expect(replay.chat(ask)).rejects.toThrow(/exhausted/);
+    expect(inner.calls).toBe(2);
+  });
+
+  it("rejects drifted requests in strict mode", async () => {
+    const file = cassettePath(dir, "bob");
+    await makeRecordingDriver(scriptedDriver(), file).chat(ask);
+
+    const lenient = makeReplayDriver(file);
+    expect((await lenient.chat([{ role: "user", from: "User", content: "something else" }])).text).toBe("Let me look.");
+
+    const strict = makeReplayDriver(file, { strict: true });
+    await expect(strict.chat([{ role: "user", from: "User", content: "something else" }])).rejects.toThrow(/differs from recording/);
+  });
+
+  it("replays the recorded model capabilities instead of probing the server", async () => {
+    const cwd = process.cwd();
+    const saved = { run: process.env.ORG_RUN_ID, record: process.env.ORG_LLM_RECORD, replay: process.env.ORG_LLM_REPLAY };
+    const models = JSON.stringify({ data: [{ id: "m1", max_context_length: 4321 }] });
+    const stub = await startStubLlmServer([{ contentType: "application/json", body: models }]);
+    const defaults = { model: "m1", baseUrl: stub.baseUrl, protocol: "openai" as const };
+    try {
+      process.chdir(dir); // runs and agent memory live under cwd
+      clearCapabilityCache();
+      process.env.ORG_RUN_ID = "rec1";
+      process.env.ORG_LLM_RECORD = "1";
+      await new AgentManger().parse("alice^m1^lmstudio.openai", defaults);
+      const recorded = JSON.parse(fs.readFileSync(path.join(dir, ".org/runs/rec1/llm/alice.caps.json"), "utf8"));
+      expect(recorded).toMatchObject({ contextTokens: 4321, source: "probe" });
+
+      clearCapabilityCache();
+      for (const id of ["alice", "bob"]) fs.writeFileSync(path.join(dir, `.org/runs/rec1/llm/${id}.jsonl`), ""); // no turns were taken
+      delete process.env.ORG_LLM_RECORD;
+      process.env.ORG_RUN_ID = "rep1";
+      process.env.ORG_LLM_REPLAY = "rec1";
+      await new AgentManger().parse("alice^m1^lmstudio.openai,bob^m1^lmstudio.openai", defaults); // bob has no recorded caps
+      expect(stub.requests.map((r) => r.url)).toEqual(["/v1/models"]); // only the recording probed
+    } finally {
+      await new Promise((r) => setTimeout(r, 50)); // agents save memory without awaiting
+      process.chdir(cwd);
+      await stub.close();
+      clearCapabilityCache();
+      for (const [k, v] of [["ORG_RUN_ID", saved.run], ["ORG_LLM_RECORD", saved.record], ["ORG_LLM_REPLAY", saved.replay]] as const) {
+        if (v === undefined) delete process.env[k];
+        else process.env[k] = v;
+      }
+    }
+  });
+});
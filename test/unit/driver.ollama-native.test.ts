@@ -0,0 +1,95 @@
+// test/unit/driver.ollama-native.test.ts
+import { describe, it, expect } from "bun:test";
+import { makeStreamingOllamaNative, toOllamaMessages } from "../../src/drivers/streaming-ollama-native";
+import { SH_TOOL_DEF } from "../../src/tools/sh";
+import type { ChatToolCall } from "../../src/drivers/types";
+import { startStubLlmServer, fixture } from "../helpers/stub-llm-server";
+
+const NDJSON = "application/x-ndjson";
+
+describe("Ollama native /api/chat driver", () => {
+  it("streams content, thinking, tool calls and usage from a recorded NDJSON stream", async () => {
+    const stub = await startStubLlmServer([{ contentType: NDJSON, body: fixture("ollama/tool-call.ndjson") }]);
+    try {
+      const driver = makeStreamingOllamaNative({
+        baseUrl: `${stub.baseUrl}/v1`,
+        model: "qwen3:8b",
+        options: { num_ctx: 16384, temperature: 0.2, seed: 7 },
+        keepAlive: "10m",
+      });
+
+      const tokens: string[] = [];
+      const reasoning: string[] = [];
+      const deltas: ChatToolCall[] = [];
+      const out = await driver.chat(
+        [
+          { role: "system", from: "System", content: "You are alice." },
+          { role: "user", from: "User", content: "list files" },
+        ],
+        {
+          tools: [SH_TOOL_DEF],
+          onToken: (t) => tokens.push(t),
+          onReasoningToken: (t) => reasoning.push(t),
+          onToolCallDelta: (d) => deltas.push(d),
+        }
+      );
+
+      expect(out.text).toBe("Checking now.");
+      expect(tokens.join("")).toBe("Checking now.");
+      expect(out.reasoning).toBe("Need the listing.");
+      expect(reasoning.join("")).toBe("Need the listing.");
+      expect(out.toolCalls).toEqual([
+        { id: "call_0", type: "function", function: { name: "sh", arguments: '{"cmd":"ls -la"}' } },
+      ]);
+      expect(deltas.length).toBe(1);
+      expect(out.usage).toEqual({ promptTokens: 57, completionTokens: 19 });
+
+      const req = stub.requests[0];
+      expect(req.url).toBe("/api/chat");
+      expect(req.body.model).toBe("qwen3:8b");
+      expect(req.body.stream).toBe(true);
+      expect(req.body.options).toEqual({ num_ctx: 16384, temperature: 0.2, seed: 7 });
+      expect(req.body.keep_alive).toBe("10m");
+      expect(req.body.tools[0].function.name).toBe("sh");
+      expect(req.body.messages[0]).toEqual({ role: "system", content: "You are alice." });
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("moves tool calls written in the reply text out of the text", async () => {
+    const line = (content: string, done = false) => JSON.stringify({ model: "qwen2.5", message: { role: "assistant", content }, done }) + "\n";
+    const body = line("Let me look.\n<tool_call>\n") + line('{"name": "sh", "arguments": {"cmd": "pwd"}}\n</tool_call>') + line("", true);
+    const stub = await startStubLlmServer([{ contentType: NDJSON, body }]);
+    try {
+      const driver = makeStreamingOllamaNative({ baseUrl: stub.baseUrl, model: "qwen2.5", toolParser: "hermes" });
+      const out = await driver.chat([{ role: "user", from: "User", content: "where are we?" }]);
+
+      expect(out.text).toBe("Let me look.");
+      expect(out.toolCalls.map((c) => [c.function.name, c.function.arguments])).toEqual([["sh", '{"cmd":"pwd"}']]);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("surfaces error lines", async () => {
+    const stub = await startStubLlmServer([{ contentType: NDJSON, body: fixture("ollama/error.ndjson") }]);
+    try {
+      const driver = makeStreamingOllamaNative({ baseUrl: stub.baseUrl, model: "nope" });
+      await expect(driver.chat([{ role: "user", from: "User", content: "hi" }])).rejects.toThrow(/not found/);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("sends tool call arguments as objects and names tool results", () => {
+    const call: ChatToolCall = { id: "call_0", type: "function", function: { name: "sh", arguments: '{"cmd":"pwd"}' } };
+    const msgs = toOllamaMessages([
+      { role: "assistant", from: "Me", content: "", tool_calls: [call] } as any,
+      { role: "tool", from: "Tool", content: "/work", tool_call_id: "call_0", name: "sh" },
+    ]);
+
+    expect(msgs[0]).toEqual({ role: "assistant", content: "", tool_calls: [{ function: { name: "sh", arguments: { cmd: "pwd" } } }] });
+    expect(msgs[1]).toEqual({ role: "tool", content: "/work", tool_name: "sh" });
+  });
+});
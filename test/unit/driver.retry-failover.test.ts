@@ -0,0 +1,167 @@
+// test/unit/driver.retry-failover.test.ts
+import { describe, it, expect } from "bun:test";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import {
+  makeRetryingDriver,
+  isRetryableChatError,
+  parseFailoverList,
+  withRetries,
+  type FailoverTarget,
+  type RetryPolicy,
+} from "../../src/drivers/retry-driver";
+import type { ChatDriver } from "../../src/drivers/types";
+import { startStubLlmServer, fixture } from "../helpers/stub-llm-server";
+
+const FAST: RetryPolicy = { maxRetries: 2, baseDelayMs: 1, maxDelayMs: 4, cooldownMs: 60_000 };
+const ask = [{ role: "user", from: "User", content: "hi" }];
+// Real drivers share the 1 req/s "llm-ask" rate limiter, so multi-request cases need headroom.
+const SLOW = 20_000;
+
+const openai = (baseUrl: string, model = "m1"): FailoverTarget => ({
+  baseUrl,
+  model,
+  driver: makeStreamingOpenAiLmStudio({ baseUrl, model }),
+});
+
+describe("retry + failover driver", () => {
+  it("retries 429/5xx with backoff until the endpoint recovers", async () => {
+    const stub = await startStubLlmServer([
+      { status: 503, contentType: "text/plain", body: "loading model" },
+      { status: 429, contentType: "text/plain", body: "slow down" },
+      { body: fixture("openai/hello.sse") },
+    ]);
+    try {
+      const driver = makeRetryingDriver([openai(stub.baseUrl)], FAST);
+      const out = await driver.chat(ask);
+      expect(out.text).toBe("Hello.");
+      expect(stub.requests.length).toBe(3);
+    } finally {
+      await stub.close();
+    }
+  }, SLOW);
+
+  it("does not retry client errors", async () => {
+    const stub = await startStubLlmServer([{ status: 400, contentType: "text/plain", body: "bad request" }]);
+    try {
+      const driver = makeRetryingDriver([openai(stub.baseUrl)], FAST);
+      await expect(driver.chat(ask)).rejects.toThrow(/400/);
+      expect(stub.requests.length).toBe(1);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("fails over to the next endpoint/model and keeps preferring it", async () => {
+    const dead = await startStubLlmServer([
+      { status: 500, body: "x" }, { status: 500, body: "x" }, { status: 500, body: "x" },
+    ]);
+    const backup = await startStubLlmServer([
+      { body: fixture("openai/hello.sse") },
+      { body: fixture("openai/hello.sse") },
+    ]);
+    try {
+      const driver = makeRetryingDriver([openai(dead.baseUrl), openai(backup.baseUrl, "m2")], FAST);
+      expect((await driver.chat(ask, { model: "m1" })).text).toBe("Hello.");
+      expect((await driver.chat(ask, { model: "m1" })).text).toBe("Hello.");
+
+      expect(dead.requests.length).toBe(3); // 1 attempt + 2 retries, then never again during cooldown
+      expect(backup.requests.map((r) => r.body.model)).toEqual(["m2", "m2"]);
+    } finally {
+      await dead.close();
+      await backup.close();
+    }
+  }, SLOW);
+
+  it("fails over when the primary refuses connections", async () => {
+    const gone = await startStubLlmServer([]);
+    const goneUrl = gone.baseUrl;
+    await gone.close();
+    const backup = await startStubLlmServer([{ body: fixture("openai/hello.sse") }]);
+    try {
+      const driver = makeRetryingDriver([openai(goneUrl), openai(backup.baseUrl)], FAST);
+      expect((await driver.chat(ask)).text).toBe("Hello.");
+    } finally {
+      await backup.close();
+    }
+  }, SLOW);
+
+  it("goes back to the primary when the failover target fails too", async () => {
+    const served: string[] = [];
+    const scripted = (name: string, fails: (call: number) => boolean): ChatDriver => {
+      let call = 0;
+      return {
+        async chat() {
+          if (fails(call++)) throw Object.assign(new Error("boom"), { status: 503 });
+          served.push(name);
+          return { text: name, toolCalls: [] };
+        },
+      };
+    };
+    const driver = makeRetryingDriver([
+      { baseUrl: "a", model: "m1", driver: scripted("primary", (n) => n < 3) }, // a brief outage
+      { baseUrl: "b", model: "m2", driver: scripted("backup", (n) => n > 0) },  // serves once, then fails
+    ], FAST);
+
+    expect((await driver.chat(ask)).text).toBe("backup");
+    expect((await driver.chat(ask)).text).toBe("primary");
+    expect((await driver.chat(ask)).text).toBe("primary"); // preferred again, not the failing backup
+    expect(served).toEqual(["backup", "primary", "primary"]);
+  });
+
+  it("keeps the shared failover list off drivers for another API", () => {
+    const saved = { shared: process.env.ORG_LLM_FAILOVER, own: process.env.ORG_LLM_FAILOVER_CLAUDE };
+    const made: string[] = [];
+    const make = (t: { baseUrl: string; model: string }): ChatDriver => {
+      made.push(`${t.model}@${t.baseUrl}`);
+      return { chat: async () => ({ text: "", toolCalls: [] }) };
+    };
+    const primary = { baseUrl: "https://api.anthropic.com", model: "claude" };
+    try {
+      process.env.ORG_LLM_FAILOVER = "qwen3@http://10.0.0.5:1234";
+      delete process.env.ORG_LLM_FAILOVER_CLAUDE;
+      withRetries("claude", primary, make, { sharedFailover: false });
+      expect(made).toEqual(["claude@https://api.anthropic.com"]);
+
+      made.length = 0;
+      process.env.ORG_LLM_FAILOVER_CLAUDE = "claude-2@https://gw.example.com";
+      withRetries("claude", primary, make, { sharedFailover: false });
+      expect(made).toEqual(["claude@https://api.anthropic.com", "claude-2@https://gw.example.com"]);
+
+      made.length = 0;
+      withRetries("alice", { baseUrl: "http://localhost:1234", model: "m" }, make);
+      expect(made).toEqual(["m@http://localhost:1234", "qwen3@http://10.0.0.5:1234"]);
+    } finally {
+      for (const [k, v] of [["ORG_LLM_FAILOVER", saved.shared], ["ORG_LLM_FAILOVER_CLAUDE", saved.own]] as const) {
+        if (v === undefined) delete process.env[k];
+        else process.env[k] = v;
+      }
+    }
+  });
+
+  it("never retries once tokens have been streamed", async () => {
+    let calls = 0;
+    const flaky: ChatDriver = {
+      async chat(_m, opts) {
+        calls++;
+        opts?.onToken?.("partial");
+        throw Object.assign(new Error("socket hang up"), { code: "ECONNRESET" });
+      },
+    };
+    const driver = makeRetryingDriver([{ baseUrl: "x", model: "m", driver: flaky }], FAST);
+    await expect(driver.chat(ask, { onToken: () => { } })).rejects.toThrow(/hang up/);
+    expect(calls).toBe(1);
+  });
+
+  it("classifies errors and parses failover lists", () => {
+    expect(isRetryableChatError({ status: 502 })).toBe(true);
+    expect(isRetryableChatError({ status: 404 })).toBe(false);
+    expect(isRetryableChatError(new Error("[fetch timeout] x", { cause: { code: "ECONNREFUSED" } }))).toBe(true);
+
+    const primary = { baseUrl: "http://a/v1", model: "big" };
+    expect(parseFailoverList("small, big@http://b/v1 ,@http://c/v1", primary)).toEqual([
+      { model: "small", baseUrl: "http://a/v1" },
+      { model: "big", baseUrl: "http://b/v1" },
+      { model: "big", baseUrl: "http://c/v1" },
+    ]);
+  });
+});
@@ -0,0 +1,112 @@
+// test/unit/driver.sampling.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdtempSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import { makeStreamingOllamaNative } from "../../src/drivers/streaming-ollama-native";
+import { makeStreamingAnthropic } from "../../src/drivers/streaming-anthropic";
+import type { SamplingParams } from "../../src/drivers/sampling";
+import { parseCaretSpec } from "../../src/agents/agent-definition";
+import { parseTeamFile } from "../../src/config/team-file";
+import { AgentManger } from "../../src/agents/agent-manager";
+import type { AgentCallbacks } from "../../src/agents/agent";
+import { NoiseFilters } from "../../src/scheduler/filters";
+import { startStubLlmServer, fixture } from "../helpers/stub-llm-server";
+
+const SSE_OK = 'data: {"choices":[{"delta":{"content":"ok"}}]}\n\ndata: [DONE]\n\n';
+const sampling: SamplingParams = { temperature: 0.3, topP: 0.9, seed: 42, maxTokens: 256, stop: ["</done>"] };
+const hi = [{ role: "user" as const, from: "User", content: "hi" }];
+
+describe("per-agent sampling and endpoints", () => {
+  it("maps sampling parameters onto each wire format", async () => {
+    const stub = await startStubLlmServer([
+      { body: SSE_OK },
+      { contentType: "application/x-ndjson", body: fixture("ollama/tool-call.ndjson") },
+      { body: fixture("anthropic/tool-use.sse") },
+    ]);
+    try {
+      await makeStreamingOpenAiLmStudio({ baseUrl: stub.baseUrl, model: "m", sampling }).chat(hi);
+      await makeStreamingOllamaNative({ baseUrl: stub.baseUrl, model: "m", options: { num_ctx: 8192, temperature: 1 }, sampling }).chat(hi);
+      await makeStreamingAnthropic({ baseUrl: stub.baseUrl, model: "m", apiKey: "k", sampling }).chat(hi);
+
+      const [openai, ollama, anthropic] = stub.requests.map((r) => r.body);
+      expect(openai).toMatchObject({ temperature: 0.3, top_p: 0.9, seed: 42, max_tokens: 256, stop: ["</done>"] });
+      expect(ollama.options).toEqual({ num_ctx: 8192, temperature: 0.3, top_p: 0.9, seed: 42, num_predict: 256, stop: ["</done>"] });
+      expect(anthropic).toMatchObject({ temperature: 0.3, top_p: 0.9, max_tokens: 256, stop_sequences: ["</done>"] });
+      expect(anthropic.seed).toBeUndefined();
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("leaves unset parameters to the server", async () => {
+    const stub = await startStubLlmServer([{ body: SSE_OK }]);
+    try {
+      await makeStreamingOpenAiLmStudio({ baseUrl: stub.baseUrl, model: "m" }).chat(hi);
+      const body = stub.requests[0].body;
+      for (const k of ["temperature", "top_p", "seed", "max_tokens", "stop"]) expect(body[k]).toBeUndefined();
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("declares endpoints and sampling in the caret spec and the team file", () => {
+    expect(parseCaretSpec("coder^qwen3:8b@http://10.0.0.5:11434^ollama.native,planner^gpt-oss")).toEqual([
+      { id: "coder", model: "qwen3:8b", baseUrl: "http://10.0.0.5:11434", kind: "ollama", protocol: "native" },
+      { id: "planner", model: "gpt-oss" },
+    ]);
+
+    const [a] = parseTeamFile("version: 1\nagents:\n  - id: a\n    topP: 0.5\n    seed: 7\n    maxTokens: 512\n    stop: END", "org.yaml", ".");
+    expect(a.sampling).toEqual({ topP: 0.5, seed: 7, maxTokens: 512, stop: ["END"] });
+    expect(() => parseTeamFile("version: 1\nagents:\n  - id: a\n    topP: 2", "org.yaml", "."))
+      .toThrow("org.yaml: agents[0].topP: expected a number between 0 and 1");
+    expect(() => parseTeamFile("version: 1\nagents:\n  - id: a\n    stop: []", "org.yaml", "."))
+      .toThrow("org.yaml: agents[0].stop: expected a stop sequence or a list of them");
+  });
+
+  describe("a planner and a coder on different servers", () => {
+    let cwd: string;
+    let tmp: string;
+    let prevProbe: string | undefined;
+    beforeEach(() => {
+      cwd = process.cwd();
+      tmp = mkdtempSync(path.join(tmpdir(), "org-sampling-"));
+      process.chdir(tmp); // agent memory persists under cwd
+      prevProbe = process.env.ORG_MODEL_PROBE;
+      process.env.ORG_MODEL_PROBE = "0";
+    });
+    afterEach(async () => {
+      await new Promise((r) => setTimeout(r, 50)); // respond() saves memory without awaiting
+      process.chdir(cwd);
+      rmSync(tmp, { recursive: true, force: true });
+      if (prevProbe === undefined) delete process.env.ORG_MODEL_PROBE;
+      else process.env.ORG_MODEL_PROBE = prevProbe;
+    });
+
+    it("each agent uses its own base URL, model and sampling", async () => {
+      const remote = await startStubLlmServer([{ body: SSE_OK }]);
+      const local = await startStubLlmServer([{ body: SSE_OK }]);
+      try {
+        const specs = await new AgentManger().parse(
+          `planner^big-model@${remote.baseUrl}^lmstudio.openai,coder^small-model@${local.baseUrl}^lmstudio.openai`,
+          { model: "shared", baseUrl: "http://127.0.0.1:9", protocol: "openai", sampling: { temperature: 0.7 } },
+        );
+        const callbacks: AgentCallbacks = {
+          shouldAbort: () => false,
+          onStreamStart: () => {},
+          onStreamEnd: () => {},
+          onRoute: async () => false,
+          onRouteCompleted: async () => false,
+        };
+        for (const s of specs) await s.model.respond(hi, 1, new NoiseFilters(), [], callbacks);
+
+        expect(remote.requests[0].body).toMatchObject({ model: "big-model", temperature: 0.7 });
+        expect(local.requests[0].body).toMatchObject({ model: "small-model", temperature: 0.7 });
+      } finally {
+        await remote.close();
+        await local.close();
+      }
+    });
+  });
+});
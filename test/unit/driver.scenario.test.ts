@@ -0,0 +1,82 @@
+// test/unit/driver.scenario.test.ts
+import { describe, it, expect } from "bun:test";
+import { existsSync, mkdtempSync, readFileSync, rmSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { AgentManger } from "../../src/agents/agent-manager";
+import { ScenarioDriver, scenarioFromObject } from "../../src/drivers/scenario-driver";
+import { parseYaml } from "../../src/config/yaml";
+import { scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+const defaults = { model: "m", baseUrl: "http://127.0.0.1:9", protocol: "openai" as const };
+
+const ALICE = `version: 1
+agent: alice
+steps:
+  - expect: { from: User, contains: "check the build" }
+    say: |
+      ##file:status.txt
+      build check started
+    tools:
+      - sh: echo scenario-ok
+  - expect: { role: tool, contains: scenario-ok }
+    say: "@@bob the build is green"
+`;
+
+const BOB = {
+  version: 1,
+  steps: [{ expect: { from: "alice", contains: "green" }, delayMs: 20, say: "##file:notes.txt\nbuild green per alice" }],
+};
+
+describe("scenario-driven mock agents", () => {
+  it("two scripted agents run a tool, DM each other and write a file", async () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-scenario-"));
+    const prev = process.cwd();
+    process.chdir(dir);
+    try {
+      writeFileSync("alice.yaml", ALICE);
+      writeFileSync("bob.json", JSON.stringify(BOB));
+      const specs = await new AgentManger().create([
+        { id: "alice", model: "alice.yaml", kind: "mock", budgets: { maxTools: 3 } },
+        { id: "bob", model: "bob.json", kind: "mock" },
+      ], defaults);
+      const drivers = specs.map((s) => (s.model as any).driver as ScenarioDriver);
+      expect(drivers.every((d) => d instanceof ScenarioDriver)).toBe(true);
+
+      const sched = scriptedScheduler(specs.map((s) => s.model));
+      const running = sched.start();
+      await sched.interject("@@alice please check the build");
+      for (let i = 0; i < 300 && !existsSync("notes.txt"); i++) await wait(10);
+      sched.stop();
+      await running;
+
+      expect(drivers.map((d) => d.failures)).toEqual([[], []]);
+      expect(drivers.every((d) => d.done())).toBe(true);
+      expect(readFileSync("status.txt", "utf8")).toBe("build check started\n");
+      expect(readFileSync("notes.txt", "utf8")).toContain("build green per alice");
+    } finally {
+      process.chdir(prev);
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("reports bad scenarios and unexpected input with the file and step", async () => {
+    expect(() => scenarioFromObject(parseYaml("version: 1\nsteps:\n  - expect: { form: User }\n    say: hi"), "s.yaml"))
+      .toThrow("s.yaml: steps[0].expect.form: unknown field (known: role, from, contains, notContains, matches)");
+    expect(() => scenarioFromObject({ version: 1, steps: [{ delayMs: 5 }] }, "s.yaml"))
+      .toThrow("s.yaml: steps[0]: a step needs `say`, `tools` or both");
+    expect(() => new ScenarioDriver(scenarioFromObject({ version: 1, agent: "bob", steps: [{ say: "hi" }] }, "s.yaml"), "alice"))
+      .toThrow('s.yaml: agent: written for "bob", used by "alice"');
+
+    const driver = new ScenarioDriver(scenarioFromObject({ version: 1, steps: [{ expect: { from: "User" }, say: "hi" }] }, "s.yaml"));
+    // Memory side-calls don't consume steps.
+    const schema = { type: "object", required: ["persona", "confidence"], properties: { persona: { type: "string" }, confidence: { type: "number", minimum: 0 } } };
+    expect((await driver.chat([], { responseFormat: { name: "x", schema } })).text).toBe('{"persona":"","confidence":0}');
+    await expect(driver.chat([{ role: "user", from: "bob", content: "yo" }]))
+      .rejects.toThrow('s.yaml: steps[0].expect: no new message with from "User"; got user from bob: "yo"');
+    await expect(driver.chat([{ role: "user", from: "User", content: "again" }]))
+      .rejects.toThrow("s.yaml: no step left for call 2 (the scenario has 1)");
+    expect(driver.failures.length).toBe(2);
+  });
+});
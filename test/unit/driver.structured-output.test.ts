@@ -0,0 +1,122 @@
+// test/unit/driver.structured-output.test.ts
+import { describe, it, expect } from "bun:test";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import { makeStreamingOllamaNative } from "../../src/drivers/streaming-ollama-native";
+import { chatStructured, parseJsonText, validateJson } from "../../src/drivers/structured-output";
+import { makeRecordingDriver, makeReplayDriver, readCassette } from "../../src/drivers/cassette-driver";
+import { makeRetryingDriver } from "../../src/drivers/retry-driver";
+import type { ChatDriver, ChatMessage, ChatOutput } from "../../src/drivers/types";
+import { LANE_SUMMARY_FORMAT, renderLaneSummary, type LaneSummary } from "../../src/memory/memory-schemas";
+import { startStubLlmServer } from "../helpers/stub-llm-server";
+
+const ask = [{ role: "user", from: "User", content: "summarize" }];
+
+/** Returns the given replies in order and records what it was sent. */
+class ScriptedDriver implements ChatDriver {
+  calls: ChatMessage[][] = [];
+  constructor(private readonly replies: string[], readonly structuredOutput = true) {}
+  async chat(messages: ChatMessage[]): Promise<ChatOutput> {
+    this.calls.push(messages);
+    return { text: this.replies[this.calls.length - 1] ?? "", toolCalls: [] };
+  }
+}
+
+describe("structured output", () => {
+  it("sends the schema as response_format to OpenAI-compatible servers", async () => {
+    const stub = await startStubLlmServer([{
+      body: 'data: {"choices":[{"delta":{"content":"{\\"bullets\\":[\\"a\\"]}"}}]}\n\ndata: [DONE]\n\n',
+    }]);
+    try {
+      const driver = makeStreamingOpenAiLmStudio({ baseUrl: stub.baseUrl, model: "m" });
+      const { value } = await chatStructured<LaneSummary>(driver, ask, { responseFormat: LANE_SUMMARY_FORMAT });
+      expect(value).toEqual({ bullets: ["a"] });
+      expect(stub.requests[0].body.response_format).toEqual({
+        type: "json_schema",
+        json_schema: { name: "lane_summary", schema: LANE_SUMMARY_FORMAT.schema, strict: true },
+      });
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("sends the schema as format to native Ollama", async () => {
+    const stub = await startStubLlmServer([{
+      contentType: "application/x-ndjson",
+      body: JSON.stringify({ message: { role: "assistant", content: '{"bullets":[]}' }, done: true }) + "\n",
+    }]);
+    try {
+      const driver = makeStreamingOllamaNative({ baseUrl: stub.baseUrl, model: "m" });
+      await driver.chat(ask, { responseFormat: LANE_SUMMARY_FORMAT });
+      expect(stub.requests[0].body.format).toEqual(LANE_SUMMARY_FORMAT.schema);
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("re-asks once with the validation errors, then accepts a valid reply", async () => {
+    const driver = new ScriptedDriver(['{"bullet": "typo"}', '```json\n{"bullets": ["fixed"]}\n```']);
+    const { value } = await chatStructured<LaneSummary>(driver, ask, { responseFormat: LANE_SUMMARY_FORMAT });
+
+    expect(value).toEqual({ bullets: ["fixed"] });
+    expect(driver.calls).toHaveLength(2);
+    const nudge = driver.calls[1][driver.calls[1].length - 1].content;
+    expect(nudge).toContain("$.bullets: required");
+    expect(nudge).toContain("$.bullet: not allowed");
+  });
+
+  it("gives up after one re-ask and exposes the first reply", async () => {
+    const driver = new ScriptedDriver(["- just prose", "- still prose"]);
+    const res = await chatStructured(driver, ask, { responseFormat: LANE_SUMMARY_FORMAT });
+
+    expect(res.value).toBeNull();
+    expect(res.output.text).toBe("- just prose");
+    expect(driver.calls).toHaveLength(2);
+  });
+
+  it("does not re-ask a driver that cannot send the schema", async () => {
+    const driver = new ScriptedDriver(["- the summary in prose", "{}"], false);
+    const res = await chatStructured(driver, ask, { responseFormat: LANE_SUMMARY_FORMAT });
+
+    expect(res.value).toBeNull();
+    expect(res.output.text).toBe("- the summary in prose");
+    expect(driver.calls).toHaveLength(1);
+  });
+
+  it("keeps the re-ask behaviour through the retry and cassette wrappers", async () => {
+    const target = (structured: boolean) => ({ baseUrl: "http://x", model: "m", driver: new ScriptedDriver([], structured) });
+    expect(makeRetryingDriver([target(true)]).structuredOutput).toBe(true);
+    expect(makeRetryingDriver([target(true), target(false)]).structuredOutput).toBe(false);
+
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "org-structured-"));
+    try {
+      const file = path.join(dir, "alice.jsonl");
+      const live = await chatStructured(makeRecordingDriver(new ScriptedDriver(["prose", '{"bullets": ["ok"]}']), file), ask, { responseFormat: LANE_SUMMARY_FORMAT });
+      expect(live.value).toEqual({ bullets: ["ok"] });
+      expect(readCassette(file)).toHaveLength(2);
+
+      const replayed = await chatStructured(makeReplayDriver(file), ask, { responseFormat: LANE_SUMMARY_FORMAT });
+      expect(replayed.value).toEqual({ bullets: ["ok"] });
+    } finally {
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("validates the schema subset", () => {
+    const schema = {
+      type: "object",
+      properties: { n: { type: "integer", minimum: 0 }, tags: { type: "array", items: { type: "string" }, maxItems: 2 } },
+      required: ["n"],
+    };
+    expect(validateJson({ n: 1, tags: ["a"] }, schema)).toEqual([]);
+    expect(validateJson({ n: -1.5, tags: ["a", 2, "c"] }, schema)).toEqual([
+      "$.n: expected integer, got number",
+      "$.tags: at most 2 items",
+      "$.tags[1]: expected string, got integer",
+    ]);
+    expect(parseJsonText('Sure! {"a": 1} hope that helps')).toEqual({ a: 1 });
+    expect(renderLaneSummary({ bullets: ["one", "- two", " "] })).toBe("- one\n- two");
+  });
+});
@@ -0,0 +1,92 @@
+// test/unit/executor.tool-allowlist.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdtempSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { StandardToolExecutor } from "../../src/executors/standard-tool-executor";
+import { AdvancedGuardRail } from "../../src/guardrails/advanced-guardrail";
+import { LlmAgent } from "../../src/agents/llm-agent";
+import { withRecipeTools } from "../../src/agents/agent-definition";
+import type { AgentCallbacks } from "../../src/agents/agent";
+import type { ChatDriver, ChatMessage, ChatOutput, ChatToolCall } from "../../src/drivers/types";
+import { NoiseFilters } from "../../src/scheduler/filters";
+
+const shCall = (id: string, cmd: string): ChatToolCall =>
+  ({ id, type: "function", function: { name: "sh", arguments: JSON.stringify({ cmd }) } }) as ChatToolCall;
+
+describe("per-agent tool allowlists", () => {
+  it("refuses calls outside the allowlist with a guard decision", async () => {
+    const added: ChatMessage[] = [];
+    const memory: any = { add: async (m: ChatMessage) => { added.push(m); } };
+    const guard = new AdvancedGuardRail({ agentId: "reviewer" });
+    guard.beginTurn({ maxToolHops: 5 });
+
+    const res = await new StandardToolExecutor({ allowedTools: [] }).execute({
+      calls: [shCall("c1", "rm -rf build"), shCall("c2", "ls"), shCall("c3", "pwd")],
+      maxTools: 5,
+      abortCallback: () => false,
+      guard,
+      memory,
+      finalText: "",
+      agentId: "reviewer",
+    });
+
+    expect(res).toEqual({ toolsUsed: 0, forceEndTurn: true });
+    const tools = added.filter((m) => m.role === "tool");
+    expect(tools.map((m) => m.tool_call_id)).toEqual(["c1", "c2"]); // the second refusal ends the turn
+    expect(JSON.parse(String(tools[0].content))).toMatchObject({ ok: false, stderr: "tool not allowed: sh", exit_code: 126 });
+    const nudges = added.filter((m) => m.role === "system").map((m) => String(m.content));
+    expect(nudges[0]).toContain('Tool "sh" is not available to you. You have no tools in this session');
+  });
+
+  it("recipes fill in tools for agents that do not declare their own", () => {
+    const defs = [{ id: "a" }, { id: "b", tools: [] }];
+    expect(withRecipeTools(defs, ["apply_patch", "sh", "git"])).toEqual([{ id: "a", tools: ["sh"] }, { id: "b", tools: [] }]);
+    expect(withRecipeTools(defs, undefined)).toBe(defs);
+  });
+
+  describe("LlmAgent", () => {
+    let cwd: string;
+    let tmp: string;
+    beforeEach(() => {
+      cwd = process.cwd();
+      tmp = mkdtempSync(path.join(tmpdir(), "org-tools-"));
+      process.chdir(tmp); // agent memory persists under cwd
+    });
+    afterEach(async () => {
+      await new Promise((r) => setTimeout(r, 50)); // respond() saves memory without awaiting
+      process.chdir(cwd);
+      rmSync(tmp, { recursive: true, force: true });
+    });
+
+    it("an agent without tools gets no tool schemas and cannot run sh", async () => {
+      const seen: { messages: ChatMessage[]; opts: any }[] = [];
+      const replies: ChatOutput[] = [
+        { text: "", toolCalls: [shCall("c1", "touch pwned")] },
+        { text: "@@user looks fine", toolCalls: [] },
+      ];
+      const driver: ChatDriver = {
+        async chat(messages, opts) {
+          if (opts?.responseFormat) return { text: "{}", toolCalls: [] }; // memory side-calls
+          seen.push({ messages, opts });
+          return replies.shift() ?? { text: "", toolCalls: [] };
+        },
+      };
+      const callbacks: AgentCallbacks = {
+        shouldAbort: () => false,
+        onStreamStart: () => {},
+        onStreamEnd: () => {},
+        onRoute: async () => false,
+        onRouteCompleted: async () => false,
+      };
+
+      await new LlmAgent("reviewer", driver, "m", undefined, { tools: [] })
+        .respond([{ role: "user", from: "User", content: "review" }], 3, new NoiseFilters(), [], callbacks);
+
+      expect(seen[0].opts.tools).toBeUndefined();
+      expect(JSON.stringify(seen[0].messages)).toContain("You have no tools.");
+      const toolMsg = seen[1].messages.find((m) => m.role === "tool");
+      expect(String(toolMsg?.content)).toContain("tool not allowed: sh");
+    });
+  });
+});
@@ -0,0 +1,121 @@
+// test/unit/inbox.threads.test.ts
+import { describe, it, expect } from "bun:test";
+import { Inbox, threadOf, type MessageMeta } from "../../src/scheduler/inbox";
+import type { SchedulerEventOf } from "../../src/scheduler/events";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+const user = (content: string) => ({ role: "user" as const, from: "User", content });
+const from = (who: string, content: string) => ({ role: "user" as const, from: who, content });
+
+describe("Inbox threads", () => {
+  it("gives each message an id and a thread", () => {
+    const seen: string[] = [];
+    const inbox = new Inbox((to, msg, meta) => seen.push(`${to} ${meta.id} ${meta.threadId ?? "-"}`));
+    inbox.push("alice", user("all"));
+    inbox.push("alice", user("just you"), { direct: true });
+    inbox.push("alice", from("Bob", "psst"), { direct: true, replyTo: "m2" });
+    inbox.push("alice", { role: "system", from: "System", content: "[budget] low" });
+
+    expect(seen).toEqual(["alice m1 group", "alice m2 dm:alice+user", "alice m3 dm:alice+bob", "alice m4 -"]);
+    expect(inbox.snapshotMeta().queues.alice[2]).toMatchObject({ id: "m3", direct: true, replyTo: "m2", priority: 0 });
+    expect(threadOf("bob", "Alice", true)).toBe(threadOf("alice", "bob", true));
+  });
+
+  it("hands out one thread per batch, system notes riding along", () => {
+    const inbox = new Inbox();
+    inbox.push("alice", user("all"));
+    inbox.push("alice", from("bob", "psst"), { direct: true });
+    inbox.push("alice", { role: "system", from: "System", content: "note" });
+    inbox.push("alice", user("again"));
+    inbox.push("alice", from("carol", "urgent"), { direct: true, priority: 2 });
+
+    const batches = [];
+    while (inbox.hasWork("alice")) {
+      const b = inbox.nextBatchFor("alice");
+      batches.push(`${b.threadId}: ${b.messages.map((m) => m.content).join(", ")}`);
+    }
+    expect(batches).toEqual(["dm:alice+carol: note, urgent", "group: all, again", "dm:alice+bob: psst"]);
+    expect(inbox.nextBatchFor("alice")).toEqual({ messages: [], meta: [] });
+  });
+
+  it("holds cc copies until the agent has a turn anyway", () => {
+    const inbox = new Inbox();
+    inbox.push("alice", from("bob", "[cc] fyi"), { direct: true, cc: true, threadId: "dm:alice+bob+carol" });
+    expect(inbox.hasWork("alice")).toBe(false);
+    expect(inbox.pending("alice")).toEqual({ count: 0, userDMs: 0, agentDMs: 0 });
+    inbox.push("alice", user("all"));
+    const b = inbox.nextBatchFor("alice");
+    expect([b.threadId, b.messages.map((m) => m.content)]).toEqual(["group", ["[cc] fyi", "all"]]);
+  });
+
+  it("drops messages whose time to live ran out", async () => {
+    const inbox = new Inbox();
+    inbox.push("alice", user("soon stale"), { ttlMs: 5 });
+    inbox.push("alice", user("keeps"));
+    expect(inbox.pending("alice").count).toBe(2);
+    await wait(15);
+    expect(inbox.nextBatchFor("alice").messages.map((m) => m.content)).toEqual(["keeps"]);
+    expect(inbox.hasAnyWork()).toBe(false);
+  });
+
+  it("answers the user first, and lets a guard's nudge expire", async () => {
+    const batches: string[] = [];
+    const alice = new ScriptedAgent("alice", (b) => {
+      batches.push(b.map((m) => m.content).join(", "));
+      return b.some((m) => m.content === "now please") ? "@@group who should go?" : undefined;
+    });
+    const bob = new ScriptedAgent("bob", () => undefined);
+    const sched = scriptedScheduler([alice, bob]);
+    const queued: SchedulerEventOf<"message-enqueued">[] = [];
+    sched.events.on("message-enqueued", (e) => queued.push(e));
+    (sched as any).inbox.push("alice", from("bob", "earlier chatter"));
+
+    await sched.interject("@@alice now please");
+    const running = sched.start();
+    for (let i = 0; i < 100 && batches.length < 2; i++) await wait(5);
+    sched.stop();
+    await running;
+
+    expect(batches.slice(0, 2).map((b) => b.split(", ")[0])).toEqual(["now please", "earlier chatter"]); // the nudge rides along with the second
+    expect(queued.find((e) => e.message.content === "now please")?.meta.priority).toBe(1);
+    const nudge = queued.find((e) => e.message.role === "system")?.meta;
+    expect(nudge && nudge.expiresAt! - nudge.at).toBe(5 * 60_000);
+  });
+
+  it("restores ids and threads, and keeps counting after them", () => {
+    const a = new Inbox();
+    a.push("alice", user("drained"));
+    a.nextBatchFor("alice");
+    a.push("alice", from("bob", "psst"), { direct: true });
+    const b = new Inbox();
+    b.restore(a.snapshot(), a.snapshotMeta());
+    expect(b.snapshotMeta().queues.alice).toEqual(a.snapshotMeta().queues.alice);
+    expect(b.push("alice", user("next")).id).toBe("m3");
+
+    const old = new Inbox(); // a session saved before messages had metadata
+    old.restore({ alice: [from("bob", "hi")] });
+    expect(old.snapshotMeta().queues.alice[0]).toMatchObject({ id: "m1", threadId: "group" });
+  });
+
+  it("links what an agent sends to the message it answers", async () => {
+    const alice = new ScriptedAgent("alice", (b) => (b.some((m) => m.from === "User") ? "@@bob can you check?" : undefined));
+    const bob = new ScriptedAgent("bob", (b) => (b.some((m) => m.from === "alice") ? "@@alice looks fine" : undefined));
+    const sched = scriptedScheduler([alice, bob]);
+    const queued: SchedulerEventOf<"message-enqueued">[] = [];
+    sched.events.on("message-enqueued", (e) => queued.push(e));
+
+    await sched.interject("@@alice start");
+    const running = sched.start();
+    for (let i = 0; i < 100 && queued.length < 3; i++) await wait(5);
+    sched.stop();
+    await running;
+
+    const [ask, check, answer]: MessageMeta[] = queued.map((e) => e.meta);
+    expect(queued.map((e) => `${e.message.from}->${e.to}`)).toEqual(["User->alice", "alice->bob", "bob->alice"]);
+    expect(ask.threadId).toBe("dm:alice+user");
+    expect(check).toMatchObject({ threadId: "dm:alice+bob", direct: true });
+    expect(check.replyTo).toBeUndefined(); // alice was answering the user, not bob
+    expect(answer).toMatchObject({ threadId: "dm:alice+bob", replyTo: check.id });
+  });
+});
@@ -0,0 +1,56 @@
+// test/unit/metrics.llm-usage.test.ts
+import { describe, it, expect } from "bun:test";
+import { makeStreamingOpenAiLmStudio } from "../../src/drivers/streaming-openai-lmstudio";
+import { usageOrEstimate } from "../../src/drivers/usage";
+import { rollupBy, rollupUsage, type LlmCallEvent } from "../../src/metrics/runtime-metrics";
+import { startStubLlmServer, fixture } from "../helpers/stub-llm-server";
+
+const ev = (agent: string, runId: string, p: number, c: number, ttftMs: number | undefined, totalMs: number, estimated?: boolean): LlmCallEvent => ({
+  kind: "llm", runId, agent, promptTokens: p, completionTokens: c, ttftMs, totalMs, estimated,
+  timestamp: "2025-01-01T00:00:00.000Z" as LlmCallEvent["timestamp"],
+});
+
+describe("LLM usage accounting", () => {
+  it("reads usage and TTFT from an OpenAI-compatible stream", async () => {
+    const stub = await startStubLlmServer([{ body: fixture("openai/hello.sse") }]);
+    try {
+      const driver = makeStreamingOpenAiLmStudio({ baseUrl: stub.baseUrl, model: "m" });
+      const out = await driver.chat([{ role: "user", from: "User", content: "hi" }]);
+
+      expect(out.usage).toEqual({ promptTokens: 12, completionTokens: 3 });
+      expect(out.timing!.ttftMs!).toBeGreaterThanOrEqual(0);
+      expect(out.timing!.totalMs).toBeGreaterThanOrEqual(out.timing!.ttftMs!);
+      expect(stub.requests[0].body.stream_options).toEqual({ include_usage: true });
+    } finally {
+      await stub.close();
+    }
+  });
+
+  it("estimates whatever the server omitted", () => {
+    const msgs = [{ role: "user", from: "User", content: "x".repeat(40) }];
+
+    expect(usageOrEstimate(msgs, { text: "y".repeat(8), toolCalls: [] }))
+      .toEqual({ promptTokens: 10, completionTokens: 2, estimated: true });
+    expect(usageOrEstimate(msgs, { text: "", toolCalls: [], usage: { promptTokens: 7, completionTokens: 1 } }))
+      .toEqual({ promptTokens: 7, completionTokens: 1 });
+    expect(usageOrEstimate(msgs, { text: "abcd", toolCalls: [], usage: { promptTokens: 7 } }))
+      .toEqual({ promptTokens: 7, completionTokens: 1, estimated: true });
+  });
+
+  it("rolls up tokens, tokens/sec and TTFT per agent and per run", () => {
+    const events = [
+      ev("alice", "r1", 100, 50, 200, 1200),
+      ev("alice", "r1", 120, 30, 400, 1400, true),
+      ev("bob", "r2", 10, 10, undefined, 500),
+    ];
+
+    const alice = rollupUsage(events.slice(0, 2));
+    expect(alice).toMatchObject({ calls: 2, promptTokens: 220, completionTokens: 80, estimatedCalls: 1, ttftP50: 200 });
+    expect(alice.tokensPerSec).toBeCloseTo(40); // 80 tokens over 2s of generation
+
+    const byRun = rollupBy(events, (e) => e.runId);
+    expect([...byRun.keys()]).toEqual(["r1", "r2"]);
+    expect(byRun.get("r2")!.ttftP50).toBeNaN();
+    expect(byRun.get("r2")!.tokensPerSec).toBeCloseTo(20);
+  });
+});
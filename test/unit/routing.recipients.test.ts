@@ -0,0 +1,75 @@
+// test/unit/routing.recipients.test.ts
+import { describe, it, expect } from "bun:test";
+import { roleTags, routeWithTags } from "../../src/routing/route-with-tags";
+import type { SchedulerEventOf } from "../../src/scheduler/events";
+import type { ChatMessage } from "../../src/types";
+import { ScriptedAgent, scriptedScheduler, type Script } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+/** A team from "id:role" specs; each agent records its turns in `turns`. */
+function team(specs: string[], script: (id: string) => Script) {
+  const turns: string[] = [];
+  const agents = specs.map((spec) => {
+    const [id, role] = spec.split(":");
+    const a = new ScriptedAgent(id, (b) => { turns.push(id); return script(id)(b); });
+    a.role = role;
+    return a;
+  });
+  return { agents, turns };
+}
+
+async function run(agents: ScriptedAgent[], kickoff: string, until: () => boolean) {
+  const sched = scriptedScheduler(agents);
+  const queued: SchedulerEventOf<"message-enqueued">[] = [];
+  sched.events.on("message-enqueued", (e) => queued.push(e));
+  await sched.interject(kickoff);
+  const running = sched.start();
+  for (let i = 0; i < 100 && !until(); i++) await wait(5);
+  await wait(20);
+  sched.stop();
+  await running;
+  return queued;
+}
+
+describe("recipient lists, role tags and cc", () => {
+  it("resolves role tags from the team's roles", () => {
+    expect(roleTags([{ id: "bob", role: "reviewer" }, { id: "carol", role: "Reviewer" }, { id: "dev" }]))
+      .toEqual({ reviewer: ["bob", "carol"], reviewers: ["bob", "carol"] });
+    const out = routeWithTags("@@reviewers,cc:dev please look", ["bob", "carol", "dev"], { reviewers: ["bob", "carol"] });
+    expect(out.deliveries).toEqual([{ kind: "agents", to: ["bob", "carol"], cc: ["dev"], content: "please look" }]);
+    expect(out.sawTags.agent).toBe(true);
+  });
+
+  it("sends a role multicast to each reviewer, and a cc copy that earns no turn", async () => {
+    const { agents, turns } = team(["alice:lead", "bob:reviewer", "carol:reviewer", "dev"], (id) => (b) =>
+      id === "alice" && b.some((m) => m.from === "User") ? "@@reviewers,cc:dev please review the patch" : undefined);
+    const queued = await run(agents, "@@alice start", () => turns.length >= 3);
+
+    expect(turns).toEqual(["alice", "bob", "carol"]); // no jump: reviewers go in team order; dev only reads along
+    const sent = queued.filter((e) => e.message.from === "alice");
+    expect(sent.map((e) => `${e.to}${e.meta.cc ? " (cc)" : ""}`)).toEqual(["bob", "carol", "dev (cc)"]);
+    expect(new Set(sent.map((e) => e.meta.threadId))).toEqual(new Set(["dm:alice+bob+carol+dev"]));
+    expect(sent[2].message.content).toBe("[cc, to bob, carol; no reply needed] please review the patch");
+    expect(agents[3].seen).toEqual([]);
+  });
+
+  it("leaves the sender out of its own role and lets a single addressee jump the queue", async () => {
+    const { agents, turns } = team(["bob:reviewer", "carol:reviewer", "dev"], (id) => (b) =>
+      id === "carol" && b.some((m) => m.from === "User") ? "@@reviewers,cc:carol can you double-check?" : undefined);
+    const queued = await run(agents, "@@carol go", () => turns.length >= 2);
+
+    expect(turns).toEqual(["carol", "bob"]);
+    expect(queued.filter((e) => e.message.from === "carol").map((e) => e.to)).toEqual(["bob"]);
+  });
+
+  it("lets the user address several agents and copy one in", async () => {
+    const { agents, turns } = team(["alice", "bob", "carol"], () => () => undefined);
+    const queued = await run(agents, "@@alice,bob,cc:carol plan the release", () => turns.length >= 2);
+
+    expect(turns).toEqual(["alice", "bob"]);
+    expect(queued.map((e) => `${e.to}${e.meta.cc ? " (cc)" : ""} ${e.meta.threadId}`))
+      .toEqual(["alice dm:alice+bob+carol+user", "bob dm:alice+bob+carol+user", "carol (cc) dm:alice+bob+carol+user"]);
+    expect(agents[0].seen.map((m: ChatMessage) => m.content)).toEqual(["plan the release"]);
+  });
+});
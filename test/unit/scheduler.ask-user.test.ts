@@ -1,137 +1,94 @@
-
-// test/scheduler.ask-user.test.ts
-import { expect, test } from "bun:test";
-import { NoiseFilters } from "../../src/scheduler/filters";
-import RandomScheduler from "../../src/scheduler/random-scheduler";
+// test/unit/scheduler.ask-user.test.ts
+import { describe, it, expect } from "bun:test";
+import { Logger } from "../../src/logger";
+import type { AgentCallbacks, AgentReply } from "../../src/agents/agent";
+import type { NoiseFilters } from "../../src/scheduler/filters";
 import type { ChatMessage } from "../../src/types";
-import type { GuardDecision } from "../../src/guardrails/guardrail";
-import type { Agent, AgentCallbacks } from "../../src/agents/agent";
-
-class MockAgent implements Agent {
-  id: string;
-  received: ChatMessage[][] = [];
-  saved = false;
-
-  constructor(id: string) { this.id = id; }
+import type { SchedulerKind } from "../../src/scheduler/create-scheduler";
+import { spyMethod } from "../helpers/spy";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
 
-  // Minimal surface used by the scheduler
-  save() { this.saved = true; }
-  guardOnIdle() { return null as any as GuardDecision; }
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
 
-  async respond(
-    messagesIn: ChatMessage[],
-    _maxTools: number,
-    _filters: NoiseFilters,
-    _peers: Agent[],
-    callbacks: AgentCallbacks
-  ): Promise<string[]> {
-    this.received.push(messagesIn);
-
-    // Ask the user once via @@user (triggers onRoute → onRouteCompleted)
-    await callbacks.onRoute("@@user please reply: ping", new NoiseFilters());
-    await callbacks.onRouteCompleted("ping", 0, true);
-    return ["asked"];
+/** Asks the user a question on its first turn, the way a reply tagged @@user ends up in onRouteCompleted. */
+class AskingAgent extends ScriptedAgent {
+  private asked = false;
+  constructor(id: string) {
+    super(id, () => undefined);
+  }
+  async respond(messages: ChatMessage[], _maxTools: number, _filters: NoiseFilters, _peers: unknown[], cb: AgentCallbacks): Promise<AgentReply[]> {
+    this.seen.push(...messages);
+    if (this.asked) return [];
+    this.asked = true;
+    await cb.onRouteCompleted("ping", 0, true);
+    return [{ message: "ping", toolsUsed: 0 }];
   }
 }
 
-function makeQueue<T>(items: T[]) {
-  let q = [...items];
-  return async () => q.length ? q.shift()! : undefined;
-}
-
-test("untagged reply DMs last asker (single read via readUserLine)", async () => {
-  const a = new MockAgent("alice");
-
-  let askUserCalls: Array<{label: string; prompt: string}> = [];
-  const askUser = async (label: string, prompt: string) => {
-    askUserCalls.push({ label, prompt });
-    // If readUserLine is present, we should NOT be called.
-    // Returning empty ensures no accidental consumption.
-    return "";
-  };
-
-  // First line the user types:
-  const readUserLine = makeQueue<string>(["pong"]);
+/** Lines the user types at the prompt bridge, then nothing. */
+const lines = (...items: string[]) => async () => items.shift();
 
-  const sched = new RandomScheduler({
-    agents: [a as unknown as Agent],
-    maxTools: 10,
-    onAskUser: askUser,
+/** Run the agents with the prompt on and `typed` as the user's input; returns how often the legacy askUser ran. */
+async function run(kind: SchedulerKind, agents: ScriptedAgent[], kickoff: string | undefined, typed: string[]) {
+  let asked = 0;
+  const sched = scriptedScheduler(agents, {
+    kind,
     promptEnabled: true,
-    idleSleepMs: 1,
-    onStreamStart: () => {},
-    onStreamEnd: () => {},
-    // external bridge provided → scheduler must use this instead of askUser
-    readUserLine,
-    shuffle: <T>(x: T[]) => x,
-  } as any);
-
-  // Seed an initial message so the agent runs
-  await (sched as any).interject("hello");   // public API
-  const p = sched.start();
-  await new Promise(r => setTimeout(r, 30)); // let a tick happen
+    onAskUser: async () => { asked++; return ""; },
+    readUserLine: lines(...typed),
+  });
+  if (kickoff) await sched.interject(kickoff);
+  const running = sched.start();
+  await wait(40);
   sched.stop();
-  await p;
-
-  // We should have consumed exactly one line via readUserLine
-  expect(askUserCalls.length).toBe(0);
-
-  // And routed the user's "pong" back to the agent exactly once
-  const flattened = a.received.flat();
-  const got = flattened.find(m => m.role === "user" && m.content.includes("pong"));
-  expect(got?.content).toBe("pong");
-});
-
-test("explicit @@agent tag overrides default target", async () => {
-  const a = new MockAgent("alice");
-  const b = new MockAgent("bob");
-
-  const sched = new RandomScheduler({
-    agents: [a as any, b as any],
-    maxTools: 2,
-    onAskUser: async () => "",   // not used
-    promptEnabled: true,
-    idleSleepMs: 1,
-    onStreamStart: () => {},
-    onStreamEnd: () => {},
-    readUserLine: makeQueue<string>(["@@bob hi bob"]),
-    shuffle: <T>(x: T[]) => x,
-  } as any);
-
-  await (sched as any).interject("kick");
-  const p = sched.start();
-  await new Promise(r => setTimeout(r, 30));
-  sched.stop(); await p;
-
-  const toBob = b.received.flat().some(m => m.role === "user" && m.content.includes("hi bob"));
-  const toAlice = a.received.flat().some(m => m.role === "user" && m.content.includes("hi bob"));
-  expect(toBob).toBe(true);
-  expect(toAlice).toBe(false);
-});
-
-test("group fallback when no default target", async () => {
-  const a = new MockAgent("alice");
-  const b = new MockAgent("bob");
-
-  const sched = new RandomScheduler({
-    agents: [a as any, b as any],
-    maxTools: 2,
-    onAskUser: async () => "",   // not used
-    promptEnabled: true,
-    idleSleepMs: 1,
-    onStreamStart: () => {},
-    onStreamEnd: () => {},
-    // no DM tag and no prior asker → broadcast
-    readUserLine: makeQueue<string>(["hello all"]),
-    shuffle: <T>(x: T[]) => x,
-  } as any);
-
-  await (sched as any).interject("init");
-  const p = sched.start();
-  await new Promise(r => setTimeout(r, 30));
-  sched.stop(); await p;
+  await running;
+  return asked;
+}
 
-  const gotAlice = a.received.flat().some(m => m.role === "user" && m.content.includes("hello all"));
-  const gotBob   = b.received.flat().some(m => m.role === "user" && m.content.includes("hello all"));
-  expect(gotAlice && gotBob).toBe(true);
-});
+const got = (a: ScriptedAgent, text: string) => a.seen.some((m) => m.role === "user" && m.from === "User" && m.content === text);
+
+for (const kind of ["random", "fsm"] as const) {
+  describe(`asking the user (${kind} scheduler)`, () => {
+    it("sends an untagged reply to the agent that asked, read once through readUserLine", async () => {
+      const alice = new AskingAgent("alice");
+      const bob = new ScriptedAgent("bob", () => undefined);
+      const asked = await run(kind, [alice, bob], "@@alice hello", ["pong"]);
+
+      expect(asked).toBe(0);
+      expect(alice.seen.filter((m) => m.content === "pong")).toHaveLength(1);
+      expect(got(bob, "pong")).toBe(false);
+    });
+
+    it("lets an explicit @@agent tag override the asker", async () => {
+      const alice = new AskingAgent("alice");
+      const bob = new ScriptedAgent("bob", () => undefined);
+      await run(kind, [alice, bob], "@@alice kick", ["@@bob hi bob"]);
+
+      expect(got(bob, "hi bob")).toBe(true);
+      expect(alice.seen.some((m) => m.content.includes("hi bob"))).toBe(false);
+    });
+
+    it("falls back to the group when nobody is addressed and nobody asked", async () => {
+      const alice = new ScriptedAgent("alice", () => undefined);
+      const bob = new ScriptedAgent("bob", () => undefined);
+      await run(kind, [alice, bob], "hello all", []);
+
+      expect(got(alice, "hello all") && got(bob, "hello all")).toBe(true);
+    });
+
+    it("reads idle input through the bridge without printing its own prompt banner", async () => {
+      const alice = new ScriptedAgent("alice", () => undefined);
+      const info = spyMethod(Logger as unknown as { info: (...args: unknown[]) => void }, "info");
+      let asked: number;
+      try {
+        asked = await run(kind, [alice], undefined, ["hello"]);
+      } finally {
+        info.restore();
+      }
+
+      expect(asked).toBe(0);
+      expect(got(alice, "hello")).toBe(true); // the idle prompt defaults to the first agent
+      expect(info.calls.map((args) => args.map(String).join(" ")).join("\n")).not.toContain("You > (scheduler)");
+    });
+  });
+}
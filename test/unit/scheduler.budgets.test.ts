@@ -0,0 +1,107 @@
+// test/unit/scheduler.budgets.test.ts
+import { describe, it, expect } from "bun:test";
+import { BudgetTracker, describeExhausted, parseExtension, sessionLimits } from "../../src/scheduler/budgets";
+import { parseTeam } from "../../src/config/team-file";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+/** A ScriptedAgent that reports `tokens` prompt and completion tokens per turn. */
+class MeteredAgent extends ScriptedAgent {
+  private used = 0;
+  constructor(id: string, private readonly tokens: number) {
+    super(id, () => undefined);
+  }
+  async respond(...args: Parameters<ScriptedAgent["respond"]>) {
+    this.used += this.tokens;
+    return super.respond(...args);
+  }
+  getUsage() {
+    return { promptTokens: this.used, completionTokens: this.used };
+  }
+}
+
+describe("budgets", () => {
+  it("charges turns, tokens and cost and reports the first limit reached", async () => {
+    const alice = new MeteredAgent("alice", 500);
+    alice.budgets = { maxHops: 3, maxCostUsd: 0.01 };
+    alice.pricing = { promptPerMTok: 3, completionPerMTok: 15 };
+    const tracker = new BudgetTracker({ tokens: 1500 }, {});
+
+    await tracker.charge(alice, () => alice.respond([], 0, undefined as any, [], {} as any));
+    expect(tracker.check(alice)).toBeUndefined();
+    await tracker.charge(alice, () => alice.respond([], 0, undefined as any, [], {} as any));
+    expect(tracker.spent("alice")).toMatchObject({ hops: 2, tokens: 2000, cost: 0.018 });
+
+    // The agent's own cost limit comes before the session's token limit.
+    const over = tracker.check(alice)!;
+    expect(describeExhausted(over)).toBe("alice used its cost budget ($0.02 of $0.01)");
+    tracker.extend(over, parseExtension("$1", over)!);
+    expect(describeExhausted(tracker.check(alice)!, true)).toBe("The session used its token budget (2,000 tokens of 1,500 tokens)");
+
+    const restored = new BudgetTracker({ tokens: 1500 }, {});
+    restored.restore(tracker.snapshot());
+    expect(restored.check(alice)?.kind).toBe("tokens");
+
+    const time = { scope: "agent" as const, agentId: "a", kind: "time" as const, used: 60_000, limit: 60_000 };
+    expect(parseExtension("y", time)).toBe(60_000);
+    expect(parseExtension("5m", time)).toBe(300_000);
+    expect(parseExtension(" no ", time)).toBeNull();
+    expect(parseExtension("lots", time)).toBeNaN();
+
+    expect(sessionLimits({ "max-hops": "12" }, { ORG_MAX_HOPS: "40", ORG_TIMEOUT_MS: "600000", ORG_MAX_COST_USD: "2.5" }))
+      .toEqual({ hops: 12, time: 600_000, cost: 2.5 });
+    expect(parseTeam("version: 1\nagents:\n  - id: a\n    budgets: { maxHops: 5, maxCostUsd: 1.5 }\n    pricing: { promptPerMTok: 3 }", "org.yaml", ".").agents[0])
+      .toEqual({ id: "a", budgets: { maxHops: 5, maxCostUsd: 1.5 }, pricing: { promptPerMTok: 3 } });
+    expect(() => parseTeam("version: 1\nagents:\n  - id: a\n    budgets: { maxHops: 0 }", "org.yaml", "."))
+      .toThrow("org.yaml: agents[0].budgets.maxHops: expected an integer >= 1");
+  });
+
+  it("asks the user to extend an agent's budget, then parks the agent on no", async () => {
+    const alice = new ScriptedAgent("alice", () => "@@bob ping");
+    const bob = new ScriptedAgent("bob", () => "@@alice pong");
+    alice.budgets = { maxHops: 2 };
+    const answers = ["1", "n"];
+    const asked: string[] = [];
+    const sched = scriptedScheduler([alice, bob], {
+      promptEnabled: true,
+      readUserLine: async () => {
+        const a = answers.shift();
+        if (a !== undefined) asked.push(a);
+        return a;
+      },
+    });
+
+    await sched.interject("@@alice start"); // before start(), so the idle prompt can't take the answers
+    const running = sched.start();
+    for (let i = 0; i < 200 && asked.length < 2; i++) await wait(10);
+    await wait(50);
+    sched.stop();
+    await running;
+
+    const turns = alice.seen.filter((m) => m.from !== "System").length;
+    expect(turns).toBe(3);
+    const notes = alice.seen.filter((m) => m.from === "System").map((m) => m.content);
+    expect(notes).toEqual([
+      "[budget] You used your hop budget (2 hops of 2 hops). The user added 1 hop; finish the task without wasted steps.",
+    ]);
+    expect((sched as any).parked.has("alice")).toBe(true);
+    expect((sched as any).inbox.snapshot().alice.map((m: any) => m.content))
+      .toEqual(["pong", "[budget] You used your hop budget (3 hops of 3 hops). The user stopped you here."]);
+  });
+
+  it("ends a non-interactive session when the session budget runs out", async () => {
+    const alice = new ScriptedAgent("alice", () => "@@bob ping");
+    const bob = new ScriptedAgent("bob", () => "@@alice pong");
+    let stopped = 0;
+    const sched = scriptedScheduler([alice, bob], { budgets: { hops: 3 }, onBudgetStop: () => { stopped++; } });
+
+    const running = sched.start();
+    await sched.interject("@@alice start");
+    await running; // the scheduler stops itself
+
+    expect(stopped).toBe(1);
+    expect(alice.seen.length + bob.seen.length).toBe(3);
+    expect(sched.snapshot().budgets?.session.hops).toBe(3);
+  });
+});
@@ -0,0 +1,161 @@
+// test/unit/scheduler.concurrency.test.ts
+import { describe, it, expect } from "bun:test";
+import { execFileSync } from "node:child_process";
+import { mkdtempSync, readFileSync, rmSync, writeFileSync, existsSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { Logger } from "../../src/logger";
+import { OutputGate } from "../../src/ui/output-gate";
+import { Workspaces, concurrencyLevel, globsOverlap, isolationMode } from "../../src/sandbox/workspaces";
+import { parseTeam } from "../../src/config/team-file";
+import type { Agent } from "../../src/agents/agent";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+/** A ScriptedAgent whose turn takes `ms` and logs when it starts and ends. */
+class SlowAgent extends ScriptedAgent {
+  constructor(id: string, private readonly ms: number, private readonly log: string[]) {
+    super(id, () => undefined);
+  }
+  async respond(...args: Parameters<ScriptedAgent["respond"]>) {
+    this.log.push(`${this.id}:start`);
+    Logger.info(`${this.id} working`);
+    await wait(this.ms);
+    Logger.info(`${this.id} done`);
+    this.log.push(`${this.id}:end`);
+    return super.respond(...args);
+  }
+}
+
+async function runPair(paths: [string[], string[]]) {
+  const log: string[] = [];
+  const a = new SlowAgent("tester", 40, log);
+  const b = new SlowAgent("docs", 40, log);
+  [a.paths, b.paths] = paths;
+  const printed: string[] = [];
+  const prev = console.log;
+  console.log = (...x: any[]) => { printed.push(x.join(" ")); };
+  try {
+    const sched = scriptedScheduler([a, b], { concurrency: 2, workspaces: new Workspaces("paths", "/tmp", "t") });
+    await sched.interject("everyone: go");
+    const running = sched.start();
+    for (let i = 0; i < 100 && log.length < 4; i++) await wait(10);
+    sched.stop();
+    await running;
+  } finally {
+    console.log = prev;
+  }
+  return { log, printed: printed.filter((l) => / (working|done)$/.test(l)) };
+}
+
+const sh = (cwd: string, ...args: string[]) =>
+  execFileSync("git", ["-C", cwd, "-c", "user.name=t", "-c", "user.email=t@t", ...args], { encoding: "utf8" });
+
+describe("concurrent turns", () => {
+  it("runs agents with disjoint paths at once and prints each turn as one block", async () => {
+    const apart = await runPair([["test/**"], ["docs/**"]]);
+    expect(apart.log).toEqual(["tester:start", "docs:start", "tester:end", "docs:end"]);
+    expect(apart.printed).toEqual(["tester working", "tester done", "docs working", "docs done"]);
+
+    const shared = await runPair([["src/**"], ["src/lib/*.ts"]]);
+    expect(shared.log).toEqual(["tester:start", "tester:end", "docs:start", "docs:end"]);
+  });
+
+  it("tracks each overlapping turn on its own, and who started last", async () => {
+    const log: string[] = [];
+    const a = new SlowAgent("tester", 20, log);
+    const b = new SlowAgent("docs", 60, log);
+    [a.paths, b.paths] = [["test/**"], ["docs/**"]];
+    const c = new ScriptedAgent("ops", () => undefined);
+    const sched = scriptedScheduler([a, b, c], { kind: "round-robin", concurrency: 2, workspaces: new Workspaces("paths", "/tmp", "t") });
+    const s = sched as any;
+    const busy: string[] = [];
+    await sched.interject("@@tester,docs go");
+    const running = sched.start();
+    for (let i = 0; i < 100 && !log.includes("tester:end"); i++) await wait(5);
+    await wait(5);
+    busy.push([...s.activeAgents.keys()].join(","), String(sched.hasActiveAgent()), s.lastRan);
+    for (let i = 0; i < 100 && !log.includes("docs:end"); i++) await wait(5);
+    await wait(20);
+    busy.push([...s.activeAgents.keys()].join(","), String(sched.hasActiveAgent()), s.lastRan);
+    sched.stop();
+    await running;
+
+    expect(busy).toEqual(["docs", "true", "docs", "", "false", "docs"]); // tester's turn ending leaves docs' in place
+  });
+
+  it("plays back held output in start order once the owner finishes", async () => {
+    const out: string[] = [];
+    const gate = new OutputGate();
+    const uninstall = gate.install();
+    try {
+      const say = (s: string) => Logger.emit(() => out.push(s));
+      const first = gate.run("a", async () => { say("a1"); await wait(30); say("a2"); });
+      const second = gate.run("b", async () => { say("b1"); await gate.hold("b"); say("b2"); });
+      say("scheduler");
+      await Promise.all([first, second]);
+    } finally {
+      uninstall();
+    }
+    expect(out).toEqual(["a1", "scheduler", "a2", "b1", "b2"]);
+  });
+
+  it("gives each agent a worktree and merges them back at the end", () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-worktrees-"));
+    try {
+      sh(dir, "init", "-q");
+      writeFileSync(path.join(dir, "a.txt"), "one\n");
+      writeFileSync(path.join(dir, "b.txt"), "two\n");
+      sh(dir, "add", "-A");
+      sh(dir, "commit", "-qm", "init");
+      writeFileSync(path.join(dir, "b.txt"), "two, edited\n"); // uncommitted work carries into every worktree
+      writeFileSync(path.join(dir, "notes.md"), "untracked\n");
+
+      const ws = new Workspaces("worktree", dir, "run1");
+      const alice = { id: "alice" } as Agent;
+      const bob = { id: "bob" } as Agent;
+      const carol = { id: "carol" } as Agent;
+      const [da, db, dc] = [ws.dirFor(alice), ws.dirFor(bob), ws.dirFor(carol)];
+      expect(da).toBe(path.join(dir, ".org", "worktrees", "run1", "alice"));
+      expect(readFileSync(path.join(db, "b.txt"), "utf8")).toBe("two, edited\n");
+      expect(readFileSync(path.join(db, "notes.md"), "utf8")).toBe("untracked\n");
+      expect(ws.canRunBeside(alice, [bob])).toBe(true);
+
+      writeFileSync(path.join(da, "a.txt"), "one, by alice\n");
+      writeFileSync(path.join(db, "new.ts"), "export {};\n");
+      writeFileSync(path.join(dc, "a.txt"), "one, by carol\n"); // same line as alice: conflicts
+
+      const results = ws.merge();
+      expect(results.map((r) => [r.agentId, r.merged])).toEqual([["alice", true], ["bob", true], ["carol", false]]);
+      expect(readFileSync(path.join(dir, "a.txt"), "utf8")).toContain("one, by alice");
+      expect(readFileSync(path.join(dir, "new.ts"), "utf8")).toBe("export {};\n");
+      expect(existsSync(path.join(dir, ".org", "runs", "run1", "agents", "carol.patch"))).toBe(true);
+      expect(existsSync(da)).toBe(false);
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("reads its settings and checks declared paths", () => {
+    expect(globsOverlap(["src/**"], ["src/lib/a.ts"])).toBe(true);
+    expect(globsOverlap(["src/*.ts"], ["test/**", "docs/*.md"])).toBe(false);
+    expect(globsOverlap(["README.md"], ["**/*.md"])).toBe(true);
+    expect(concurrencyLevel(undefined, {})).toBe(1);
+    expect(concurrencyLevel(undefined, { ORG_CONCURRENCY: "3" })).toBe(3);
+    expect(() => concurrencyLevel("two", {})).toThrow('--concurrency: expected a whole number >= 1, got "two"');
+    expect(isolationMode(undefined, {})).toBe("worktree");
+    expect(() => isolationMode("chroot", {})).toThrow('--isolation: unknown mode "chroot" (known: worktree, paths)');
+
+    const ws = new Workspaces("paths", "/tmp", "t");
+    const docs = { id: "docs", paths: ["docs/**"] } as unknown as Agent;
+    expect(ws.refuseWrite(docs, "docs/guide.md")).toBeUndefined();
+    expect(ws.refuseWrite(docs, "src/app.ts")).toBe("##file:src/app.ts is outside your paths (docs/**); it was not written.");
+    expect(ws.canRunBeside(docs, [{ id: "x" } as Agent])).toBe(false); // undeclared paths run alone
+
+    expect(parseTeam('version: 1\nagents:\n  - id: a\n    paths: ["docs/**", "./README.md"]', "org.yaml", ".").agents[0].paths)
+      .toEqual(["docs/**", "README.md"]);
+    expect(() => parseTeam('version: 1\nagents:\n  - id: a\n    paths: ["../x"]', "org.yaml", "."))
+      .toThrow('org.yaml: agents[0].paths[0]: "../x" must be a glob inside the work dir');
+  });
+});
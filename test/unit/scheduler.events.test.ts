@@ -0,0 +1,88 @@
+// test/unit/scheduler.events.test.ts
+import { describe, it, expect } from "bun:test";
+import { mkdtempSync, readFileSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { SchedulerEvents, type SchedulerEvent } from "../../src/scheduler/events";
+import { StandardToolExecutor } from "../../src/executors/standard-tool-executor";
+import { AdvancedGuardRail } from "../../src/guardrails/advanced-guardrail";
+import { Workspaces } from "../../src/sandbox/workspaces";
+import type { ChatToolCall } from "../../src/drivers/types";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+describe("scheduler events", () => {
+  it("delivers by type and to catch-all listeners, and survives a failing one", () => {
+    const bus = new SchedulerEvents();
+    const got: string[] = [];
+    bus.on("turn-started", () => { throw new Error("boom"); });
+    const off = bus.on("turn-started", (e) => got.push(`started ${e.agentId}`));
+    bus.onAny((e) => got.push(`any ${e.type}`));
+
+    bus.emit({ type: "turn-started", agentId: "alice", messages: 1 });
+    bus.emit({ type: "turn-ended", agentId: "alice", replied: true, ms: 3 });
+    off();
+    bus.emit({ type: "turn-started", agentId: "bob", messages: 2 });
+    expect(got).toEqual(["started alice", "any turn-started", "any turn-ended", "any turn-started"]);
+  });
+
+  it("publishes queued messages, turns, guard decisions and written files", async () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-events-"));
+    try {
+      const alice = new ScriptedAgent("alice", (b) => (b.some((m) => m.from === "User") ? "##file:notes.md\nhello from alice" : undefined));
+      const bob = new ScriptedAgent("bob", (b) => (b.some((m) => m.from === "User") ? "@@group who should go?" : undefined));
+      const sched = scriptedScheduler([alice, bob], { concurrency: 2, workspaces: new Workspaces("paths", dir, "t") });
+      const events: SchedulerEvent[] = [];
+      sched.events.onAny((e) => events.push(e));
+
+      await sched.interject("hello all");
+      const running = sched.start();
+      for (let i = 0; i < 100 && events.filter((e) => e.type === "turn-ended").length < 3; i++) await wait(10);
+      sched.stop();
+      await running;
+
+      const summary = events.map((e) => {
+        switch (e.type) {
+          case "message-enqueued": return `queued ${e.message.from}->${e.to}${e.meta.direct ? " (dm)" : ""}`;
+          case "turn-started": return `start ${e.agentId}`;
+          case "turn-ended": return `end ${e.agentId}${e.replied ? "" : " (silent)"}`;
+          case "file-written": return `file ${path.relative(dir, e.path)} ${e.bytes}`;
+          case "guard-decision": return `guard ${e.agentId}`;
+          default: return e.type;
+        }
+      });
+      expect(summary.slice(0, 4)).toEqual(["queued User->alice", "queued User->bob", "start alice", "file notes.md 16"]);
+      expect(summary).toContain("guard bob");
+      expect(summary).toContain("queued bob->alice");
+      expect(summary).toContain("queued System->bob"); // the guard's nudge
+      expect(summary.filter((s) => s.startsWith("end ")).slice(0, 3)).toEqual(["end alice", "end bob", "end alice (silent)"]);
+      expect(readFileSync(path.join(dir, "notes.md"), "utf8")).toBe("hello from alice\n");
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("reports each tool call the executor runs", async () => {
+    const calls: string[] = [];
+    const guard = new AdvancedGuardRail({ agentId: "alice" });
+    guard.beginTurn({ maxToolHops: 5 });
+    const sh = { id: "c1", type: "function", function: { name: "sh", arguments: JSON.stringify({ cmd: "exit 3" }) } } as ChatToolCall;
+
+    await new StandardToolExecutor({ allowedTools: ["sh"] }).execute({
+      calls: [sh],
+      maxTools: 5,
+      abortCallback: () => false,
+      guard,
+      memory: { add: async () => {} } as any,
+      finalText: "",
+      agentId: "alice",
+      cwd: tmpdir(),
+      observer: {
+        started: (c) => calls.push(`started ${c.callId} ${c.tool} ${c.args}`),
+        finished: (c) => calls.push(`finished ${c.callId} ${c.tool} ok=${c.ok} exit=${c.exitCode}`),
+      },
+    });
+    expect(calls).toEqual(['started c1 sh {"cmd":"exit 3"}', "finished c1 sh ok=false exit=3"]);
+  });
+});
@@ -0,0 +1,181 @@
+// test/unit/scheduler.fsm.test.ts
+import { describe, it, expect } from "bun:test";
+import { mkdtempSync, readFileSync, rmSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import {
+  FSMScheduler, SCHEDULER_STATES, TRANSITIONS, isLegalTransition, traceTransitions, type SchedulerTransition,
+} from "../../src/scheduler/fsm-scheduler";
+import type { ChatMessage } from "../../src/types";
+import { ScriptedAgent, scriptedScheduler, type Script } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+/** A ScriptedAgent that hands the turn to the user after replying, as an LlmAgent does when it asks @@user. */
+class AskingAgent extends ScriptedAgent {
+  constructor(id: string, script: Script) {
+    super(id, script);
+  }
+  async respond(...args: Parameters<ScriptedAgent["respond"]>) {
+    const out = await super.respond(...args);
+    if (out.length > 0) await args[4].onRouteCompleted(out[0].message, 0, true);
+    return out;
+  }
+}
+
+const userSaid = (a: ScriptedAgent, text: string) => a.seen.some((m: ChatMessage) => m.role === "user" && m.content === text);
+
+/**
+ * Run `agents` on the fsm with `kickoff`, answering prompts from `lines`; once they
+ * run out the scheduler is stopped. Returns the transitions as "From>To[:agent]".
+ */
+async function trace(agents: ScriptedAgent[], kickoff: string, lines: string[], opts: Record<string, unknown> = {}) {
+  const queue = [...lines];
+  let askUser = 0;
+  const sched = scriptedScheduler(agents, {
+    kind: "fsm",
+    promptEnabled: true,
+    onAskUser: async () => { askUser++; return ""; },
+    readUserLine: async () => {
+      if (queue.length === 0) sched.stop();
+      return queue.shift() ?? "";
+    },
+    ...opts,
+  }) as FSMScheduler;
+  const seen: SchedulerTransition[] = [];
+  sched.events.on("state-changed", (e) => seen.push(e.transition));
+  await sched.interject(kickoff);
+  const running = sched.start();
+  for (let i = 0; i < 200 && sched.state !== "Stopped"; i++) await wait(5);
+  sched.stop();
+  await running;
+
+  seen.forEach((t, i) => {
+    expect(t.seq).toBe(i + 1);
+    expect(isLegalTransition(t.from, t.to)).toBe(true);
+  });
+  return { steps: seen.map((t) => `${t.from}>${t.to}${t.agentId ? `:${t.agentId}` : ""}`), seen, askUser };
+}
+
+describe("fsm scheduler", () => {
+  it("only allows the transitions in its table", () => {
+    expect(Object.keys(TRANSITIONS)).toEqual([...SCHEDULER_STATES]);
+    for (const from of SCHEDULER_STATES) {
+      for (const to of TRANSITIONS[from]) expect(SCHEDULER_STATES).toContain(to);
+      expect(TRANSITIONS[from]).not.toContain(from);
+    }
+    expect(TRANSITIONS.Stopped).toEqual(["Idle"]);
+    expect(isLegalTransition("Idle", "RunAgent")).toBe(false);
+    expect(isLegalTransition("Draining", "SelectAgent")).toBe(false);
+    expect(isLegalTransition("AwaitUser", "RunAgent")).toBe(true);
+  });
+
+  it("waits on the user inside the asking agent's turn and DMs the reply back to it", async () => {
+    const alice = new AskingAgent("alice", (b) => (b.some((m) => m.content === "hello") ? "@@group please reply: ping" : undefined));
+    const { steps, askUser } = await trace([alice], "hello", ["pong"]);
+
+    expect(steps).toEqual([
+      "Stopped>Idle",
+      "Idle>SelectAgent",
+      "SelectAgent>RunAgent:alice",
+      "RunAgent>AwaitUser:alice",
+      "AwaitUser>RunAgent",
+      "RunAgent>SelectAgent:alice",
+      "SelectAgent>Idle", // rescheduled: the user's reply is queued
+      "Idle>SelectAgent",
+      "SelectAgent>RunAgent:alice",
+      "RunAgent>SelectAgent:alice",
+      "SelectAgent>Idle", // alice had nothing to say
+      "Idle>AwaitUser",
+      "AwaitUser>Stopped",
+    ]);
+    expect(userSaid(alice, "pong")).toBe(true);
+    expect(askUser).toBe(0); // the readUserLine bridge answers every prompt
+  });
+
+  it("lets an explicit @@agent tag override the asker", async () => {
+    const alice = new AskingAgent("alice", (b) => (b.some((m) => m.content === "kick") ? "@@group who should go?" : undefined));
+    const bob = new ScriptedAgent("bob", () => undefined);
+    const { steps } = await trace([alice, bob], "@@alice kick", ["@@bob hi bob"]);
+
+    // (alice comes back afterwards for the guard's low-signal nudge)
+    expect(steps.filter((s) => s.startsWith("SelectAgent>RunAgent")).slice(0, 2)).toEqual(["SelectAgent>RunAgent:alice", "SelectAgent>RunAgent:bob"]);
+    expect(userSaid(bob, "hi bob")).toBe(true);
+    expect(userSaid(alice, "hi bob")).toBe(false);
+  });
+
+  it("sends untagged user text to the whole group when nobody was addressed", async () => {
+    const alice = new ScriptedAgent("alice", () => undefined);
+    const bob = new ScriptedAgent("bob", () => undefined);
+    const { steps } = await trace([alice, bob], "hello all", []);
+
+    expect(steps).toEqual([
+      "Stopped>Idle",
+      "Idle>SelectAgent",
+      "SelectAgent>RunAgent:alice",
+      "RunAgent>SelectAgent:alice",
+      "SelectAgent>RunAgent:bob",
+      "RunAgent>SelectAgent:bob",
+      "SelectAgent>Idle",
+      "Idle>AwaitUser",
+      "AwaitUser>Stopped",
+    ]);
+    expect(userSaid(alice, "hello all") && userSaid(bob, "hello all")).toBe(true);
+  });
+
+  it("stops from SelectAgent when the session budget runs out and nobody can extend it", async () => {
+    const alice = new ScriptedAgent("alice", () => "@@bob please run the tests");
+    const bob = new ScriptedAgent("bob", () => "@@alice all green");
+    const { steps } = await trace([alice, bob], "@@alice go", [], { promptEnabled: false, budgets: { hops: 1 } });
+
+    // alice's turn spends the only hop; bob's is refused before it starts, which ends the session.
+    expect(steps).toEqual([
+      "Stopped>Idle",
+      "Idle>SelectAgent",
+      "SelectAgent>RunAgent:alice",
+      "RunAgent>SelectAgent:alice",
+      "SelectAgent>Idle",
+      "Idle>SelectAgent",
+      "SelectAgent>Stopped",
+    ]);
+    expect(bob.seen).toEqual([]);
+  });
+
+  it("parks in Draining until draining is cancelled", async () => {
+    const sched = scriptedScheduler([new ScriptedAgent("alice", () => undefined)], { kind: "fsm" }) as FSMScheduler;
+    const seen: string[] = [];
+    sched.events.on("state-changed", ({ transition: t }) => seen.push(`${t.from}>${t.to}`));
+    const running = sched.start();
+    await wait(10);
+    expect(await sched.drain()).toBe(true);
+    await wait(20);
+    expect(sched.state).toBe("Draining");
+    sched.stopDraining();
+    await wait(20);
+    sched.stop();
+    await running;
+    expect(seen).toEqual(["Stopped>Idle", "Idle>Draining", "Draining>Idle", "Idle>Stopped"]);
+  });
+
+  it("writes each transition to the trace file", async () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-fsm-trace-"));
+    try {
+      const file = path.join(dir, "runs", "r1", "scheduler-trace.jsonl");
+      const sched = scriptedScheduler([new ScriptedAgent("alice", () => undefined)], { kind: "fsm" }) as FSMScheduler;
+      const seen: SchedulerTransition[] = [];
+      sched.events.on("state-changed", (e) => seen.push(e.transition));
+      traceTransitions(sched, file);
+      await sched.interject("@@alice hi");
+      const running = sched.start();
+      await wait(20);
+      sched.stop();
+      await running;
+
+      const lines = readFileSync(file, "utf8").trim().split("\n").map((l) => JSON.parse(l));
+      expect(lines).toEqual(JSON.parse(JSON.stringify(seen)));
+      expect(lines[2]).toMatchObject({ seq: 3, from: "SelectAgent", to: "RunAgent", agentId: "alice", reason: "1 message" });
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+});
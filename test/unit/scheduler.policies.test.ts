@@ -0,0 +1,74 @@
+// test/unit/scheduler.policies.test.ts
+import { describe, it, expect } from "bun:test";
+import { Inbox } from "../../src/scheduler/inbox";
+import { AGE_STEP_S, PriorityScheduler, priorityScore, roleWeights } from "../../src/scheduler/priority-scheduler";
+import { RoundRobinScheduler } from "../../src/scheduler/round-robin-scheduler";
+import { schedulerKind } from "../../src/scheduler/create-scheduler";
+import { parseTeam } from "../../src/config/team-file";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+/** Three agents; `first` DMs `to` on its first turn. Returns the order turns were taken in. */
+async function turnOrder(kind: "random" | "round-robin" | "priority", ids: string[], first: string, to: string, roles: Record<string, string> = {}) {
+  const turns: string[] = [];
+  const agents = ids.map((id) => {
+    const a = new ScriptedAgent(id, () => {
+      turns.push(id);
+      return id === first && turns.length === 1 ? `@@${to} please check this` : undefined;
+    });
+    a.role = roles[id];
+    return a;
+  });
+  const sched = scriptedScheduler(agents, { kind });
+  await sched.interject("everyone: read the task"); // no tag, no default target: a broadcast
+  const running = sched.start();
+  for (let i = 0; i < 200 && turns.length < ids.length; i++) await wait(5);
+  await wait(30);
+  sched.stop();
+  await running;
+  return { turns, sched };
+}
+
+describe("scheduler policies", () => {
+  it("random lets a DM jump the queue; round-robin keeps team order", async () => {
+    // c takes b's slot, and answers the broadcast and a's DM as two threads (two turns).
+    expect((await turnOrder("random", ["a", "b", "c"], "a", "c")).turns).toEqual(["a", "c", "c", "b"]);
+    const rr = await turnOrder("round-robin", ["a", "b", "c"], "a", "c");
+    expect(rr.sched).toBeInstanceOf(RoundRobinScheduler);
+    expect(rr.turns).toEqual(["a", "b", "c", "c"]); // c answers the broadcast, then a's DM
+  });
+
+  it("priority goes by role weight, then DM urgency", async () => {
+    const { turns, sched } = await turnOrder("priority", ["dev", "lead", "rev"], "lead", "rev", { lead: "lead" });
+    expect(sched).toBeInstanceOf(PriorityScheduler);
+    expect(turns).toEqual(["lead", "rev", "dev", "rev"]); // rev's second turn is lead's DM thread
+  });
+
+  it("scores waiting time and DMs, and reads its settings", () => {
+    const inbox = new Inbox();
+    inbox.push("a", { role: "user", from: "User", content: "all" });
+    inbox.push("a", { role: "user", from: "User", content: "you" }, { direct: true });
+    inbox.push("a", { role: "user", from: "bob", content: "hi" }, { direct: true });
+    const p = inbox.pending("a");
+    expect(p).toMatchObject({ count: 3, userDMs: 1, agentDMs: 1 });
+    expect(inbox.nextPromptFor("a").length).toBe(3);
+    expect(inbox.pending("a")).toEqual({ count: 0, userDMs: 0, agentDMs: 0 });
+
+    const now = 1_000_000;
+    const fresh = { count: 1, oldestAt: now, userDMs: 0, agentDMs: 0 };
+    const stale = { ...fresh, oldestAt: now - 3 * AGE_STEP_S * 1000 };
+    expect(priorityScore(3, fresh, now)).toBe(3);
+    expect(priorityScore(1, stale, now)).toBe(4); // waiting long enough outranks a fresh lead
+    expect(priorityScore(1, { ...fresh, userDMs: 1 }, now)).toBe(6);
+    expect(priorityScore(3, { ...fresh, count: 0 }, now)).toBe(0);
+
+    expect(roleWeights({ ORG_ROLE_WEIGHTS: "Reviewer=0.5, lead=4, bogus" })).toMatchObject({ reviewer: 0.5, lead: 4, planner: 2 });
+    expect(schedulerKind(undefined, { ORG_SCHEDULER: "Priority" })).toBe("priority");
+    expect(schedulerKind("round-robin", { ORG_SCHEDULER: "fsm" })).toBe("round-robin");
+    expect(() => schedulerKind("fifo", {})).toThrow('unknown scheduler "fifo" (known: random, round-robin, priority, fsm)');
+    expect(parseTeam("version: 1\nagents:\n  - id: a\n    role: reviewer", "org.yaml", ".").agents[0]).toEqual({ id: "a", role: "reviewer" });
+    expect(() => parseTeam("version: 1\nagents:\n  - id: a\n    role: code reviewer", "org.yaml", "."))
+      .toThrow('org.yaml: agents[0].role: "code reviewer" is not a valid role (a word, e.g. "reviewer")');
+  });
+});
@@ -0,0 +1,60 @@
+// test/unit/scheduler.route-speaker.test.ts
+import { expect, test } from "bun:test";
+import { RandomScheduler } from "../../src/scheduler/random-scheduler";
+import { Agent, type AgentCallbacks, type AgentReply } from "../../src/agents/agent";
+import type { NoiseFilters } from "../../src/scheduler/filters";
+import type { ChatMessage } from "../../src/types";
+
+/** Says `line` on its first turn, routed like a real agent, and records what it is sent. */
+class OneLineAgent extends Agent {
+  readonly received: ChatMessage[] = [];
+  constructor(id: string, private line?: string) {
+    super(id);
+  }
+  async respond(messages: ChatMessage[], _maxTools: number, filters: NoiseFilters, _peers: Agent[], cb: AgentCallbacks): Promise<AgentReply[]> {
+    this.received.push(...messages);
+    const line = this.line;
+    this.line = undefined;
+    if (!line) return [];
+    await cb.onRoute(line, filters);
+    return [{ message: line, toolsUsed: 0 }];
+  }
+  async load() {}
+  async save() {}
+}
+
+async function run(agents: Agent[]) {
+  const sched = new RandomScheduler({
+    agents,
+    maxTools: 1,
+    onAskUser: async () => undefined,
+    projectDir: "/tmp",
+    reviewMode: "never",
+    promptEnabled: false,
+    idleSleepMs: 1,
+    shuffle: <T>(x: T[]) => x,
+    onStreamStart: () => {},
+    onStreamEnd: async () => {},
+  } as any);
+  await sched.interject("@@alice go");
+  const p = sched.start();
+  await new Promise((r) => setTimeout(r, 40));
+  sched.stop();
+  await p;
+}
+
+test("a DM carries the speaking agent's id", async () => {
+  const alice = new OneLineAgent("alice", "@@bob can you check the build?");
+  const bob = new OneLineAgent("bob");
+  await run([alice, bob]);
+
+  expect(bob.received.map((m) => `${m.from}: ${m.content}`)).toEqual(["alice: can you check the build?"]);
+});
+
+test("@@group reaches everyone but the speaker", async () => {
+  const [alice, bob, carol] = [new OneLineAgent("alice", "@@group build is green"), new OneLineAgent("bob"), new OneLineAgent("carol")];
+  await run([alice, bob, carol]);
+
+  const heard = (a: OneLineAgent) => a.received.find((m) => m.content.includes("build is green"))?.from ?? "-";
+  expect([heard(alice), heard(bob), heard(carol)]).toEqual(["-", "alice", "alice"]);
+});
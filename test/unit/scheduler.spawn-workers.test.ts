@@ -0,0 +1,69 @@
+// test/unit/scheduler.spawn-workers.test.ts
+import { describe, it, expect } from "bun:test";
+import { extractSpawns, parseWorkerCommand } from "../../src/scheduler/workers";
+import type { Agent } from "../../src/agents/agent";
+import { parseTeam } from "../../src/config/team-file";
+import { ScriptedAgent, scriptedScheduler, scriptedSpawner } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+describe("runtime worker agents", () => {
+  it("parses ##spawn: lines and /spawn, /retire commands", () => {
+    expect(extractSpawns("On it.\n##spawn:tester run the unit tests\n  ##spawn:docs update the README\n@@bob ping")).toEqual({
+      text: "On it.\n@@bob ping",
+      requests: [{ template: "tester", task: "run the unit tests" }, { template: "docs", task: "update the README" }],
+    });
+    expect(extractSpawns("see ##spawn:tester inline")).toEqual({ text: "see ##spawn:tester inline", requests: [] });
+
+    expect(parseWorkerCommand("/spawn tester check the build")).toEqual({ kind: "spawn", request: { template: "tester", task: "check the build" } });
+    expect(parseWorkerCommand("/retire @@tester-1")).toEqual({ kind: "retire", id: "tester-1" });
+    expect(parseWorkerCommand("/spawn tester")).toEqual({ kind: "usage", message: "usage: /spawn <template> <task>" });
+    expect(parseWorkerCommand("/spawner is a word")).toBeNull();
+
+    const team = parseTeam("version: 1\nagents:\n  - id: lead\ntemplates:\n  - id: tester\n    tools: [sh]", "org.yaml", ".");
+    expect(team.templates).toEqual([{ id: "tester", tools: ["sh"] }]);
+    expect(() => parseTeam("version: 1\nagents:\n  - id: lead\ntemplates:\n  - id: Lead", "org.yaml", "."))
+      .toThrow('org.yaml: templates[0].id: duplicate agent or template id "Lead"');
+  });
+
+  it("a lead fans out a worker that reports back and is retired", async () => {
+    const { spawner, created } = scriptedSpawner([{ id: "tester" }], () => "@@lead 12 tests pass");
+    const lead = new ScriptedAgent("lead", (batch) =>
+      batch.some((m) => m.from === "User") ? "##spawn:tester run the unit tests" : undefined);
+    const sched = scriptedScheduler([lead], { spawner });
+
+    const running = sched.start();
+    await sched.interject("@@lead ship it");
+    for (let i = 0; i < 100 && !lead.seen.some((m) => m.from === "tester-1"); i++) await wait(10);
+    sched.stop();
+    await running;
+
+    expect(created).toEqual([["tester-1"]]);
+    expect(lead.seen.find((m) => m.from === "tester-1")?.content).toBe("12 tests pass");
+    expect((sched as any).agents.map((a: Agent) => a.id)).toEqual(["lead"]);
+  });
+
+  it("the user spawns from a team agent and retires by hand", async () => {
+    const replies: string[] = [];
+    const { spawner } = scriptedSpawner([{ id: "coder" }], (batch) => {
+      replies.push(...batch.map((m) => `${m.role}:${m.from}:${m.content}`));
+      return undefined; // still working
+    });
+    const coder = new ScriptedAgent("coder", () => undefined);
+    const sched = scriptedScheduler([coder], { spawner });
+
+    const running = sched.start();
+    await sched.interject("/spawn coder add a --version flag");
+    for (let i = 0; i < 100 && replies.length < 2; i++) await wait(10);
+    const live = (sched as any).agents.map((a: Agent) => a.id);
+    await sched.interject("/retire coder-1");
+    await wait(50);
+    sched.stop();
+    await running;
+
+    expect(live).toEqual(["coder", "coder-1"]);
+    expect(replies[0]).toContain("system:System:You are coder-1, spawned by user");
+    expect(replies[1]).toBe("user:User:add a --version flag");
+    expect((sched as any).agents.map((a: Agent) => a.id)).toEqual(["coder"]);
+  });
+});
@@ -0,0 +1,101 @@
+// test/unit/session.resume.test.ts
+import { describe, it, expect, beforeEach, afterEach } from "bun:test";
+import { mkdtempSync, readFileSync, rmSync, writeFileSync, mkdirSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { SessionStore, latestSessionRunId } from "../../src/runtime/session-state";
+import { MemoryPersisitence } from "../../src/memory/memory-persistence";
+import type { SchedulerSnapshot } from "../../src/scheduler/types";
+import { ScriptedAgent, scriptedScheduler, scriptedSpawner } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+const team = { source: "org.yaml", definitions: [{ id: "lead" }, { id: "bob" }], templates: [{ id: "tester" }] };
+
+describe("resumable sessions", () => {
+  let cwd: string;
+  let tmp: string;
+  beforeEach(() => {
+    cwd = process.cwd();
+    tmp = mkdtempSync(path.join(tmpdir(), "org-resume-"));
+    process.chdir(tmp); // .org/runs and .orgmemories live under cwd
+  });
+  afterEach(() => {
+    process.chdir(cwd);
+    rmSync(tmp, { recursive: true, force: true });
+  });
+
+  it("saves the session with agent memories and restores both", () => {
+    const memory = new MemoryPersisitence().pathFor("lead");
+    mkdirSync(path.dirname(memory), { recursive: true });
+    writeFileSync(memory, '{"messagesBuffer":["an hour of work"]}');
+
+    const snapshot: SchedulerSnapshot = {
+      inbox: { bob: [{ role: "user", from: "lead", content: "please review" }] },
+      mutedMs: { lead: 2000 },
+      lastUserDMTarget: "lead",
+      respondingAgent: null,
+      interjection: null,
+      workers: [],
+    };
+    const session = SessionStore.create("run-1", team, { recipe: "fix" });
+    session.checkpoint(snapshot);
+    session.setReview(["/work/.org/runs/x/session.patch"]);
+
+    writeFileSync(memory, "{}"); // a later run reused the id
+    expect(latestSessionRunId()).toBe("run-1");
+    const loaded = SessionStore.load("run-1");
+    loaded.restoreMemories();
+
+    expect(readFileSync(memory, "utf8")).toContain("an hour of work");
+    expect(loaded.team).toEqual(team);
+    expect(loaded.args).toEqual({ recipe: "fix" });
+    expect(loaded.scheduler).toEqual(snapshot);
+    expect(loaded.review).toEqual({ patches: ["/work/.org/runs/x/session.patch"] });
+
+    expect(() => SessionStore.load("nope")).toThrow('[resume] no saved session for run "nope"');
+    expect(() => SessionStore.load("../../etc")).toThrow('invalid run id "../../etc"');
+    expect(() => SessionStore.load("..")).toThrow('invalid run id ".."');
+    writeFileSync(path.join(loaded.dir, "session.json"), '{"version":9}');
+    expect(() => SessionStore.load("run-1")).toThrow("unsupported session version 9 (expected 1)");
+  });
+
+  it("a restored scheduler picks up queued messages and live workers", async () => {
+    // First run: the lead hands work to bob and a tester worker, then the process dies.
+    const snapshots: SchedulerSnapshot[] = [];
+    const first = scriptedSpawner(team.templates, () => undefined);
+    const lead = new ScriptedAgent("lead", (batch) =>
+      batch.some((m) => m.from === "User") ? "##spawn:tester run the unit tests\n@@bob please review" : undefined);
+    const before = scriptedScheduler([lead, new ScriptedAgent("bob", () => undefined)], {
+      spawner: first.spawner,
+      onCheckpoint: (s) => { snapshots.push(s); },
+    });
+    (before as any).inbox.push("lead", { role: "user", from: "User", content: "ship it" });
+    (before as any).mute("bob", 60_000); // bob is muted, so his DM stays queued
+    const running = before.start();
+    for (let i = 0; i < 100 && snapshots.length === 0; i++) await wait(10);
+    before.stop();
+    await running;
+
+    const saved = snapshots[0];
+    expect(saved.workers).toEqual([{ id: "tester-1", template: "tester", parent: "lead" }]);
+    expect(saved.inbox.bob).toEqual([{ role: "user", from: "lead", content: "please review" }]);
+    expect(saved.mutedMs.bob).toBeGreaterThan(50_000);
+
+    // Second run: same team, fresh objects.
+    const second = scriptedSpawner(team.templates, () => undefined);
+    const bob = new ScriptedAgent("bob", () => undefined);
+    const after = scriptedScheduler([new ScriptedAgent("lead", () => undefined), bob], { spawner: second.spawner });
+    await after.restore({ ...saved, mutedMs: {} });
+    const resumed = after.start();
+    for (let i = 0; i < 100 && (!bob.seen.length || !second.agents[0]?.seen.length); i++) await wait(10);
+    after.stop();
+    await resumed;
+
+    expect(second.created).toEqual([["tester-1"]]);
+    expect(bob.seen).toEqual([{ role: "user", from: "lead", content: "please review" }]);
+    const [note, task] = second.agents[0].seen.map((m) => String(m.content));
+    expect(note).toContain("spawned by lead");
+    expect(task).toBe("run the unit tests");
+    expect(after.snapshot().workers).toEqual(saved.workers);
+  });
+});
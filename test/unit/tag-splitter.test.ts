@@ -239,4 +239,19 @@ describe("TagSplitter (token-driven)", () => {
       index: 0,
     });
   });
+
+  it("reads recipient lists, role tags and cc: into one agents part", () => {
+    const opts: Opts = { ...baseOpts, agentTokens: ["alice", "bob", "carol"], roleTokens: { reviewers: ["bob", "carol"] } };
+    expect(TagSplitter.split("@@alice,Bob look", opts)[0]).toEqual({ kind: "agents", tag: "alice,Bob", to: ["alice", "Bob"], cc: [], content: "look", index: 0 });
+    expect(TagSplitter.split("@@reviewers look", opts)[0]).toMatchObject({ kind: "agents", to: ["bob", "carol"], cc: [] });
+    expect(TagSplitter.split("@@alice,cc:reviewers look", opts)[0]).toMatchObject({ to: ["alice"], cc: ["bob", "carol"] });
+    expect(TagSplitter.split("@@cc:bob,carol fyi", opts)[0]).toMatchObject({ to: ["carol"], cc: ["bob"] });
+    expect(TagSplitter.split("@@bob,cc:reviewers look", opts)[0]).toMatchObject({ to: ["bob"], cc: ["carol"] }); // to wins over cc
+  });
+
+  it("ends a recipient list at the first item it doesn't know", () => {
+    expect(TagSplitter.split("@@alice, hi", baseOpts)[0]).toEqual({ kind: "agent", tag: "alice", content: ", hi", index: 0 });
+    expect(TagSplitter.split("@@alice,bob,zed hi", baseOpts)[0]).toMatchObject({ kind: "agents", to: ["alice", "bob"], content: ",zed hi" });
+    expect(TagSplitter.split("@@cc:group hi", baseOpts)[0]).toMatchObject({ kind: "group", content: "@@cc:group hi" });
+  });
 });
@@ -0,0 +1,73 @@
+// test/unit/toolcall-parsers.conformance.test.ts
+import { describe, it, expect } from "bun:test";
+import { extractToolCalls, getToolCallParser, toolCallParserNames } from "../../src/drivers/toolcall-parser-registry";
+import { makeStreamingGoogleLmStudio } from "../../src/drivers/streaming-google-lmstudio";
+import { makeStreamingDeepseekOllama } from "../../src/drivers/streaming-deepseek-ollama";
+import { makeStreamingDeepseekNoToolsOllama } from "../../src/drivers/streaming-deepseek-no-toools-ollama";
+import { fixture, startStubLlmServer } from "../helpers/stub-llm-server";
+
+type Case = { name: string; input: string; text?: string; expect: Array<{ name: string; arguments: unknown }> };
+type Corpus = { shared: Case[]; parsers: Record<string, Case[]> };
+
+const corpus = JSON.parse(fixture("toolcalls/corpus.json")) as Corpus;
+
+describe("text tool-call parser conformance", () => {
+  it("has corpus coverage for every registered parser", () => {
+    for (const name of toolCallParserNames()) {
+      expect(corpus.parsers[name]?.length ?? 0).toBeGreaterThan(0);
+    }
+  });
+
+  for (const parserName of toolCallParserNames()) {
+    describe(parserName, () => {
+      for (const c of [...corpus.shared, ...(corpus.parsers[parserName] ?? [])]) {
+        it(c.name, () => {
+          const calls = getToolCallParser(parserName).parseAll(c.input);
+
+          // Contract shared by every parser: well-formed calls, JSON-string arguments.
+          for (const call of calls) {
+            expect(call.type).toBe("function");
+            expect(typeof call.id).toBe("string");
+            expect(call.id.length).toBeGreaterThan(0);
+            expect(() => JSON.parse(call.function.arguments)).not.toThrow();
+          }
+
+          expect(calls.map((k) => ({ name: k.function.name, arguments: JSON.parse(k.function.arguments) })))
+            .toEqual(c.expect);
+
+          // Drivers hand on the reply without the markup of the calls they parsed.
+          const extracted = extractToolCalls(getToolCallParser(parserName), c.input);
+          expect(extracted.toolCalls.length).toBe(calls.length);
+          if (c.text !== undefined) expect(extracted.text).toBe(c.text);
+        });
+      }
+    });
+  }
+
+  it("leaves the parsed calls out of the reply text in every driver that parses them", async () => {
+    const sse = (text: string) => [text.slice(0, 12), text.slice(12)]
+      .map((content) => `data: ${JSON.stringify({ choices: [{ index: 0, delta: { content } }] })}\n\n`).join("") + "data: [DONE]\n\n";
+    const gemma = corpus.parsers.gemma[0];
+    const deepseek = corpus.parsers.deepseek[0];
+    const drivers = [
+      [makeStreamingGoogleLmStudio, gemma],
+      [makeStreamingDeepseekOllama, deepseek],
+      [makeStreamingDeepseekNoToolsOllama, deepseek],
+    ] as const;
+
+    for (const [make, c] of drivers) {
+      const stub = await startStubLlmServer([{ body: sse(c.input) }]);
+      try {
+        const out = await make({ baseUrl: stub.baseUrl, model: "m" }).chat([{ role: "user", from: "User", content: "go" }]);
+        expect(out.text).toBe(c.text!);
+        expect(out.toolCalls.map((k) => ({ name: k.function.name, arguments: JSON.parse(k.function.arguments) }))).toEqual(c.expect);
+      } finally {
+        await stub.close();
+      }
+    }
+  });
+
+  it("rejects unknown parser names", () => {
+    expect(() => getToolCallParser("nope")).toThrow(/unknown tool-call parser "nope"/);
+  });
+});
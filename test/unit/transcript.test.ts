@@ -0,0 +1,126 @@
+// test/unit/transcript.test.ts
+import { describe, it, expect } from "bun:test";
+import { mkdtempSync, readFileSync, rmSync, mkdirSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import path from "node:path";
+import { readTranscript, recordTranscript, sha256, type TranscriptEntry } from "../../src/runtime/transcript";
+import { renderTranscript, transcriptCommand } from "../../src/cli/transcript";
+import { SchedulerEvents } from "../../src/scheduler/events";
+import { ScriptedAgent, scriptedScheduler } from "../_helpers/scripted-agent";
+
+const wait = (ms: number) => new Promise((r) => setTimeout(r, ms));
+const at = (s: number) => new Date(Date.UTC(2026, 0, 2, 10, 0, s)).toISOString();
+
+describe("run transcript", () => {
+  it("records every delivery with its id, thread, hash and guard decision", async () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-transcript-"));
+    try {
+      const file = path.join(dir, "runs", "r1", "transcript.jsonl");
+      const alice = new ScriptedAgent("alice", (b) => (b.some((m) => m.from === "User") ? "@@bob,cc:carol please check the build" : undefined));
+      const bob = new ScriptedAgent("bob", (b) => (b.some((m) => m.from === "alice") ? "@@group who should go?" : undefined));
+      const carol = new ScriptedAgent("carol", () => undefined);
+      const sched = scriptedScheduler([alice, bob, carol]);
+      recordTranscript(sched.events, file);
+
+      await sched.interject("@@alice start");
+      const running = sched.start();
+      for (let i = 0; i < 100 && readTranscript(file).filter((e) => e.type === "message" && e.guard).length < 2; i++) await wait(5);
+      sched.stop();
+      await running;
+
+      const msgs = readTranscript(file).filter((e): e is TranscriptEntry & { type: "message" } => e.type === "message");
+      expect(msgs.slice(0, 6).map((e) => `${e.id} ${e.from}->${e.to} ${e.kind} ${e.threadId ?? "-"}${e.guard ? " guarded" : ""}`)).toEqual([
+        "m1 User->alice dm dm:alice+user",
+        "m2 alice->bob dm dm:alice+bob+carol",
+        "m3 alice->carol cc dm:alice+bob+carol",
+        "m4 System->bob system -", // the guard's nudge goes out before the broadcast it judged
+        "m5 bob->alice group group guarded",
+        "m6 bob->carol group group guarded",
+      ]);
+      expect(msgs[1].sha256).toBe(sha256("please check the build"));
+      expect(msgs[4].guard?.nudge).toBeTruthy();
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("records @@user messages, files, tool calls and suppressed broadcasts from the event bus", () => {
+    const dir = mkdtempSync(path.join(tmpdir(), "org-transcript-"));
+    try {
+      const file = path.join(dir, "transcript.jsonl");
+      const bus = new SchedulerEvents();
+      const off = recordTranscript(bus, file);
+      bus.emit({ type: "tool-started", agentId: "alice", callId: "c1", tool: "sh", args: '{"cmd":"ls"}' });
+      bus.emit({ type: "tool-finished", agentId: "alice", callId: "c1", tool: "sh", ok: true, exitCode: 0, ms: 4 });
+      bus.emit({ type: "file-written", agentId: "alice", path: "notes.md", bytes: 6 });
+      bus.emit({ type: "guard-decision", agentId: "bob", decision: { nudge: "say more" } });
+      bus.emit({ type: "guard-decision", agentId: "bob", decision: { suppressBroadcast: true } });
+      bus.emit({ type: "user-addressed", agentId: "alice", content: "done" });
+      off();
+      bus.emit({ type: "user-addressed", agentId: "alice", content: "not recorded" });
+
+      expect(readTranscript(file).map(({ at: _at, ...e }) => e)).toEqual([
+        { type: "tool", from: "alice", callId: "c1", tool: "sh", args: '{"cmd":"ls"}', ok: true, exitCode: 0, ms: 4 },
+        { type: "file", from: "alice", path: "notes.md", bytes: 6 },
+        { type: "suppressed", from: "bob", guard: { suppressBroadcast: true } },
+        { type: "message", from: "alice", to: "user", kind: "user", sha256: sha256("done"), content: "done" },
+      ]);
+    } finally {
+      rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("renders lanes, one row per message, and folds runs of tool calls", () => {
+    const msg = (s: number, from: string, to: string, kind: "dm" | "group" | "cc" | "system" | "user", content: string, threadId?: string): TranscriptEntry =>
+      ({ type: "message", at: at(s), from, to, kind, threadId, sha256: sha256(content), content });
+    const entries: TranscriptEntry[] = [
+      msg(1, "User", "alice", "dm", "start"),
+      msg(2, "alice", "bob", "dm", "check a|b", "dm:alice+bob+carol"),
+      msg(2, "alice", "carol", "cc", "[cc, to bob; no reply needed] check a|b", "dm:alice+bob+carol"),
+      { type: "tool", at: at(3), from: "bob", callId: "c1", tool: "sh", args: '{"cmd":"bun test"}', ok: false, exitCode: 1, ms: 900 },
+      { type: "tool", at: at(4), from: "bob", callId: "c2", tool: "sh", args: '{"cmd":"git diff"}', ok: true, exitCode: 0, ms: 20 },
+      { type: "message", at: at(5), from: "bob", to: "alice", kind: "group", sha256: "", content: "<b>red</b>", threadId: "group", guard: { nudge: "x" } },
+      { type: "suppressed", at: at(6), from: "bob", guard: { suppressBroadcast: true } },
+      msg(7, "alice", "user", "user", "all green\nshipping"),
+    ];
+
+    const md = renderTranscript(entries, "md", "r1").split("\n");
+    expect(md.slice(0, 4)).toEqual(["# Transcript r1", "", "| time | User | alice | bob |", "| --- | --- | --- | --- |"]);
+    expect(md.slice(4)).toEqual([
+      "| 10:00:01 | <b>→ alice</b><br>start |  |  |",
+      "| 10:00:02 |  | <b>→ bob · cc carol</b><br>check a\\|b |  |",
+      '| 10:00:03 |  |  | <details><summary>2 tool calls (1 failed)</summary>sh {&quot;cmd&quot;:&quot;bun test&quot;} → exit 1, 900 ms<br>sh {&quot;cmd&quot;:&quot;git diff&quot;} → ok, 20 ms</details> |',
+      "| 10:00:05 |  |  | <b>→ group (guard: nudged)</b><br>&lt;b&gt;red&lt;/b&gt; |",
+      "| 10:00:06 |  |  | <b>@@group (guard: broadcast suppressed)</b> |",
+      "| 10:00:07 |  | <b>→ User</b><br>all green<br>shipping |  |",
+      "",
+    ]);
+
+    const html = renderTranscript(entries, "html", "r1");
+    expect(html).toContain("<tr><th>time</th><th>User</th><th>alice</th><th>bob</th></tr>");
+    expect(html).toContain("<td><details><summary>2 tool calls (1 failed)</summary>");
+    expect(html).not.toContain("<b>red</b>");
+  });
+
+  it("`org transcript` reads a run's file and writes Markdown or HTML", async () => {
+    const root = mkdtempSync(path.join(tmpdir(), "org-transcript-"));
+    try {
+      const run = path.join(root, ".org", "runs", "r1");
+      mkdirSync(run, { recursive: true });
+      const entry: TranscriptEntry = { type: "message", at: at(1), from: "User", to: "alice", kind: "dm", id: "m1", sha256: sha256("hi"), content: "hi" };
+      writeFileSync(path.join(run, "transcript.jsonl"), JSON.stringify(entry) + "\n{cut sh");
+
+      const out = path.join(root, "t.html");
+      expect(await transcriptCommand(["r1", "--out", out], root)).toBe(0);
+      expect(readFileSync(out, "utf8")).toContain("<b>→ alice</b><br>hi");
+      expect(await transcriptCommand(["r1", "--format=md", "--out", path.join(root, "t.md")], root)).toBe(0);
+      expect(readFileSync(path.join(root, "t.md"), "utf8")).toContain("| 10:00:01 | <b>→ alice</b><br>hi |");
+      expect(await transcriptCommand(["r2"], root)).toBe(1);
+      expect(await transcriptCommand(["r1", "--format", "pdf"], root)).toBe(2);
+      expect(await transcriptCommand(["..", "--out", out], root)).toBe(2);
+      expect(await transcriptCommand(["../../etc"], root)).toBe(2);
+    } finally {
+      rmSync(root, { recursive: true, force: true });
+    }
+  });
+});